@@ -0,0 +1,26 @@
+//! Decode every frame of a video as fast as possible and print the metrics summary.
+//!
+//! ```text
+//! cargo run --release --example decode_all -- video.mp4
+//! ```
+
+use anyhow::Context;
+use metric_video_player::{MetricsCollector, PlayerOptions, VideoFrame, VideoPlayer};
+use std::path::PathBuf;
+
+fn main() -> anyhow::Result<()> {
+    let path: PathBuf = std::env::args_os().nth(1).context("Usage: decode_all <video>")?.into();
+    
+    let mut player = VideoPlayer::new(&path, &PlayerOptions::default())?;
+    println!("{}: {}x{} {}", path.display(), player.get_width(), player.get_height(), player.get_codec_name());
+    
+    let mut metrics = MetricsCollector::default();
+    // One buffer for the whole run, like the benchmark mode
+    let mut frame = VideoFrame::default();
+    while player.next_frame_into(&mut frame)? {
+        metrics.record_frame(frame.frame_number, &frame);
+    }
+    
+    metrics.print_summary();
+    Ok(())
+}
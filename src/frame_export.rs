@@ -0,0 +1,162 @@
+use crate::{video_player::{format_timecode_ms, is_stdin, VideoFrame}, Args};
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, SyncSender, TrySendError};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+/// Frames waiting to be encoded before new ones are skipped instead of stalling playback
+const QUEUE_LENGTH: usize = 8;
+
+/// Image format for `--export-format`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum ExportFormat {
+    #[default]
+    Png,
+    Jpg,
+    Bmp,
+}
+
+impl ExportFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Png => "png",
+            ExportFormat::Jpg => "jpg",
+            ExportFormat::Bmp => "bmp",
+        }
+    }
+}
+
+/// Writes every Nth played frame to `dir/frame_<number>.<ext>`, encoding on a worker thread.
+pub struct FrameExporter {
+    dir: PathBuf,
+    every: u64,
+    submitted: u64,
+    skipped: u64,
+    sender: Option<SyncSender<VideoFrame>>,
+    worker: Option<JoinHandle<()>>,
+    written: Arc<AtomicU64>,
+    failed: Arc<AtomicBool>,
+}
+
+impl FrameExporter {
+    /// Exporter for `--export-frames`, or None when it wasn't given.
+    pub fn from_args(args: &Args) -> Result<Option<Self>> {
+        args.export_frames
+            .as_ref()
+            .map(|dir| Self::new(dir.clone(), args.export_every, args.export_format))
+            .transpose()
+    }
+    
+    pub fn new(dir: PathBuf, every: u64, format: ExportFormat) -> Result<Self> {
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create directory {:?}", dir))?;
+        
+        let (sender, receiver) = mpsc::sync_channel::<VideoFrame>(QUEUE_LENGTH);
+        let written = Arc::new(AtomicU64::new(0));
+        let failed = Arc::new(AtomicBool::new(false));
+        
+        let worker = std::thread::spawn({
+            let dir = dir.clone();
+            let written = written.clone();
+            let failed = failed.clone();
+            move || {
+                for frame in receiver {
+                    let path = dir.join(format!("frame_{:06}.{}", frame.frame_number, format.extension()));
+                    if let Err(e) = frame.save_image(&path) {
+                        // A full disk or vanished directory won't fix itself, stop instead of failing every frame
+                        log::warn!("Disabling frame export: {:#}", e);
+                        failed.store(true, Ordering::Relaxed);
+                        return;
+                    }
+                    written.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+        });
+        
+        log::info!("Exporting every {} frame(s) as {} to {:?}", every, format.extension(), dir);
+        Ok(Self {
+            dir,
+            every: every.max(1),
+            submitted: 0,
+            skipped: 0,
+            sender: Some(sender),
+            worker: Some(worker),
+            written,
+            failed,
+        })
+    }
+    
+    /// Queue the frame for writing if it's one of the exported ones.
+    pub fn submit(&mut self, frame: &VideoFrame) {
+        self.submitted += 1;
+        if (self.submitted - 1) % self.every != 0 || self.failed.load(Ordering::Relaxed) {
+            return;
+        }
+        
+        let Some(sender) = &self.sender else {
+            return;
+        };
+        match sender.try_send(frame.clone()) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => self.skipped += 1,
+            Err(TrySendError::Disconnected(_)) => self.sender = None,
+        }
+    }
+    
+    /// Wait for queued frames to be written and log how many made it.
+    pub fn finish(mut self) {
+        log::info!("{}", self.shut_down());
+    }
+    
+    fn shut_down(&mut self) -> String {
+        // Closing the channel ends the worker's loop once the queue is drained
+        self.sender = None;
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+        
+        let mut summary = format!("Exported {} frames to {}", self.written.load(Ordering::Relaxed), self.dir.display());
+        if self.skipped > 0 {
+            summary.push_str(&format!(" ({} skipped, encoding couldn't keep up)", self.skipped));
+        }
+        summary
+    }
+}
+
+impl Drop for FrameExporter {
+    fn drop(&mut self) {
+        if self.worker.is_some() {
+            log::info!("{}", self.shut_down());
+        }
+    }
+}
+
+/// Where a screenshot of `frame` from `source` goes: `dir/<video name>_<HH-MM-SS.mmm>.png`.
+pub fn screenshot_path(dir: &Path, source: &Path, frame: &VideoFrame) -> PathBuf {
+    let name = if is_stdin(source) {
+        "stdin".into()
+    } else {
+        source.file_stem().map_or_else(|| "frame".into(), |stem| stem.to_string_lossy())
+    };
+    // Colons aren't allowed in Windows file names
+    let timecode = format_timecode_ms(frame.timestamp).replace(':', "-");
+    dir.join(format!("{}_{}.png", name, timecode))
+}
+
+/// Encode `frame` as a PNG on a background thread so big frames don't stall the UI.
+/// Returns the path it will be written to and a handle to join for the outcome.
+pub fn save_screenshot(frame: VideoFrame, dir: &Path, source: &Path) -> (PathBuf, JoinHandle<Result<()>>) {
+    let path = screenshot_path(dir, source, &frame);
+    let handle = std::thread::spawn({
+        let dir = dir.to_path_buf();
+        let path = path.clone();
+        move || {
+            std::fs::create_dir_all(&dir)
+                .with_context(|| format!("Failed to create directory {:?}", dir))?;
+            frame.save_image(&path)
+        }
+    });
+    (path, handle)
+}
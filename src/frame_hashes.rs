@@ -0,0 +1,129 @@
+use crate::{video_player::{format_timecode_ms, FrameHash, VideoFrame}, Args, ReportFormat};
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::time::Duration;
+
+/// Mismatches listed individually by `finish`, the rest are only counted
+const MAX_REPORTED_MISMATCHES: usize = 10;
+
+/// Writes `--hash-frames` digests to `--hashes-file` and checks them against `--verify-hashes`.
+/// Both files hold one `frame_number,hash` line per frame.
+pub struct FrameHashes {
+    kind: FrameHash,
+    writer: Option<BufWriter<File>>,
+    expected: Option<HashMap<u64, String>>,
+    checked: u64,
+    missing: u64,
+    mismatches: Vec<(u64, Duration)>,
+}
+
+impl FrameHashes {
+    /// Recorder for `--hashes-file` and `--verify-hashes`, or None when neither was given.
+    pub fn from_args(args: &Args) -> Result<Option<Self>> {
+        if args.hashes_file.is_none() && args.verify_hashes.is_none() {
+            return Ok(None);
+        }
+        if args.hash_frames == FrameHash::None {
+            anyhow::bail!("--hashes-file and --verify-hashes need --hash-frames");
+        }
+        
+        let writer = args.hashes_file.as_ref()
+            .map(|path| {
+                File::create(path)
+                    .map(BufWriter::new)
+                    .with_context(|| format!("Failed to create hashes file {:?}", path))
+            })
+            .transpose()?;
+        let expected = args.verify_hashes.as_deref().map(read_hashes).transpose()?;
+        Ok(Some(Self {
+            kind: args.hash_frames,
+            writer,
+            expected,
+            checked: 0,
+            missing: 0,
+            mismatches: Vec::new(),
+        }))
+    }
+    
+    /// Write the frame's digest and compare it with the expected one.
+    pub fn record(&mut self, frame: &VideoFrame) -> Result<()> {
+        let Some(hash) = &frame.digest else {
+            return Ok(());
+        };
+        if let Some(writer) = &mut self.writer {
+            writeln!(writer, "{},{}", frame.frame_number, hash).context("Failed to write hashes file")?;
+        }
+        
+        let Some(expected) = &self.expected else {
+            return Ok(());
+        };
+        match expected.get(&frame.frame_number) {
+            // Every algorithm has its own digest length
+            Some(expected_hash) if expected_hash.len() != hash.len() => {
+                anyhow::bail!("The --verify-hashes file wasn't made with --hash-frames {}", self.kind.name());
+            }
+            Some(expected_hash) => {
+                self.checked += 1;
+                if !expected_hash.eq_ignore_ascii_case(hash) {
+                    self.mismatches.push((frame.frame_number, frame.timestamp));
+                }
+            }
+            None => self.missing += 1,
+        }
+        Ok(())
+    }
+    
+    /// Flush the hashes file and report the verification, failing if any frame differed.
+    /// The report goes to stderr with `--report-format json`, like the rest of the text output.
+    pub fn finish(mut self, args: &Args) -> Result<()> {
+        if let Some(mut writer) = self.writer.take() {
+            writer.flush().context("Failed to write hashes file")?;
+        }
+        if self.expected.is_none() {
+            return Ok(());
+        }
+        
+        let mut report = vec![format!("Frame hashes: {} checked, {} mismatched", self.checked, self.mismatches.len())];
+        if self.missing > 0 {
+            report.push(format!("  {} frames weren't in the hashes file", self.missing));
+        }
+        for (frame_number, timestamp) in self.mismatches.iter().take(MAX_REPORTED_MISMATCHES) {
+            report.push(format!("  Frame {} ({}) differs", frame_number, format_timecode_ms(*timestamp)));
+        }
+        if self.mismatches.len() > MAX_REPORTED_MISMATCHES {
+            report.push(format!("  ... and {} more", self.mismatches.len() - MAX_REPORTED_MISMATCHES));
+        }
+        for line in report {
+            if args.report_format == ReportFormat::Json {
+                eprintln!("{}", line);
+            } else {
+                println!("{}", line);
+            }
+        }
+        if !self.mismatches.is_empty() {
+            anyhow::bail!("{} frames don't match the recorded hashes", self.mismatches.len());
+        }
+        Ok(())
+    }
+}
+
+/// Parse a hashes file, skipping blank lines.
+fn read_hashes(path: &Path) -> Result<HashMap<u64, String>> {
+    let file = File::open(path).with_context(|| format!("Failed to open hashes file {:?}", path))?;
+    let mut hashes = HashMap::new();
+    for (index, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.with_context(|| format!("Failed to read hashes file {:?}", path))?;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let (frame_number, hash) = line.split_once(',')
+            .and_then(|(frame_number, hash)| Some((frame_number.trim().parse::<u64>().ok()?, hash.trim())))
+            .with_context(|| format!("{:?} line {}: expected frame_number,hash", path, index + 1))?;
+        hashes.insert(frame_number, hash.to_string());
+    }
+    Ok(hashes)
+}
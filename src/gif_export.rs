@@ -0,0 +1,92 @@
+use crate::{video_player::{FrameHash, OutputFormat, PlayerOptions, VideoPlayer}, Args};
+use anyhow::{Context, Result};
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{Delay, Frame, RgbaImage};
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+use std::time::Duration;
+
+/// Frames a GIF is cut off at, beyond which files get too large to attach anywhere
+const MAX_GIF_FRAMES: u64 = 300;
+
+/// Palette quantization speed from 1 (best) to 30 (fastest); 10 looks the same on video at a fraction of the time
+const QUANTIZE_SPEED: i32 = 10;
+
+/// Write `--from`..`--to` of `video` as a looping GIF at `--gif-fps`, scaled to `--gif-width`.
+pub fn write_gif(video: &Path, out: &Path, args: &Args) -> Result<()> {
+    let fps = args.gif_fps.max(1);
+    let from = args.from.unwrap_or(Duration::ZERO);
+    let longest = Duration::from_secs_f64(MAX_GIF_FRAMES as f64 / fps as f64);
+    let to = match args.to {
+        Some(to) if to <= from => anyhow::bail!("--to {:.3}s must be after --from {:.3}s", to.as_secs_f64(), from.as_secs_f64()),
+        Some(to) if to - from > longest => {
+            log::warn!("{:.1}s at {} FPS is over {} frames, only the first {:.1}s are exported",
+                (to - from).as_secs_f64(), fps, MAX_GIF_FRAMES, longest.as_secs_f64());
+            from + longest
+        }
+        Some(to) => to,
+        None => from + longest,
+    };
+    
+    // The player seeks to --from and stops at --to by itself
+    let options = PlayerOptions {
+        target_fps: 0,
+        output_width: args.gif_width,
+        output_height: None,
+        scale_factor: None,
+        output_format: OutputFormat::Rgba,
+        yuv_direct: false,
+        probe: false,
+        start: Some(from),
+        end: Some(to),
+        hash_frames: false,
+        frame_hash: FrameHash::None,
+        ..args.player_options()
+    };
+    let mut player = VideoPlayer::new(video, &options)?;
+    
+    let file = File::create(out).with_context(|| format!("Failed to create {:?}", out))?;
+    let mut encoder = GifEncoder::new_with_speed(BufWriter::new(file), QUANTIZE_SPEED);
+    encoder.set_repeat(Repeat::Infinite)?;
+    
+    // Keep the first frame in each tick of the GIF's clock and show it until the next kept one,
+    // so sources slower than --gif-fps keep their timing too
+    let tick = |timestamp: Duration| (timestamp.saturating_sub(from).as_secs_f64() * fps as f64) as u64;
+    let mut pending: Option<(RgbaImage, u64)> = None;
+    let mut written = 0;
+    while written < MAX_GIF_FRAMES - 1 {
+        let Some(frame) = player.next_frame()? else {
+            break;
+        };
+        let frame_tick = tick(frame.timestamp);
+        if pending.as_ref().is_some_and(|(_, pending_tick)| frame_tick <= *pending_tick) {
+            continue;
+        }
+        
+        let image = RgbaImage::from_raw(frame.width, frame.height, frame.data)
+            .context("Decoded frame is smaller than its size says")?;
+        if let Some((previous, previous_tick)) = pending.replace((image, frame_tick)) {
+            encode(&mut encoder, previous, frame_tick - previous_tick, fps).with_context(|| format!("Failed to write {:?}", out))?;
+            written += 1;
+        }
+    }
+    if let Some((last, _)) = pending {
+        encode(&mut encoder, last, 1, fps).with_context(|| format!("Failed to write {:?}", out))?;
+        written += 1;
+    }
+    drop(encoder);
+    
+    if written == 0 {
+        anyhow::bail!("No frames between {:.3}s and {:.3}s", from.as_secs_f64(), to.as_secs_f64());
+    }
+    let (width, height) = player.get_output_size();
+    println!("Wrote {} frames ({}x{}, {} FPS) to {:?}", written, width, height, fps, out);
+    Ok(())
+}
+
+/// Add `image` to the GIF, shown for `ticks` frames at `fps`.
+fn encode(encoder: &mut GifEncoder<BufWriter<File>>, image: RgbaImage, ticks: u64, fps: u32) -> image::ImageResult<()> {
+    let delay = Delay::from_numer_denom_ms(1000 * ticks as u32, fps);
+    encoder.encode_frame(Frame::from_parts(image, 0, 0, delay))
+}
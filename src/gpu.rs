@@ -0,0 +1,121 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+
+/// How often the GPU is queried (4 Hz); NVML averages utilization over its own sample period anyway
+#[cfg(feature = "gpu-metrics")]
+const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(250);
+
+/// One reading of the GPU. Everything but `process_memory_mb` is device-wide.
+#[derive(Debug, Clone, Copy)]
+pub struct GpuSample {
+    pub utilization_percent: f64,
+    /// Load on the video decode engine (NVDEC), which `--hwaccel cuda` uses
+    pub decoder_percent: f64,
+    pub memory_used_mb: f64,
+    /// VRAM held by this process, None when the driver doesn't report it (e.g. Windows WDDM)
+    pub process_memory_mb: Option<f64>,
+}
+
+/// Polls the first NVIDIA GPU through NVML on a background thread until dropped.
+pub struct GpuMonitor {
+    latest: Arc<Mutex<GpuSample>>,
+    shutdown: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl GpuMonitor {
+    /// Start polling, or None when built without the `gpu-metrics` feature or no GPU/driver answers.
+    #[cfg(feature = "gpu-metrics")]
+    pub fn start() -> Option<Self> {
+        // Loads libnvidia-ml at runtime, so the binary still starts on machines without the driver
+        let nvml = match nvml_wrapper::Nvml::init() {
+            Ok(nvml) => nvml,
+            Err(e) => {
+                log::debug!("GPU metrics unavailable, NVML failed to load: {}", e);
+                return None;
+            }
+        };
+        let first = match query(&nvml) {
+            Ok(sample) => sample,
+            Err(e) => {
+                log::debug!("GPU metrics unavailable: {:#}", e);
+                return None;
+            }
+        };
+        
+        let latest = Arc::new(Mutex::new(first));
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let handle = std::thread::spawn({
+            let latest = latest.clone();
+            let shutdown = shutdown.clone();
+            move || {
+                while !shutdown.load(Ordering::Relaxed) {
+                    // Unparked early by drop
+                    std::thread::park_timeout(POLL_INTERVAL);
+                    match query(&nvml) {
+                        Ok(sample) => {
+                            if let Ok(mut latest) = latest.lock() {
+                                *latest = sample;
+                            }
+                        }
+                        Err(e) => log::debug!("GPU query failed: {:#}", e),
+                    }
+                }
+            }
+        });
+        
+        Some(Self { latest, shutdown, handle: Some(handle) })
+    }
+    
+    #[cfg(not(feature = "gpu-metrics"))]
+    pub fn start() -> Option<Self> {
+        None
+    }
+    
+    /// The most recent reading, at most `POLL_INTERVAL` old.
+    pub fn latest(&self) -> Option<GpuSample> {
+        self.latest.lock().ok().map(|latest| *latest)
+    }
+}
+
+impl Drop for GpuMonitor {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            handle.thread().unpark();
+            let _ = handle.join();
+        }
+    }
+}
+
+#[cfg(feature = "gpu-metrics")]
+fn query(nvml: &nvml_wrapper::Nvml) -> anyhow::Result<GpuSample> {
+    use nvml_wrapper::enums::device::UsedGpuMemory;
+    
+    let device = nvml.device_by_index(0)?;
+    let utilization = device.utilization_rates()?;
+    let decoder = device.decoder_utilization()?;
+    let memory = device.memory_info()?;
+    
+    // Not listed means nothing allocated yet, e.g. software decoding. A process using both CUDA
+    // and a graphics context is listed twice with the same allocation, hence max rather than sum.
+    let pid = std::process::id();
+    let mut process_memory = Some(0);
+    for process in device.running_compute_processes()?.into_iter().chain(device.running_graphics_processes()?) {
+        if process.pid == pid {
+            process_memory = match (process_memory, process.used_gpu_memory) {
+                (Some(total), UsedGpuMemory::Used(bytes)) => Some(total.max(bytes)),
+                _ => None,
+            };
+        }
+    }
+    
+    const MB: f64 = 1024.0 * 1024.0;
+    Ok(GpuSample {
+        utilization_percent: utilization.gpu as f64,
+        decoder_percent: decoder.utilization as f64,
+        memory_used_mb: memory.used as f64 / MB,
+        process_memory_mb: process_memory.map(|bytes| bytes as f64 / MB),
+    })
+}
@@ -1,35 +1,125 @@
-use crate::{video_player::VideoPlayer, metrics::MetricsCollector, Args};
+use crate::{video_player::{format_timecode, format_timecode_ms, format_timestamp, OutputFormat, PlayerOptions, VideoFrame, VideoPlayer, VIDEO_EXTENSIONS}, frame_export::{self, FrameExporter}, metrics::MetricsCollector, playlist::Playlist, thumbnails::Thumbnails, window_title::{PlaybackState, WindowTitle}, Args};
 use eframe::egui;
-use std::time::Instant;
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// How far the arrow keys seek, in seconds
+const SEEK_STEP_SECS: f64 = 5.0;
+
+/// How much +/- change the target FPS
+const FPS_STEP: u32 = 5;
+
+/// Upper end of the target FPS slider, beyond which there's "Unlimited"
+const MAX_TARGET_FPS: u32 = 240;
+
+/// Shortcuts listed under Help → Keyboard Shortcuts
+const KEYBOARD_SHORTCUTS: &[(&str, &str)] = &[
+    ("Space", "Play / pause"),
+    ("← / →", "Seek -5s / +5s"),
+    (", / .", "Step one frame back / forward while paused"),
+    ("+ / -", "Raise / lower the target FPS"),
+    ("M", "Show / hide the metrics window"),
+    ("O", "Show / hide the on-video stats"),
+    ("S", "Save a screenshot"),
+    ("F / F11", "Toggle fullscreen"),
+];
+
+/// Choices offered in the speed selector
+const PLAYBACK_SPEEDS: [f32; 6] = [0.25, 0.5, 1.0, 1.5, 2.0, 4.0];
+
+/// How long confirmation messages stay visible
+const STATUS_MESSAGE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Most recent frames shown in the frame time plot
+const FRAME_TIME_PLOT_SAMPLES: usize = 300;
+
+/// Seek bar ticks and plot markers for `--detect-scenes` cuts
+const SCENE_CHANGE_COLOR: egui::Color32 = egui::Color32::from_rgb(240, 200, 40);
 
 pub struct MetricVideoPlayerApp {
     player: VideoPlayer,
     metrics: MetricsCollector,
+    frame_exporter: Option<FrameExporter>,
     args: Args,
+    playlist: Playlist,
+    window_title: WindowTitle,
     
     // GUI state
     is_playing: bool,
     frame_texture: Option<egui::TextureHandle>,
+    last_frame: Option<VideoFrame>,
+    /// Previously shown frame, recycled as the next decode target
+    spare_frame: Option<VideoFrame>,
+    /// Frames decoded ahead of time, up to `--prefetch`, presented in order
+    prefetched: VecDeque<VideoFrame>,
     last_frame_time: Option<Instant>,
+    /// Total texture update time, to compare against the SDL2 upload cost
+    upload_time: Duration,
+    uploads: u32,
     
     // Control state
-    target_fps_input: String,
     show_metrics_window: bool,
     show_advanced_metrics: bool,
+    plot_pacing_error: bool, // Plot deviation from the ideal interval instead of frame time
+    show_osd: bool,
+    show_shortcuts: bool,
+    record_stepped_frames: bool,
+    loop_playback: bool,
+    
+    // Seek bar state
+    seek_position: Option<f64>,
+    resume_after_seek: bool,
+    thumbnails: Option<Thumbnails>, // Opened on the first hover
+    
+    reset_metrics_on_open: bool,
+    error_message: Option<String>,
+    status_message: Option<(String, Instant)>,
+    /// Screenshots still being encoded, with where they're going
+    pending_screenshots: Vec<(PathBuf, JoinHandle<anyhow::Result<()>>)>,
+    playback_finished: bool,
 }
 
 impl MetricVideoPlayerApp {
-    pub fn new(player: VideoPlayer, metrics: MetricsCollector, args: Args) -> Self {
+    pub fn new(mut player: VideoPlayer, metrics: MetricsCollector, args: Args, playlist: Playlist) -> Self {
+        player.set_output_format(display_format(&args));
+        
+        let frame_exporter = FrameExporter::from_args(&args).unwrap_or_else(|e| {
+            log::error!("Frame export disabled: {:#}", e);
+            None
+        });
+        
         Self {
-            target_fps_input: args.target_fps.to_string(),
+            loop_playback: args.loop_playback,
+            show_osd: args.overlay,
+            show_shortcuts: false,
             player,
             metrics,
+            frame_exporter,
             args,
+            playlist,
+            window_title: WindowTitle::default(),
             is_playing: true, // Start playing automatically
             frame_texture: None,
+            last_frame: None,
+            spare_frame: None,
+            prefetched: VecDeque::new(),
             last_frame_time: None,
+            upload_time: Duration::ZERO,
+            uploads: 0,
             show_metrics_window: true,
             show_advanced_metrics: false,
+            plot_pacing_error: false,
+            record_stepped_frames: false,
+            seek_position: None,
+            resume_after_seek: false,
+            thumbnails: None,
+            reset_metrics_on_open: true,
+            error_message: None,
+            status_message: None,
+            pending_screenshots: Vec::new(),
+            playback_finished: false,
         }
     }
     
@@ -43,12 +133,18 @@ impl MetricVideoPlayerApp {
         
         // Check if it's time for the next frame
         let should_advance = if let Some(last_time) = self.last_frame_time {
-            let target_interval = if self.args.target_fps > 0 {
-                std::time::Duration::from_nanos(1_000_000_000 / self.args.target_fps as u64)
+            let target_fps = self.player.get_target_fps();
+            let target_interval = if target_fps > 0 {
+                std::time::Duration::from_nanos(1_000_000_000 / target_fps as u64)
             } else {
-                std::time::Duration::from_millis(33) // ~30 FPS default
+                Duration::ZERO // Unlimited, a new frame every repaint
             };
+            let target_interval = target_interval.div_f32(self.player.get_playback_speed());
             
+            // Until the frame is due, decode ahead so a slow frame later doesn't delay a present
+            if last_time.elapsed() < target_interval {
+                self.prefetch(last_time + target_interval);
+            }
             last_time.elapsed() >= target_interval
         } else {
             true // Always advance the first frame
@@ -56,67 +152,486 @@ impl MetricVideoPlayerApp {
         
         if should_advance {
             log::debug!("Advancing to next frame...");
-            if let Ok(Some(frame)) = self.player.next_frame() {
+            let next = if self.args.realtime && self.player.get_target_fps() > 0 {
+                self.decode_realtime()
+            } else {
+                self.next_frame()
+            };
+            if let Ok(Some(frame)) = next {
                 log::debug!("Got frame {}: {}x{}", frame.frame_number, frame.width, frame.height);
-                self.metrics.record_frame(frame.frame_number, &frame);
-                
-                // Save first frame to disk for debugging
-                if frame.frame_number == 1 {
-                    if let Err(e) = image::save_buffer(
-                        "debug_frame_1.png",
-                        &frame.data,
-                        frame.width,
-                        frame.height,
-                        image::ColorType::Rgb8,
-                    ) {
-                        log::error!("Failed to save debug frame: {}", e);
-                    } else {
-                        log::info!("Saved debug frame to debug_frame_1.png");
-                    }
+                if let Some(scene) = self.metrics.record_frame(frame.frame_number, &frame) {
+                    log::info!("{}", scene);
+                }
+                if let Some(exporter) = &mut self.frame_exporter {
+                    exporter.submit(&frame);
                 }
                 
-                // Convert frame data to texture
-                let color_image = egui::ColorImage::from_rgb(
-                    [frame.width as usize, frame.height as usize],
-                    &frame.data,
-                );
-                
-                log::debug!("Creating texture from {}x{} image with {} bytes", 
-                    frame.width, frame.height, frame.data.len());
-                log::debug!("ColorImage size: {:?}", color_image.size);
-                
-                // Create texture with explicit options
-                let texture_options = egui::TextureOptions {
-                    magnification: egui::TextureFilter::Linear,
-                    minification: egui::TextureFilter::Linear,
-                    wrap_mode: egui::TextureWrapMode::ClampToEdge,
-                };
-                
-                // Always use the same texture name so it gets updated, not recreated
-                self.frame_texture = Some(ctx.load_texture(
-                    "video_frame",
-                    color_image,
-                    texture_options,
-                ));
-                
-                log::debug!("Texture created successfully");
-                log::debug!("Texture handle ID: {:?}", self.frame_texture.as_ref().unwrap().id());
+                self.show_frame(ctx, frame);
                 
                 self.last_frame_time = Some(Instant::now());
+            } else if self.playlist.len() > 1 && self.switch_video(true, self.loop_playback) {
+                log::info!("End of video, moving to the next one");
+            } else if self.loop_playback && self.player.is_seekable() {
+                log::info!("End of video, restarting");
+                if let Err(e) = self.player.restart() {
+                    log::error!("Failed to restart video: {}", e);
+                    self.is_playing = false;
+                    self.playback_finished = true;
+                } else if self.args.reset_metrics_per_loop {
+                    self.metrics.reset();
+                } else {
+                    self.metrics.mark_discontinuity();
+                }
             } else {
                 // End of video
                 self.is_playing = false;
+                self.playback_finished = true;
+                self.metrics.pause();
                 log::info!("Video playback completed");
+                if self.uploads > 0 {
+                    log::info!("Texture upload: {:.3} ms/frame", self.upload_time.as_secs_f64() * 1000.0 / self.uploads as f64);
+                }
+            }
+        }
+    }
+    
+    /// The next frame in order: the oldest prefetched one, or a fresh decode when none are queued.
+    fn next_frame(&mut self) -> anyhow::Result<Option<VideoFrame>> {
+        match self.prefetched.pop_front() {
+            Some(frame) => Ok(Some(frame)),
+            None => self.decode_next(),
+        }
+    }
+    
+    /// Decode frames into the prefetch queue until it holds `--prefetch` frames or `deadline` passes.
+    /// `--realtime` decodes on its own schedule, so it isn't prefetched.
+    fn prefetch(&mut self, deadline: Instant) {
+        if self.args.realtime {
+            return;
+        }
+        while self.prefetched.len() < self.args.prefetch && Instant::now() < deadline {
+            match self.decode_next() {
+                Ok(Some(frame)) => self.prefetched.push_back(frame),
+                // The end of the video is left for `next_frame` to run into
+                Ok(None) => break,
+                Err(e) => {
+                    log::warn!("Prefetching stopped: {:#}", e);
+                    break;
+                }
+            }
+        }
+    }
+    
+    /// Number of the frame on screen, which the player is ahead of by the prefetched frames.
+    fn shown_frame_number(&self) -> u64 {
+        self.last_frame.as_ref().map_or_else(|| self.player.get_current_frame(), |frame| frame.frame_number)
+    }
+    
+    /// Timestamp of the frame on screen, which the player's position is ahead of by the prefetched frames.
+    fn shown_position(&self) -> Duration {
+        self.last_frame.as_ref().map_or_else(|| self.player.get_position(), |frame| frame.timestamp)
+    }
+    
+    /// Drop prefetched frames after a seek or a video change, so stale frames aren't shown.
+    fn flush_prefetched(&mut self) {
+        if let Some(frame) = self.prefetched.pop_front() {
+            self.spare_frame.get_or_insert(frame);
+        }
+        self.prefetched.clear();
+    }
+    
+    /// Decode the next frame into a recycled buffer.
+    fn decode_next(&mut self) -> anyhow::Result<Option<VideoFrame>> {
+        let mut frame = self.spare_frame.take().unwrap_or_default();
+        let decoded = self.player.next_frame_into(&mut frame)?;
+        self.metrics.record_decode_errors(self.player.take_decode_errors());
+        self.metrics.record_corrupt_frames(self.player.take_corrupt_frames());
+        if decoded {
+            Ok(Some(frame))
+        } else {
+            self.spare_frame = Some(frame);
+            Ok(None)
+        }
+    }
+    
+    /// Decode the frame due on the `--realtime` schedule, dropping any that are already late.
+    fn decode_realtime(&mut self) -> anyhow::Result<Option<VideoFrame>> {
+        let target_interval = Duration::from_nanos(1_000_000_000 / self.player.get_target_fps() as u64);
+        let frame = self.player.next_frame_realtime(target_interval)?;
+        self.metrics.record_decode_errors(self.player.take_decode_errors());
+        self.metrics.record_corrupt_frames(self.player.take_corrupt_frames());
+        self.metrics.record_dropped_frames(self.player.take_dropped_frames());
+        if let Some(error) = self.player.take_pacing_error() {
+            self.metrics.record_pacing_error(error);
+        }
+        Ok(frame)
+    }
+    
+    fn show_frame(&mut self, ctx: &egui::Context, frame: VideoFrame) {
+        let upload_started = Instant::now();
+        
+        // Convert frame data to texture. Video is opaque, so the RGBA data is already premultiplied
+        let size = [frame.width as usize, frame.height as usize];
+        let color_image = match frame.format {
+            OutputFormat::Rgba => egui::ColorImage::from_rgba_premultiplied(size, &frame.data),
+            OutputFormat::Rgb24 => egui::ColorImage::from_rgb(size, &frame.data),
+            OutputFormat::Gray8 => egui::ColorImage::from_gray(size, &frame.data),
+            OutputFormat::Yuv420p => {
+                log::warn!("egui can't display {} frames", frame.format.name());
+                return;
+            }
+        };
+        
+        let texture_options = egui::TextureOptions {
+            magnification: egui::TextureFilter::Linear,
+            minification: egui::TextureFilter::Linear,
+            wrap_mode: egui::TextureWrapMode::ClampToEdge,
+        };
+        
+        // Update the existing texture in place; a new one is only needed when the size changes
+        match &mut self.frame_texture {
+            Some(texture) if texture.size() == size => texture.set(color_image, texture_options),
+            _ => {
+                log::debug!("Creating {}x{} video texture", frame.width, frame.height);
+                self.frame_texture = Some(ctx.load_texture("video_frame", color_image, texture_options));
+            }
+        }
+        self.upload_time += upload_started.elapsed();
+        self.uploads += 1;
+        
+        // Keep the pixels around for screenshots, recycling the previous frame's buffer
+        self.spare_frame = self.last_frame.replace(frame);
+    }
+    
+    fn save_screenshot(&mut self) {
+        let Some(frame) = &self.last_frame else {
+            log::warn!("No frame decoded yet, nothing to save");
+            return;
+        };
+        
+        let screenshot = frame_export::save_screenshot(frame.clone(), &self.args.screenshot_dir, self.player.get_source());
+        self.pending_screenshots.push(screenshot);
+    }
+    
+    /// Report screenshots whose encoding has finished.
+    fn check_screenshots(&mut self) {
+        let (finished, pending) = std::mem::take(&mut self.pending_screenshots)
+            .into_iter()
+            .partition(|(_, handle)| handle.is_finished());
+        self.pending_screenshots = pending;
+        
+        for (path, handle) in finished {
+            match handle.join().unwrap_or_else(|_| Err(anyhow::anyhow!("Screenshot thread panicked"))) {
+                Ok(()) => {
+                    log::info!("Saved screenshot to {:?}", path);
+                    self.status_message = Some((format!("Screenshot saved to {}", path.display()), Instant::now()));
+                }
+                Err(e) => {
+                    log::error!("Failed to save screenshot: {:#}", e);
+                    self.error_message = Some(format!("Failed to save screenshot:\n{:#}", e));
+                }
+            }
+        }
+    }
+    
+    fn open_file_dialog(&mut self) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("Video", VIDEO_EXTENSIONS)
+            .add_filter("All files", &["*"])
+            .pick_file()
+        else {
+            return;
+        };
+        
+        self.open_file(path);
+    }
+    
+    /// Move to the next or previous playlist video, returning false if there's none.
+    fn switch_video(&mut self, forward: bool, wrap: bool) -> bool {
+        let Some(player) = self.playlist.advance(forward, wrap, &self.args, &mut self.metrics) else {
+            return false;
+        };
+        self.set_player(player);
+        self.args.video_path = self.playlist.current().to_path_buf();
+        true
+    }
+    
+    /// Start playing `player` in place of the current video.
+    fn set_player(&mut self, mut player: VideoPlayer) {
+        player.set_output_format(display_format(&self.args));
+        player.set_playback_speed(self.player.get_playback_speed());
+        self.metrics.set_video_info(player.info().clone());
+        self.player = player;
+        self.flush_prefetched();
+        self.frame_texture = None;
+        self.last_frame = None;
+        self.last_frame_time = None;
+        self.seek_position = None;
+        self.thumbnails = None;
+        self.is_playing = true;
+        self.playback_finished = false;
+        self.metrics.resume();
+    }
+    
+    /// Replace the current video with `path`, keeping the old one if it can't be opened.
+    fn open_file(&mut self, path: PathBuf) {
+        log::info!("Opening video file: {:?}", path);
+        
+        let options = PlayerOptions {
+            output_format: display_format(&self.args),
+            ..self.args.player_options()
+        };
+        match VideoPlayer::new(&path, &options) {
+            Ok(player) => {
+                self.set_player(player);
+                self.args.video_path = path;
+                
+                if self.reset_metrics_on_open {
+                    self.metrics.reset();
+                }
+            }
+            Err(e) => {
+                log::error!("Failed to open {:?}: {:#}", path, e);
+                self.error_message = Some(format!("Failed to open {}:\n{:#}", path.display(), e));
+            }
+        }
+    }
+    
+    fn handle_keyboard(&mut self, ctx: &egui::Context) {
+        // Don't steal keys from text fields (e.g. typing a value into the Target FPS slider)
+        if ctx.wants_keyboard_input() {
+            return;
+        }
+        
+        let pressed = |key| ctx.input(|i| i.key_pressed(key));
+        
+        if pressed(egui::Key::Space) {
+            self.toggle_playback();
+        }
+        
+        let seek_back = pressed(egui::Key::ArrowLeft);
+        let seek_forward = pressed(egui::Key::ArrowRight);
+        if seek_back || seek_forward {
+            let position = self.shown_position().as_secs_f64();
+            let target = if seek_forward { position + SEEK_STEP_SECS } else { position - SEEK_STEP_SECS };
+            self.seek_preview(ctx, target);
+        }
+        
+        let step_back = pressed(egui::Key::Comma);
+        let step_forward = pressed(egui::Key::Period);
+        if !self.is_playing && (step_back || step_forward) {
+            self.step_frame(ctx, step_forward);
+        }
+        
+        if pressed(egui::Key::S) {
+            self.save_screenshot();
+        }
+        
+        if pressed(egui::Key::O) {
+            self.show_osd = !self.show_osd;
+        }
+        
+        if pressed(egui::Key::M) {
+            self.show_metrics_window = !self.show_metrics_window;
+        }
+        
+        if pressed(egui::Key::Plus) || pressed(egui::Key::Equals) {
+            self.nudge_target_fps(true);
+        }
+        if pressed(egui::Key::Minus) {
+            self.nudge_target_fps(false);
+        }
+        
+        if pressed(egui::Key::F) || pressed(egui::Key::F11) {
+            let fullscreen = ctx.input(|i| i.viewport().fullscreen.unwrap_or(false));
+            ctx.send_viewport_cmd(egui::ViewportCommand::Fullscreen(!fullscreen));
+        }
+    }
+    
+    fn toggle_playback(&mut self) {
+        self.is_playing = !self.is_playing;
+        if self.is_playing {
+            self.last_frame_time = Some(Instant::now());
+            self.player.reset_realtime_clock();
+            self.metrics.resume();
+        } else {
+            self.metrics.pause();
+        }
+    }
+    
+    /// Raise or lower the target FPS by `FPS_STEP`, starting from the video's rate when unlimited.
+    fn nudge_target_fps(&mut self, up: bool) {
+        let current = match self.player.get_target_fps() {
+            0 => self.player.get_native_fps().round() as u32,
+            fps => fps,
+        };
+        let fps = if up { current + FPS_STEP } else { current.saturating_sub(FPS_STEP) };
+        self.set_target_fps(fps.clamp(1, MAX_TARGET_FPS));
+    }
+    
+    /// Change the pacing of the current video, and of videos opened later.
+    fn set_target_fps(&mut self, fps: u32) {
+        self.args.target_fps = fps;
+        self.player.set_target_fps(fps);
+    }
+    
+    fn osd_text(&mut self) -> String {
+        format!(
+            "FPS: {:.1}\nAvg FPS: {:.1}\nFrame: {}\nMemory: {:.1} MB",
+            self.metrics.get_current_fps(),
+            self.metrics.get_average_fps(),
+            self.shown_frame_number(),
+            self.metrics.get_current_memory_mb()
+        )
+    }
+    
+    /// Show the next or previous frame while paused. Stepping back from the first frame is a no-op.
+    fn step_frame(&mut self, ctx: &egui::Context, forward: bool) {
+        if !forward {
+            // Frame numbers are 1-based while seek_to_frame takes a 0-based index,
+            // so the previous frame of frame N is index N - 2
+            let current = self.shown_frame_number();
+            if current < 2 {
+                return;
+            }
+            
+            if let Err(e) = self.player.seek_to_frame(current - 2) {
+                log::error!("Failed to seek: {}", e);
+                return;
+            }
+            self.flush_prefetched();
+        }
+        
+        match self.next_frame() {
+            Ok(Some(frame)) => {
+                // Manual steps would skew FPS stats, so they're only recorded on request
+                if self.record_stepped_frames {
+                    if let Some(scene) = self.metrics.record_frame(frame.frame_number, &frame) {
+                        log::info!("{}", scene);
+                    }
+                }
+                self.show_frame(ctx, frame);
             }
+            Ok(None) => log::debug!("No frame to step to"),
+            Err(e) => log::error!("Failed to decode frame: {}", e),
+        }
+    }
+    
+    fn export_metrics_dialog(&mut self) {
+        let default_name = format!("metrics_{}.json", chrono::Local::now().format("%Y-%m-%d_%H%M"));
+        let Some(path) = rfd::FileDialog::new()
+            .set_file_name(default_name.as_str())
+            .add_filter("JSON", &["json"])
+            .add_filter("CSV", &["csv"])
+            .add_filter("SQLite", &["db"])
+            .save_file()
+        else {
+            return;
+        };
+        
+        // A name ending in .trace.json gets a Chrome trace
+        match self.metrics.export_to_file(&path, None) {
+            Ok(()) => {
+                log::info!("Metrics exported to: {:?}", path);
+                self.status_message = Some((format!("Metrics exported to {}", path.display()), Instant::now()));
+            }
+            Err(e) => {
+                log::error!("Failed to export metrics: {:#}", e);
+                self.error_message = Some(format!("Failed to export metrics to {}:\n{:#}", path.display(), e));
+            }
+        }
+    }
+    
+    /// Mark detected cuts on the seek bar; clicking a tick seeks to the cut.
+    fn scene_ticks(&mut self, ui: &egui::Ui, ctx: &egui::Context, seek_bar: egui::Rect, range: std::ops::RangeInclusive<f64>) {
+        let span = range.end() - range.start();
+        if span <= 0.0 {
+            return;
+        }
+        let (left, width) = seek_bar_rail(seek_bar);
+        
+        let mut clicked = None;
+        for (index, scene) in self.metrics.scene_changes().iter().enumerate() {
+            if !range.contains(&scene.timestamp) {
+                continue;
+            }
+            let x = left + ((scene.timestamp - range.start()) / span) as f32 * width;
+            let tick = egui::Rect::from_center_size(egui::pos2(x, seek_bar.center().y), egui::vec2(5.0, seek_bar.height()));
+            let response = ui
+                .interact(tick, ui.id().with(("scene_tick", index)), egui::Sense::click())
+                .on_hover_text(format!("Scene change at {}", format_timecode_ms(Duration::from_secs_f64(scene.timestamp))));
+            let stroke = egui::Stroke::new(if response.hovered() { 3.0 } else { 2.0 }, SCENE_CHANGE_COLOR);
+            ui.painter().line_segment([egui::pos2(x, tick.top()), egui::pos2(x, tick.bottom())], stroke);
+            if response.clicked() {
+                clicked = Some(scene.timestamp);
+            }
+        }
+        if let Some(timestamp) = clicked {
+            self.seek_preview(ctx, timestamp);
+        }
+    }
+    
+    /// Seek to `position` (seconds) and display the frame found there without recording metrics.
+    fn seek_preview(&mut self, ctx: &egui::Context, position: f64) {
+        if let Err(e) = self.player.seek_to_time(Duration::from_secs_f64(position.max(0.0))) {
+            log::error!("Failed to seek: {}", e);
+            return;
+        }
+        self.flush_prefetched();
+        self.metrics.mark_discontinuity();
+        
+        match self.decode_next() {
+            Ok(Some(frame)) => self.show_frame(ctx, frame),
+            Ok(None) => log::debug!("No frame at seek position {:.2}s", position),
+            Err(e) => log::error!("Failed to decode frame after seek: {}", e),
         }
     }
 }
 
 impl eframe::App for MetricVideoPlayerApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.ui(ctx);
+    }
+    
+    /// Closing the window ends the session, so that's when `--export-metrics` is written.
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        self.metrics.print_summary();
+        if let Some(export_path) = &self.args.export_metrics {
+            log::info!("Exporting metrics to: {:?}", export_path);
+            if let Err(e) = self.metrics.export_to_file(export_path, self.args.metrics_format) {
+                log::error!("Failed to export metrics: {:#}", e);
+            }
+        }
+    }
+}
+
+impl MetricVideoPlayerApp {
+    pub fn is_finished(&self) -> bool {
+        self.playback_finished
+    }
+    
+    pub fn into_metrics(self) -> MetricsCollector {
+        self.metrics
+    }
+    
+    fn ui(&mut self, ctx: &egui::Context) {
         // ALWAYS request repaint for continuous updates
         ctx.request_repaint();
         
+        self.handle_keyboard(ctx);
+        self.check_screenshots();
+        
+        let state = if self.is_playing {
+            PlaybackState::Playing
+        } else if self.playback_finished {
+            PlaybackState::Finished
+        } else {
+            PlaybackState::Paused
+        };
+        if let Some(title) = self.window_title.update(&self.player, &self.playlist, self.shown_position(), self.metrics.get_current_fps(), state) {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Title(title.to_string()));
+        }
+        
         // Update video frame
         self.update_frame(ctx);
         
@@ -124,14 +639,19 @@ impl eframe::App for MetricVideoPlayerApp {
         egui::TopBottomPanel::top("menu_bar").show(ctx, |ui| {
             egui::menu::bar(ui, |ui| {
                 ui.menu_button("File", |ui| {
-                    if ui.button("Export Metrics").clicked() {
-                        // TODO: Implement file dialog for export
-                        if let Some(export_path) = &self.args.export_metrics {
-                            if let Err(e) = self.metrics.export_to_file(export_path) {
-                                log::error!("Failed to export metrics: {}", e);
-                            }
-                        }
+                    if ui.button("Open…").clicked() {
+                        ui.close_menu();
+                        self.open_file_dialog();
+                    }
+                    ui.checkbox(&mut self.reset_metrics_on_open, "Reset metrics on open");
+                    ui.separator();
+                    if ui.button("Export Metrics…").clicked() {
                         ui.close_menu();
+                        self.export_metrics_dialog();
+                    }
+                    if ui.button("Save Screenshot (S)").clicked() {
+                        ui.close_menu();
+                        self.save_screenshot();
                     }
                     if ui.button("Quit").clicked() {
                         ctx.send_viewport_cmd(egui::ViewportCommand::Close);
@@ -139,52 +659,39 @@ impl eframe::App for MetricVideoPlayerApp {
                 });
                 
                 ui.menu_button("View", |ui| {
-                    ui.checkbox(&mut self.show_metrics_window, "Show Metrics");
+                    ui.checkbox(&mut self.show_metrics_window, "Show Metrics (M)");
                     ui.checkbox(&mut self.show_advanced_metrics, "Advanced Metrics");
+                    ui.checkbox(&mut self.show_osd, "On-Video Stats (O)");
+                    ui.checkbox(&mut self.record_stepped_frames, "Record Stepped Frames");
+                    ui.checkbox(&mut self.loop_playback, "Loop Playback");
+                });
+                
+                ui.menu_button("Help", |ui| {
+                    if ui.button("Keyboard Shortcuts").clicked() {
+                        ui.close_menu();
+                        self.show_shortcuts = true;
+                    }
                 });
             });
         });
         
         // Main video panel
         egui::CentralPanel::default().show(ctx, |ui| {
-            // Fill the entire background with a color to test if panel is visible
-            ui.painter().rect_filled(
-                ui.available_rect_before_wrap(),
-                0.0,
-                egui::Color32::from_rgb(50, 50, 50)
-            );
-            
-            ui.heading("Metric Video Player");
-            ui.label("If you see this, text rendering works!");
-            ui.colored_label(egui::Color32::YELLOW, "This should be YELLOW text");
-            
-            if ui.button("TEST BUTTON - Click me!").clicked() {
-                log::info!("Button clicked!");
-            }
+            // Built up front since it needs &mut self for the memory reading
+            let osd_text = if self.show_osd { Some(self.osd_text()) } else { None };
             
             // Video display
             if let Some(texture) = &self.frame_texture {
                 log::debug!("RENDER: Have texture, size: {:?}, ID: {:?}", texture.size_vec2(), texture.id());
                 let available_size = ui.available_size();
                 log::debug!("RENDER: Available UI size: {:?}", available_size);
-                let texture_size = texture.size_vec2();
-                
-                // Test: Just draw a simple colored rectangle to see if rendering works
-                ui.colored_label(egui::Color32::RED, "VIDEO AREA - If you see this text in red, UI rendering works!");
-                
-                // Draw a test rectangle
-                let test_rect = egui::Rect::from_min_size(
-                    egui::pos2(100.0, 100.0),
-                    egui::vec2(200.0, 200.0)
-                );
-                ui.painter().rect_filled(test_rect, 0.0, egui::Color32::from_rgb(255, 0, 0));
                 
                 // Reserve space for controls at the bottom
                 let video_area_height = available_size.y - 120.0; // Reserve 120px for controls
                 let available_video_size = egui::vec2(available_size.x, video_area_height);
                 
-                // Calculate aspect ratio preserving size
-                let aspect_ratio = texture_size.x / texture_size.y;
+                // Calculate aspect ratio preserving size (pixels aren't square in anamorphic video)
+                let aspect_ratio = self.player.get_display_aspect_ratio() as f32;
                 log::debug!("RENDER: Aspect ratio: {}", aspect_ratio);
                 let display_size = if available_video_size.x / available_video_size.y > aspect_ratio {
                     egui::vec2(available_video_size.y * aspect_ratio, available_video_size.y)
@@ -203,6 +710,20 @@ impl eframe::App for MetricVideoPlayerApp {
                         // Try simpler image rendering
                         let response = ui.add(egui::Image::new(texture).fit_to_exact_size(display_size));
                         log::debug!("RENDER: Image widget added, response rect: {:?}", response.rect);
+                        
+                        // Stats overlay anchored to the video's top-left corner
+                        if let Some(text) = osd_text {
+                            let painter = ui.painter();
+                            let galley = painter.layout_no_wrap(
+                                text,
+                                egui::FontId::monospace(14.0),
+                                egui::Color32::WHITE,
+                            );
+                            let pos = response.rect.min + egui::vec2(8.0, 8.0);
+                            let background = egui::Rect::from_min_size(pos, galley.size()).expand(4.0);
+                            painter.rect_filled(background, 4.0, egui::Color32::from_black_alpha(160));
+                            painter.galley(pos, galley, egui::Color32::WHITE);
+                        }
                     },
                 );
             } else {
@@ -227,33 +748,141 @@ impl eframe::App for MetricVideoPlayerApp {
             ui.separator();
             ui.horizontal(|ui| {
                 if ui.button(if self.is_playing { "Pause" } else { "Play" }).clicked() {
-                    self.is_playing = !self.is_playing;
-                    if self.is_playing {
-                        self.last_frame_time = Some(Instant::now());
+                    self.toggle_playback();
+                }
+                
+                if ui.add_enabled(!self.is_playing, egui::Button::new("Step Back")).clicked() {
+                    self.step_frame(ctx, false);
+                }
+                if ui.add_enabled(!self.is_playing, egui::Button::new("Step Forward")).clicked() {
+                    self.step_frame(ctx, true);
+                }
+                if ui.add_enabled(self.last_frame.is_some(), egui::Button::new("Screenshot")).clicked() {
+                    self.save_screenshot();
+                }
+                
+                if self.playlist.len() > 1 {
+                    ui.separator();
+                    if ui.button("⏮ Prev").clicked() && !self.switch_video(false, true) {
+                        log::info!("No other video to switch to");
+                    }
+                    if ui.button("Next ⏭").clicked() && !self.switch_video(true, true) {
+                        log::info!("No other video to switch to");
                     }
                 }
                 
                 ui.separator();
                 
+                ui.label("Speed:");
+                let mut speed = self.player.get_playback_speed();
+                egui::ComboBox::from_id_source("playback_speed")
+                    .selected_text(format!("{}x", speed))
+                    .width(60.0)
+                    .show_ui(ui, |ui| {
+                        for option in PLAYBACK_SPEEDS {
+                            ui.selectable_value(&mut speed, option, format!("{}x", option));
+                        }
+                    });
+                if speed != self.player.get_playback_speed() {
+                    self.player.set_playback_speed(speed);
+                }
+                
+                ui.separator();
+                
                 ui.label("Target FPS:");
-                if ui.text_edit_singleline(&mut self.target_fps_input).changed() {
-                    if let Ok(fps) = self.target_fps_input.parse::<u32>() {
-                        self.args.target_fps = fps;
-                    }
+                let mut unlimited = self.player.get_target_fps() == 0;
+                let mut fps = match self.player.get_target_fps() {
+                    0 => (self.player.get_native_fps().round() as u32).clamp(1, MAX_TARGET_FPS),
+                    fps => fps,
+                };
+                let unlimited_changed = ui.checkbox(&mut unlimited, "Unlimited").changed();
+                let fps_changed = ui
+                    .add_enabled(!unlimited, egui::Slider::new(&mut fps, 1..=MAX_TARGET_FPS))
+                    .changed();
+                if unlimited_changed || fps_changed {
+                    self.set_target_fps(if unlimited { 0 } else { fps });
                 }
                 
                 ui.separator();
                 
-                // Progress bar
-                let progress = self.player.get_progress();
+                if !self.player.is_seekable() || !self.player.has_duration() {
+                    // Nothing to seek within, e.g. a pipe or live stream
+                    if self.player.is_live() {
+                        ui.colored_label(egui::Color32::RED, "● LIVE");
+                    }
+                    let end = if self.player.has_duration() { format_timestamp(self.player.get_segment().1) } else { "—".to_string() };
+                    ui.label(format!("{} / {}", format_timestamp(self.shown_position()), end));
+                    return;
+                }
+                
+                // Seek bar over the played segment - while dragging, show the dragged position instead of the decoded one
+                let (start, end) = self.player.get_segment();
+                let (start_secs, end_secs) = (start.as_secs_f64(), end.as_secs_f64());
+                let mut position = self.seek_position
+                    .unwrap_or(self.shown_position().as_secs_f64().clamp(start_secs, end_secs));
+                let progress = if end_secs > start_secs { (position - start_secs) / (end_secs - start_secs) } else { 0.0 };
                 ui.label(format!("Progress: {:.1}%", progress * 100.0));
-                ui.add(egui::ProgressBar::new(progress as f32).show_percentage());
+                ui.label(format!("{} / {}", format_timestamp(Duration::from_secs_f64(position)), format_timestamp(end)));
+                
+                ui.spacing_mut().slider_width = ui.available_width().max(100.0);
+                let response = ui.add(
+                    egui::Slider::new(&mut position, start_secs..=end_secs)
+                        .show_value(false)
+                        .trailing_fill(true),
+                );
+                
+                if response.drag_started() {
+                    // Hold frame advancement while scrubbing
+                    self.resume_after_seek = self.is_playing;
+                    self.is_playing = false;
+                }
+                
+                if response.changed() {
+                    self.seek_preview(ctx, position);
+                    if response.dragged() {
+                        self.seek_position = Some(position);
+                    }
+                }
+                
+                if response.drag_stopped() {
+                    self.seek_position = None;
+                    if self.resume_after_seek {
+                        self.is_playing = true;
+                        self.last_frame_time = Some(Instant::now());
+                    }
+                }
+                
+                self.scene_ticks(ui, ctx, response.rect, start_secs..=end_secs);
+                
+                // Preview the hovered position, unless dragging already shows the real frame
+                if !response.dragged() {
+                    if let Some(pointer) = response.hover_pos() {
+                        let (left, width) = seek_bar_rail(response.rect);
+                        let fraction = ((pointer.x - left) / width).clamp(0.0, 1.0) as f64;
+                        let hovered = start_secs + fraction * (end_secs - start_secs);
+                        if self.thumbnails.is_none() && !self.args.no_thumbnails {
+                            self.thumbnails = Some(Thumbnails::new(
+                                self.player.get_source(),
+                                &self.args.player_options(),
+                                (start, end),
+                                ctx.clone(),
+                            ));
+                        }
+                        response.on_hover_ui_at_pointer(|ui| {
+                            if let Some(thumbnails) = &mut self.thumbnails {
+                                thumbnails.show(ui, hovered);
+                            }
+                            ui.label(format_timestamp(Duration::from_secs_f64(hovered)));
+                        });
+                    }
+                }
             });
             
             // Quick metrics display
             ui.horizontal(|ui| {
-                ui.label(format!("Frame: {}/{}", 
-                    self.player.get_current_frame(),
+                ui.label(format!("Frame: {}/{}{}", 
+                    self.shown_frame_number(),
+                    if self.player.is_frame_count_exact() { "" } else { "~" },
                     self.player.get_total_frames()
                 ));
                 ui.separator();
@@ -263,8 +892,43 @@ impl eframe::App for MetricVideoPlayerApp {
                 ui.separator();
                 ui.label(format!("Memory: {:.1} MB", self.metrics.get_current_memory_mb()));
             });
+            
+            if self.status_message.as_ref().is_some_and(|(_, shown_at)| shown_at.elapsed() >= STATUS_MESSAGE_TIMEOUT) {
+                self.status_message = None;
+            }
+            if let Some((message, _)) = &self.status_message {
+                ui.label(message.as_str());
+            }
         });
         
+        egui::Window::new("Keyboard Shortcuts")
+            .open(&mut self.show_shortcuts)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                egui::Grid::new("keyboard_shortcuts").striped(true).show(ui, |ui| {
+                    for (keys, action) in KEYBOARD_SHORTCUTS {
+                        ui.strong(*keys);
+                        ui.label(*action);
+                        ui.end_row();
+                    }
+                });
+            });
+        
+        // Error dialog
+        if let Some(message) = self.error_message.clone() {
+            egui::Window::new("Error")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+                .show(ctx, |ui| {
+                    ui.label(message);
+                    if ui.button("OK").clicked() {
+                        self.error_message = None;
+                    }
+                });
+        }
+        
         // Metrics window
         if self.show_metrics_window {
             egui::Window::new("Performance Metrics")
@@ -285,7 +949,28 @@ impl eframe::App for MetricVideoPlayerApp {
                             ui.end_row();
                             
                             ui.label("Max FPS:");
-                            ui.label(format!("{:.2}", self.metrics.get_max_fps()));
+                            ui.label(format!("{:.2}", self.metrics.get_max_render_fps()));
+                            ui.end_row();
+                            
+                            ui.label("Frame time σ:");
+                            ui.label(format!(
+                                "{:.2} ms (session {:.2} ms)",
+                                self.metrics.get_frametime_stddev_ms(),
+                                self.metrics.get_session_frametime_stddev_ms()
+                            ));
+                            ui.end_row();
+                            
+                            ui.label("Pacing jitter:");
+                            ui.label(match (
+                                self.metrics.get_pacing_jitter_ms(),
+                                self.metrics.get_mean_abs_pacing_deviation_ms(),
+                                self.metrics.get_max_pacing_deviation_ms(),
+                            ) {
+                                (Some(jitter), Some(mean), Some(max)) => {
+                                    format!("σ {:.2} ms, mean {:.2} ms, worst {:.2} ms", jitter, mean, max)
+                                }
+                                _ => "N/A".to_string(),
+                            });
                             ui.end_row();
                             
                             ui.label("Current Memory:");
@@ -304,17 +989,91 @@ impl eframe::App for MetricVideoPlayerApp {
                             ui.label(format!("{:.1}%", self.metrics.get_peak_cpu_percent()));
                             ui.end_row();
                             
+                            let or_na = |value: Option<f64>, unit: &str| value.map_or("N/A".to_string(), |value| format!("{:.0}{}", value, unit));
+                            ui.label("Current GPU:");
+                            ui.label(or_na(self.metrics.get_current_gpu_percent(), "%"));
+                            ui.end_row();
+                            
+                            ui.label("Peak GPU:");
+                            ui.label(or_na(self.metrics.get_peak_gpu_percent(), "%"));
+                            ui.end_row();
+                            
+                            ui.label("Current GPU Decoder:");
+                            ui.label(or_na(self.metrics.get_current_gpu_decoder_percent(), "%"));
+                            ui.end_row();
+                            
+                            ui.label("Peak GPU Decoder:");
+                            ui.label(or_na(self.metrics.get_peak_gpu_decoder_percent(), "%"));
+                            ui.end_row();
+                            
+                            ui.label("Current VRAM:");
+                            ui.label(or_na(self.metrics.get_current_vram_mb(), " MB"));
+                            ui.end_row();
+                            
+                            ui.label("Peak VRAM:");
+                            ui.label(or_na(self.metrics.get_peak_vram_mb(), " MB"));
+                            ui.end_row();
+                            
+                            ui.label("Player VRAM:");
+                            ui.label(or_na(self.metrics.get_current_process_vram_mb(), " MB"));
+                            ui.end_row();
+                            
                             ui.label("Dropped Frames:");
                             ui.label(format!("{}", self.metrics.get_dropped_frames()));
                             ui.end_row();
                             
                             ui.label("Session Time:");
-                            ui.label(format!("{:.1}s", self.metrics.get_session_duration().as_secs_f64()));
+                            ui.label(format_timecode(self.metrics.get_session_duration()));
                             ui.end_row();
                         });
                     
                     ui.separator();
                     
+                    ui.horizontal(|ui| {
+                        ui.selectable_value(&mut self.plot_pacing_error, false, "Frame time");
+                        ui.selectable_value(&mut self.plot_pacing_error, true, "Pacing error");
+                    });
+                    if self.plot_pacing_error {
+                        let deviations = self.metrics.pacing_deviation_series(FRAME_TIME_PLOT_SAMPLES);
+                        if deviations.len() > 1 {
+                            ui.heading("Pacing Error");
+                            // Symmetric around zero, so early and late frames read the same
+                            let max = deviations.iter().map(|ms| ms.abs()).fold(1.0, f64::max);
+                            draw_series(ui, deviations.iter().copied(), -max..=max);
+                            ui.label(format!("±{:.1} ms from the ideal frame interval", max));
+                            ui.separator();
+                        }
+                    } else {
+                        let frame_times = self.metrics.frame_time_series(FRAME_TIME_PLOT_SAMPLES);
+                        if frame_times.len() > 1 {
+                            ui.heading("Frame Time");
+                            let max = frame_times.iter().map(|&(ms, _, _)| ms).fold(1.0, f64::max);
+                            let points = draw_series(ui, frame_times.iter().map(|&(ms, _, _)| ms), 0.0..=max);
+                            // Keyframes and cuts as markers, so their decode spikes can be told apart
+                            let painter = ui.painter();
+                            for (point, &(_, keyframe, scene_change)) in points.iter().zip(&frame_times) {
+                                if scene_change {
+                                    painter.circle_filled(*point, 3.0, SCENE_CHANGE_COLOR);
+                                } else if keyframe {
+                                    painter.circle_filled(*point, 3.0, egui::Color32::from_rgb(230, 80, 60));
+                                }
+                            }
+                            if self.args.detect_scenes.is_some() {
+                                ui.label(format!("Max {:.1} ms, red dots are keyframes, yellow dots scene cuts", max));
+                            } else {
+                                ui.label(format!("Max {:.1} ms, red dots are keyframes", max));
+                            }
+                            ui.separator();
+                        }
+                    }
+                    
+                    let luma = self.metrics.average_luma_series();
+                    if !luma.is_empty() {
+                        ui.heading("Luma over Time");
+                        draw_series(ui, luma.iter().map(|&(_, luma)| luma), 0.0..=255.0);
+                        ui.separator();
+                    }
+                    
                     if self.show_advanced_metrics {
                         ui.heading("Video Information");
                         egui::Grid::new("video_info_grid")
@@ -328,31 +1087,119 @@ impl eframe::App for MetricVideoPlayerApp {
                                 ));
                                 ui.end_row();
                                 
+                                if let Some(crop) = self.player.get_crop() {
+                                    ui.label("Crop:");
+                                    ui.label(format!("{}x{} at {},{}", crop.width, crop.height, crop.x, crop.y));
+                                    ui.end_row();
+                                }
+                                
+                                let (output_width, output_height) = self.player.get_output_size();
+                                if (output_width, output_height) != (self.player.get_width(), self.player.get_height()) {
+                                    ui.label("Scaled To:");
+                                    ui.label(format!("{}x{}", output_width, output_height));
+                                    ui.end_row();
+                                }
+                                
                                 ui.label("Duration:");
-                                ui.label(format!("{:.1}s", self.player.get_duration().as_secs_f64()));
+                                if self.player.is_live() {
+                                    ui.label("LIVE");
+                                } else {
+                                    ui.label(format_timecode_ms(self.player.get_duration()));
+                                }
+                                ui.end_row();
+                                
+                                ui.label("Decoder:");
+                                ui.label(format!("{} ({})",
+                                    self.player.get_codec_name(),
+                                    self.player.get_hwaccel_name().unwrap_or("software")
+                                ));
+                                ui.end_row();
+                                
+                                let video_info = self.player.info();
+                                ui.label("Container:");
+                                ui.label(&video_info.container_format);
+                                ui.end_row();
+                                
+                                if let Some(profile) = &video_info.profile {
+                                    ui.label("Profile:");
+                                    match video_info.level {
+                                        Some(level) => ui.label(format!("{} @ level {}", profile, level)),
+                                        None => ui.label(profile),
+                                    };
+                                    ui.end_row();
+                                }
+                                
+                                ui.label("Pixel Format:");
+                                ui.label(format!("{} ({}-bit)", video_info.pixel_format, video_info.bit_depth));
+                                ui.end_row();
+                                
+                                ui.label("Color:");
+                                ui.label(format!(
+                                    "{} / {} / {} / {}",
+                                    video_info.color_space.as_deref().unwrap_or("unspecified"),
+                                    video_info.color_primaries.as_deref().unwrap_or("unspecified"),
+                                    video_info.color_transfer.as_deref().unwrap_or("unspecified"),
+                                    video_info.color_range.as_deref().unwrap_or("unspecified")
+                                ));
+                                ui.end_row();
+                                
+                                ui.label("YUV Conversion:");
+                                ui.label(format!("{}, {} range", video_info.yuv_matrix, video_info.yuv_range));
+                                ui.end_row();
+                                
+                                if let Some(hdr) = &video_info.hdr {
+                                    ui.label("HDR:");
+                                    match &video_info.tonemap {
+                                        Some(tonemap) => ui.label(format!("{}, tone mapped with {}", hdr, tonemap)),
+                                        None => ui.label(format!("{}, not tone mapped", hdr)),
+                                    };
+                                    ui.end_row();
+                                }
+                                
+                                ui.label("Rotation:");
+                                ui.label(format!("{}°", video_info.rotation));
+                                ui.end_row();
+                                
+                                ui.label("Bitrate:");
+                                ui.label(format!(
+                                    "{:.0} kbps (peak {:.0}){}",
+                                    self.metrics.get_average_bitrate_kbps(),
+                                    self.metrics.get_peak_bitrate_kbps(),
+                                    video_info.container_bit_rate.map_or(String::new(), |bit_rate| format!(", container {} kbps", bit_rate / 1000))
+                                ));
+                                ui.end_row();
+                                
+                                ui.label("Current Bitrate:");
+                                ui.label(format!(
+                                    "{:.0} kbps ({:.0} kbps over 1 s)",
+                                    self.metrics.get_current_bitrate_kbps(),
+                                    self.metrics.get_smoothed_bitrate_kbps()
+                                ));
                                 ui.end_row();
                                 
                                 ui.label("Native FPS:");
-                                ui.label(format!("{:.2}", self.player.get_native_fps()));
+                                ui.label(format!("{:.2}{}", self.player.get_native_fps(),
+                                    if self.player.is_vfr() { " (variable)" } else { "" }));
                                 ui.end_row();
                                 
                                 ui.label("Total Frames:");
-                                ui.label(format!("{}", self.player.get_total_frames()));
+                                ui.label(format!("{}{}",
+                                    if self.player.is_frame_count_exact() { "" } else { "~" },
+                                    self.player.get_total_frames()));
                                 ui.end_row();
                             });
                     }
                     
                     ui.separator();
                     
-                    if ui.button("Export Metrics").clicked() {
-                        // TODO: Implement proper file dialog
-                        let export_path = std::path::PathBuf::from("metrics_export.json");
-                        if let Err(e) = self.metrics.export_to_file(&export_path) {
-                            log::error!("Failed to export metrics: {}", e);
-                        } else {
-                            log::info!("Metrics exported to: {:?}", export_path);
+                    ui.horizontal(|ui| {
+                        if ui.button("Export Metrics…").clicked() {
+                            self.export_metrics_dialog();
                         }
-                    }
+                        if ui.button("Reset").clicked() {
+                            self.metrics.reset();
+                        }
+                    });
                     
                     if ui.button("Print Summary").clicked() {
                         self.metrics.print_summary();
@@ -360,4 +1207,75 @@ impl eframe::App for MetricVideoPlayerApp {
                 });
         }
     }
+}
+
+/// Format to decode to for display: egui textures are RGBA, so converting straight to it saves
+/// a second pass per frame, while `--grayscale` frames are expanded from luma on upload.
+fn display_format(args: &Args) -> OutputFormat {
+    if args.grayscale { OutputFormat::Gray8 } else { OutputFormat::Rgba }
+}
+
+/// Left end and length of a slider's rail, which stops short of the slider's ends by the handle radius.
+fn seek_bar_rail(seek_bar: egui::Rect) -> (f32, f32) {
+    let inset = seek_bar.height() / 2.5;
+    (seek_bar.left() + inset, seek_bar.width() - 2.0 * inset)
+}
+
+/// Plot `values` as a line across the available width, one point per sample, clamped to `range`.
+/// Returns the plotted points, for drawing markers on top.
+fn draw_series(ui: &mut egui::Ui, values: impl ExactSizeIterator<Item = f64>, range: std::ops::RangeInclusive<f64>) -> Vec<egui::Pos2> {
+    let (rect, _) = ui.allocate_exact_size(egui::vec2(ui.available_width(), 80.0), egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 2.0, ui.visuals().extreme_bg_color);
+    
+    // Samples are plotted in order rather than by timestamp, seeks would fold the line back otherwise
+    let step = rect.width() / values.len().saturating_sub(1).max(1) as f32;
+    let span = range.end() - range.start();
+    let line: Vec<egui::Pos2> = values
+        .enumerate()
+        .map(|(i, value)| {
+            let fraction = (value.clamp(*range.start(), *range.end()) - range.start()) / span;
+            egui::pos2(rect.left() + i as f32 * step, rect.bottom() - fraction as f32 * rect.height())
+        })
+        .collect();
+    painter.add(egui::Shape::line(line.clone(), egui::Stroke::new(1.0, ui.visuals().text_color())));
+    line
+}
+
+/// Drive the egui app without a window until playback ends.
+///
+/// Nothing is rasterized - egui's texture deltas are dropped - but layout and
+/// texture upload into egui's texture manager run exactly as they do on screen.
+pub fn run_offscreen(player: VideoPlayer, metrics: MetricsCollector, args: Args, playlist: Playlist) -> MetricsCollector {
+    let ctx = egui::Context::default();
+    let mut app = MetricVideoPlayerApp::new(player, metrics, args, playlist);
+    
+    let screen_rect = egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(1200.0, 800.0));
+    let start = Instant::now();
+    let mut ui_frames = 0u64;
+    let mut ui_time = Duration::ZERO;
+    
+    while !app.is_finished() {
+        let raw_input = egui::RawInput {
+            screen_rect: Some(screen_rect),
+            time: Some(start.elapsed().as_secs_f64()),
+            ..Default::default()
+        };
+        
+        let frame_start = Instant::now();
+        let _ = ctx.run(raw_input, |ctx| app.ui(ctx));
+        ui_time += frame_start.elapsed();
+        ui_frames += 1;
+        
+        // Small delay to prevent maxing out CPU between paced frames
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    
+    log::info!(
+        "Offscreen run finished: {} UI frames, {:.2} ms average UI time",
+        ui_frames,
+        ui_time.as_secs_f64() * 1000.0 / ui_frames.max(1) as f64
+    );
+    
+    app.into_metrics()
 }
\ No newline at end of file
@@ -0,0 +1,34 @@
+//! Video decoding and playback metrics behind the `metric-video-player` binary.
+//!
+//! [`VideoPlayer`] decodes frames with FFmpeg and [`MetricsCollector`] turns them into FPS,
+//! frame time, memory and CPU statistics:
+//!
+//! ```no_run
+//! use metric_video_player::{MetricsCollector, PlayerOptions, VideoPlayer};
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let mut player = VideoPlayer::new("video.mp4".as_ref(), &PlayerOptions::default())?;
+//! let mut metrics = MetricsCollector::default();
+//! // The first 1000 frames; `next_frame_into` reuses one buffer instead
+//! for frame in player.frames().take(1000) {
+//!     let frame = frame?;
+//!     metrics.record_frame(frame.frame_number, &frame);
+//! }
+//! println!("{:.2} FPS", metrics.finalize_session().average_fps);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Errors are `anyhow::Error`s with context describing what failed.
+
+pub mod metrics;
+pub mod metrics_server;
+pub mod quality;
+pub mod video_player;
+mod gpu;
+mod sqlite;
+#[cfg(test)]
+mod test_clips;
+
+pub use metrics::{FrameMetrics, MetricsCollector, SessionMetrics};
+pub use video_player::{Frames, OutputFormat, PlayerOptions, VideoInfo, VideoFrame, VideoPlayer};
@@ -1,40 +1,134 @@
 use anyhow::Result;
 use clap::Parser;
 use log::info;
-use std::path::PathBuf;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
-mod video_player;
-mod metrics;
 mod gui;
 mod sdl_gui;
+mod frame_export;
+mod frame_hashes;
+mod gif_export;
+mod playlist;
+mod poster;
+mod thumbnails;
+mod window_title;
 
-use video_player::VideoPlayer;
-use metrics::MetricsCollector;
+// Decoding and metrics live in the library; the front ends and CLI plumbing stay here
+use metric_video_player::{metrics, metrics_server, quality, video_player};
 
-#[derive(Parser, Debug)]
+use video_player::{ColorMatrix, Crop, Deinterlace, FrameHash, HwAccel, OutputFormat, PacingMode, PlayerOptions, Scaler, Tonemap, VideoFrame, YuvRange, VideoPlayer, VIDEO_EXTENSIONS};
+use metrics::{Baseline, BenchmarkAggregate, BenchmarkReport, CsvLog, DEFAULT_STATS_INTERVAL, FpsWindow, LiveMetrics, MetricsCollector, MetricsFormat, MetricsRetention, MetricsStream, SessionMetrics, SharedCsvLog, SharedLiveMetrics, SharedMetricsStream};
+use metrics_server::MetricsServer;
+use frame_export::{ExportFormat, FrameExporter};
+use frame_hashes::FrameHashes;
+use playlist::Playlist;
+
+/// `println!` for human-readable results, moved to stderr when stdout carries `--report-format json`.
+macro_rules! report {
+    ($args:expr, $($arg:tt)*) => {
+        if $args.report_format == ReportFormat::Json {
+            eprintln!($($arg)*);
+        } else {
+            println!($($arg)*);
+        }
+    };
+}
+
+/// Set by Ctrl+C in CLI and benchmark mode, so the frame loops stop and metrics still get written
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Format of the benchmark results on stdout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum ReportFormat {
+    #[default]
+    Text,
+    /// A single JSON object; progress and tables go to stderr
+    Json,
+}
+
+#[derive(Parser, Debug, Clone)]
 #[command(name = "metric-video-player")]
 #[command(about = "High-performance video player with FPS and performance metrics")]
 pub struct Args {
-    /// Path to the video file to play
-    #[arg(short = 'i', long)]
+    /// Path or URL (http, https, rtsp, rtmp) of the video to play, `-` for stdin, or a directory of videos in benchmark mode.
+    /// Repeat to play several videos in a row
+    #[arg(short = 'i', long = "video-path", value_name = "VIDEO_PATH", required_unless_present = "playlist")]
+    pub inputs: Vec<PathBuf>,
+    
+    /// Text file listing videos to play after the -i ones, one per line
+    #[arg(long, value_name = "FILE")]
+    pub playlist: Option<PathBuf>,
+    
+    /// Video being played: the first input, then whichever playlist item is current
+    #[arg(skip)]
     pub video_path: PathBuf,
     
     /// Target FPS (0 = maximum possible)
     #[arg(short, long, default_value = "0")]
     pub target_fps: u32,
     
-    /// Enable GUI mode (default: true)
-    #[arg(short, long, default_value = "true")]
-    pub gui: bool,
+    /// Playback speed multiplier (0.25 - 4.0)
+    #[arg(long, default_value = "1.0")]
+    pub speed: f32,
+    
+    /// How to wait between frames for --target-fps: sleep, or sleep then spin for the last millisecond
+    #[arg(long, value_enum, default_value = "hybrid")]
+    pub pacing: PacingMode,
+    
+    /// With --target-fps, skip late frames to stay in sync with the clock instead of falling behind
+    #[arg(long)]
+    pub realtime: bool,
+    
+    /// Enable GUI mode (default: true, falls back to CLI mode when no display is available)
+    #[arg(short, long)]
+    pub gui: Option<bool>,
+    
+    /// Run the egui interface without a window (for CI, no display needed)
+    #[arg(long)]
+    pub offscreen: bool,
     
     /// Use egui instead of SDL2 for GUI (SDL2 is default due to better video rendering)
     #[arg(long)]
     pub egui: bool,
     
-    /// Export metrics to JSON file
+    /// Frames the egui player decodes ahead between presents to absorb decode spikes (0 = off)
+    #[arg(long, value_name = "FRAMES", default_value = "4")]
+    pub prefetch: usize,
+    
+    /// Don't decode preview thumbnails when hovering the egui seek bar (keeps a second decoder from competing for the CPU)
+    #[arg(long)]
+    pub no_thumbnails: bool,
+    
+    /// Export metrics to a file: JSON, or CSV / Chrome trace / SQLite when it ends in `.csv` / `.trace.json` / `.db`
     #[arg(short, long)]
     pub export_metrics: Option<PathBuf>,
     
+    /// Format for --export-metrics, instead of guessing it from the file name
+    #[arg(long, value_name = "FORMAT")]
+    pub metrics_format: Option<MetricsFormat>,
+    
+    /// Name of this run in a SQLite --export-metrics database (default: the video's file name)
+    #[arg(long, value_name = "LABEL")]
+    pub run_label: Option<String>,
+    
+    /// Append per-frame metrics to a CSV file while playing, so interrupted runs keep their data
+    #[arg(long, value_name = "PATH")]
+    pub csv_live: Option<PathBuf>,
+    
+    /// Append per-frame metrics as JSON lines while playing, ending each session with a summary line
+    #[arg(long, value_name = "PATH")]
+    pub metrics_stream: Option<PathBuf>,
+    
+    /// With --metrics-stream, only write every Nth frame
+    #[arg(long, default_value = "1", value_parser = clap::value_parser!(u64).range(1..))]
+    pub metrics_stream_every: u64,
+    
     /// Enable verbose logging
     #[arg(short, long)]
     pub verbose: bool,
@@ -42,11 +136,414 @@ pub struct Args {
     /// Run in benchmark mode (no GUI, just metrics)
     #[arg(short, long)]
     pub benchmark: bool,
+    
+    /// Restart the video when it ends instead of stopping
+    #[arg(long = "loop")]
+    pub loop_playback: bool,
+    
+    /// Reset metrics each time a looping video restarts (default: accumulate)
+    #[arg(long)]
+    pub reset_metrics_per_loop: bool,
+    
+    /// Print and reset metrics when a playlist moves to the next video (default: one session with per-file sections)
+    #[arg(long)]
+    pub reset_metrics_per_file: bool,
+    
+    /// Number of benchmark runs, each with a freshly opened decoder
+    #[arg(long, default_value = "1")]
+    pub runs: usize,
+    
+    /// Frames to decode and discard before benchmark measurements start (counted from --start)
+    #[arg(long, default_value = "0")]
+    pub warmup: u64,
+    
+    /// Stop benchmark or CLI playback after this many frames (not counting --warmup)
+    #[arg(long, value_name = "N")]
+    pub max_frames: Option<u64>,
+    
+    /// Stop benchmark or CLI playback after this much wall-clock time, in seconds or [HH:]MM:SS(.ms)
+    #[arg(long, value_name = "TIME", value_parser = parse_time)]
+    pub max_duration: Option<Duration>,
+    
+    /// Metrics JSON from an earlier benchmark (--export-metrics) to compare against; exits non-zero on regression
+    #[arg(long, value_name = "METRICS_JSON", requires = "benchmark")]
+    pub baseline: Option<PathBuf>,
+    
+    /// How much slower than --baseline a run may be, in percent
+    #[arg(long, value_name = "PCT", default_value = "5")]
+    pub tolerance: f64,
+    
+    /// Also fail when peak memory grows by more than --tolerance over --baseline
+    #[arg(long, requires = "baseline")]
+    pub check_memory: bool,
+    
+    /// Write a Markdown report of the benchmark, compared against --baseline if given
+    #[arg(long = "report", value_name = "PATH", requires = "benchmark")]
+    pub markdown_report: Option<PathBuf>,
+    
+    /// Directory where screenshots (S key) are saved
+    #[arg(long, default_value = ".")]
+    pub screenshot_dir: PathBuf,
+    
+    /// Index of the video stream to play (see --list-streams)
+    #[arg(long)]
+    pub stream_index: Option<usize>,
+    
+    /// Print all streams in the input and exit without decoding
+    #[arg(long)]
+    pub list_streams: bool,
+    
+    /// Compare the video frame by frame against this reference and report PSNR
+    #[arg(long, value_name = "REFERENCE")]
+    pub compare: Option<PathBuf>,
+    
+    /// Also compute SSIM with --compare (slower)
+    #[arg(long, requires = "compare")]
+    pub ssim: bool,
+    
+    /// Write frames <start>:<end> (0-based, end exclusive) as PNGs and exit
+    #[arg(long, value_name = "START:END")]
+    pub dump_frames: Option<FrameRange>,
+    
+    /// Directory for --dump-frames output
+    #[arg(long, default_value = "frames")]
+    pub dump_dir: PathBuf,
+    
+    /// Write a poster frame as PNG and exit; a directory for one PNG per video when given several
+    #[arg(long, value_name = "OUT.png")]
+    pub thumbnail: Option<PathBuf>,
+    
+    /// Time of the --thumbnail frame, in seconds or [HH:]MM:SS(.ms) [default: 10% into the video]
+    #[arg(long, value_name = "TIME", value_parser = parse_time, requires = "thumbnail")]
+    pub at: Option<Duration>,
+    
+    /// Scale --thumbnail down so its longer side is at most this many pixels
+    #[arg(long, value_name = "PX", requires = "thumbnail")]
+    pub thumb_width: Option<u32>,
+    
+    /// Write the --from..--to clip as a looping animated GIF and exit
+    #[arg(long, value_name = "OUT.gif")]
+    pub gif: Option<PathBuf>,
+    
+    /// Start of the --gif clip, in seconds or [HH:]MM:SS(.ms) [default: 0]
+    #[arg(long, value_name = "TIME", value_parser = parse_time, requires = "gif")]
+    pub from: Option<Duration>,
+    
+    /// End of the --gif clip [default: as long as the frame limit allows]
+    #[arg(long, value_name = "TIME", value_parser = parse_time, requires = "gif")]
+    pub to: Option<Duration>,
+    
+    /// Frame rate of the --gif, frames in between are dropped
+    #[arg(long, value_name = "FPS", default_value = "10")]
+    pub gif_fps: u32,
+    
+    /// Width of the --gif in pixels, the height follows the aspect ratio [default: video width]
+    #[arg(long, value_name = "PX", requires = "gif")]
+    pub gif_width: Option<u32>,
+    
+    /// Write played frames to this directory as frame_<number>.<ext>, in any mode
+    #[arg(long, value_name = "DIR")]
+    pub export_frames: Option<PathBuf>,
+    
+    /// With --export-frames, only write every Nth frame
+    #[arg(long, default_value = "1", value_parser = clap::value_parser!(u64).range(1..))]
+    pub export_every: u64,
+    
+    /// Image format for --export-frames
+    #[arg(long, value_enum, default_value = "png")]
+    pub export_format: ExportFormat,
+    
+    /// Digest every frame's visible pixels, stored in the per-frame metrics
+    #[arg(long, value_enum, default_value = "none")]
+    pub hash_frames: FrameHash,
+    
+    /// With --hash-frames, write frame_number,hash lines to this file (CLI and benchmark modes)
+    #[arg(long, value_name = "PATH")]
+    pub hashes_file: Option<PathBuf>,
+    
+    /// With --hash-frames, compare each frame with a file from --hashes-file and fail on any mismatch
+    #[arg(long, value_name = "FILE")]
+    pub verify_hashes: Option<PathBuf>,
+    
+    /// Network I/O timeout in seconds for URL inputs (0 = ffmpeg default)
+    #[arg(long, default_value = "10")]
+    pub network_timeout: u64,
+    
+    /// Refuse videos whose converted frames (at the output size and format) exceed this size in MB
+    #[arg(long, default_value = "64")]
+    pub max_frame_mb: usize,
+    
+    /// Play videos regardless of --max-frame-mb
+    #[arg(long)]
+    pub allow_huge_frames: bool,
+    
+    /// Show frames as stored, ignoring rotation metadata
+    #[arg(long)]
+    pub ignore_rotation: bool,
+    
+    /// Resample anamorphic video to square pixels (also affects screenshots and dumped frames)
+    #[arg(long)]
+    pub square_pixels: bool,
+    
+    /// Keep only the region X:Y:W:H of each frame (pixels of the upright video), cut out before scaling
+    #[arg(long, value_name = "X:Y:W:H")]
+    pub crop: Option<Crop>,
+    
+    /// FFmpeg filter chain run on decoded frames before cropping and scaling, e.g. "hqdn3d,eq=gamma=1.2"
+    #[arg(long = "vf", value_name = "FILTERS")]
+    pub video_filter: Option<String>,
+    
+    /// Scale frames to WxH (-1 for either side keeps the aspect ratio), a height like 720p, or a factor like 0.5
+    #[arg(long, conflicts_with_all = ["output_width", "output_height"])]
+    pub scale: Option<ScaleSpec>,
+    
+    /// Scale frames to this width (keeps the aspect ratio unless --output-height is also given)
+    #[arg(long, value_parser = clap::value_parser!(u32).range(2..))]
+    pub output_width: Option<u32>,
+    
+    /// Scale frames to this height (keeps the aspect ratio unless --output-width is also given)
+    #[arg(long, value_parser = clap::value_parser!(u32).range(2..))]
+    pub output_height: Option<u32>,
+    
+    /// Start with the on-video stats overlay shown
+    #[arg(long)]
+    pub overlay: bool,
+    
+    /// TrueType font for the SDL2 stats overlay (default: a common system font)
+    #[arg(long)]
+    pub font: Option<PathBuf>,
+    
+    /// How benchmark results are printed on stdout
+    #[arg(long, value_enum, default_value = "text")]
+    pub report_format: ReportFormat,
+    
+    /// Exit with an error when the benchmark's average FPS is below this, for CI
+    #[arg(long, value_name = "FPS")]
+    pub fail_below_fps: Option<f64>,
+    
+    /// Pixel format frames are converted to (the egui front end uses rgba, or gray8 with --grayscale)
+    #[arg(long, value_enum, default_value = "rgb24")]
+    pub output_format: OutputFormat,
+    
+    /// Decode straight to 8-bit grayscale, in every mode (overrides --output-format and --yuv-direct)
+    #[arg(long)]
+    pub grayscale: bool,
+    
+    /// Skip pixel conversion for YUV420P video and upload the decoded planes as-is (SDL2 and CLI modes)
+    #[arg(long)]
+    pub yuv_direct: bool,
+    
+    /// Hardware decoder to use, falling back to software when unavailable
+    #[arg(long, value_enum, default_value = "none")]
+    pub hwaccel: HwAccel,
+    
+    /// swscale algorithm used for conversion and resampling (quality vs. speed)
+    #[arg(long, value_enum, default_value = "fast_bilinear")]
+    pub scaler: Scaler,
+    
+    /// Deinterlace with yadif before conversion; auto only kicks in for frames flagged as interlaced
+    #[arg(long, value_enum, default_value = "none")]
+    pub deinterlace: Deinterlace,
+    
+    /// Curve for tone mapping HDR (PQ/HLG) video to SDR; needs FFmpeg with zscale (libzimg)
+    #[arg(long, value_enum, default_value = "hable")]
+    pub tonemap: Tonemap,
+    
+    /// YUV matrix to convert with, for files whose colorspace metadata is missing or wrong
+    #[arg(long, value_enum, default_value = "auto")]
+    pub assume_colorspace: ColorMatrix,
+    
+    /// YUV range to convert with, for files whose range metadata is missing or wrong
+    #[arg(long, value_enum, default_value = "auto")]
+    pub assume_range: YuvRange,
+    
+    /// Start playback at this time, in seconds or [HH:]MM:SS(.ms)
+    #[arg(long, value_name = "TIME", value_parser = parse_time)]
+    pub start: Option<Duration>,
+    
+    /// Stop playback at this time, in seconds or [HH:]MM:SS(.ms)
+    #[arg(long, value_name = "TIME", value_parser = parse_time)]
+    pub end: Option<Duration>,
+    
+    /// Read the whole file up front to count frames when the container doesn't store the count (e.g. MKV/WebM)
+    #[arg(long)]
+    pub probe: bool,
+    
+    /// Serve Prometheus metrics on http://0.0.0.0:<PORT>/metrics while playing
+    #[arg(long, value_name = "PORT")]
+    pub metrics_port: Option<u16>,
+    
+    /// Abort on the first corrupted packet or frame instead of skipping it (for CI)
+    #[arg(long)]
+    pub strict: bool,
+    
+    /// Count frames identical to the one before (e.g. in screen recordings); hashes every frame
+    #[arg(long)]
+    pub detect_duplicates: bool,
+    
+    /// Report scene cuts: frames whose downsampled luma differs from the previous one by more than this on average (0-255, try 30)
+    #[arg(long, value_name = "THRESHOLD")]
+    pub detect_scenes: Option<f64>,
+    
+    /// Measure the average luma of every frame, to spot flashing or fades (reads every pixel)
+    #[arg(long)]
+    pub analyze_luma: bool,
+    
+    /// Per-frame samples to keep: `all`, or a limit after which samples are downsampled (summary stats stay exact)
+    #[arg(long, value_name = "all|N", default_value = "all")]
+    pub metrics_retention: MetricsRetention,
+    
+    /// Milliseconds between reads of process memory and CPU; frames in between reuse the last reading
+    #[arg(long, value_name = "MS", default_value_t = DEFAULT_STATS_INTERVAL.as_millis() as u64)]
+    pub stats_interval_ms: u64,
+    
+    /// What "Current FPS" is averaged over: a frame count, or a duration like `1s` or `500ms`
+    #[arg(long, value_name = "N|DURATION", default_value = "60")]
+    pub fps_window: FpsWindow,
+}
+
+/// Half-open range of 0-based frame indices, written as `start:end`.
+#[derive(Debug, Clone, Copy)]
+pub struct FrameRange {
+    pub start: u64,
+    pub end: u64,
+}
+
+impl FromStr for FrameRange {
+    type Err = String;
+    
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (start, end) = s
+            .split_once(':')
+            .ok_or_else(|| format!("expected <start>:<end>, got '{}'", s))?;
+        let start = start.trim().parse().map_err(|e| format!("invalid start frame '{}': {}", start, e))?;
+        let end = end.trim().parse().map_err(|e| format!("invalid end frame '{}': {}", end, e))?;
+        if end <= start {
+            return Err(format!("end frame {} must be greater than start frame {}", end, start));
+        }
+        Ok(Self { start, end })
+    }
+}
+
+/// Frame size requested with `--scale`.
+#[derive(Debug, Clone, Copy)]
+pub enum ScaleSpec {
+    /// Width and height, either of which may be left to follow the aspect ratio
+    Size(Option<u32>, Option<u32>),
+    Factor(f64),
+}
+
+impl FromStr for ScaleSpec {
+    type Err = String;
+    
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_ascii_lowercase();
+        let dimension = |value: &str| -> Result<Option<u32>, String> {
+            match value.trim() {
+                "-1" => Ok(None),
+                value => match value.parse() {
+                    Ok(0) | Err(_) => Err(format!("invalid dimension '{}' in --scale", value)),
+                    Ok(size) => Ok(Some(size)),
+                },
+            }
+        };
+        
+        if let Some(height) = s.strip_suffix('p') {
+            return Ok(Self::Size(None, Some(dimension(height)?.ok_or("expected a height like 720p")?)));
+        }
+        if let Some((width, height)) = s.split_once('x') {
+            let (width, height) = (dimension(width)?, dimension(height)?);
+            if width.is_none() && height.is_none() {
+                return Err("at most one side of WxH can be -1".to_string());
+            }
+            return Ok(Self::Size(width, height));
+        }
+        match s.parse::<f64>() {
+            Ok(factor) if factor.is_finite() && factor > 0.0 => Ok(Self::Factor(factor)),
+            _ => Err(format!("expected WxH, a height like 720p, or a factor like 0.5, got '{}'", s)),
+        }
+    }
+}
+
+/// Parse a time given as seconds (`90.5`) or `[HH:]MM:SS(.ms)` (`01:30.5`).
+fn parse_time(s: &str) -> Result<Duration, String> {
+    let parts: Vec<&str> = s.trim().split(':').collect();
+    if parts.len() > 3 {
+        return Err(format!("expected seconds or [HH:]MM:SS(.ms), got '{}'", s));
+    }
+    
+    let (seconds, whole) = parts.split_last().unwrap();
+    let seconds: f64 = seconds.parse().map_err(|_| format!("invalid seconds in '{}'", s))?;
+    if !seconds.is_finite() || seconds < 0.0 || (!whole.is_empty() && seconds >= 60.0) {
+        return Err(format!("invalid seconds in '{}'", s));
+    }
+    
+    let mut total = 0u64;
+    for (i, part) in whole.iter().enumerate() {
+        let value: u64 = part.parse().map_err(|_| format!("invalid hours or minutes in '{}'", s))?;
+        // Minutes are bounded when hours precede them
+        if i > 0 && value >= 60 {
+            return Err(format!("invalid minutes in '{}'", s));
+        }
+        total = total * 60 + value;
+    }
+    Ok(Duration::from_secs(total * 60) + Duration::from_secs_f64(seconds))
+}
+
+impl Args {
+    pub fn player_options(&self) -> PlayerOptions {
+        PlayerOptions {
+            target_fps: self.target_fps,
+            max_frame_bytes: if self.allow_huge_frames {
+                None
+            } else {
+                Some(self.max_frame_mb * 1024 * 1024)
+            },
+            network_timeout: self.network_timeout(),
+            stream_index: self.stream_index,
+            ignore_rotation: self.ignore_rotation,
+            square_pixels: self.square_pixels,
+            output_width: match self.scale {
+                Some(ScaleSpec::Size(width, _)) => width,
+                _ => self.output_width,
+            },
+            output_height: match self.scale {
+                Some(ScaleSpec::Size(_, height)) => height,
+                _ => self.output_height,
+            },
+            scale_factor: match self.scale {
+                Some(ScaleSpec::Factor(factor)) => Some(factor),
+                _ => None,
+            },
+            output_format: if self.grayscale { OutputFormat::Gray8 } else { self.output_format },
+            // egui can only display RGB(A) and gray frames
+            yuv_direct: self.yuv_direct && !self.grayscale && !self.egui && !self.offscreen,
+            hwaccel: self.hwaccel,
+            scaler: self.scaler,
+            strict: self.strict,
+            probe: self.probe,
+            hash_frames: self.detect_duplicates,
+            frame_hash: self.hash_frames,
+            pacing: self.pacing,
+            deinterlace: self.deinterlace,
+            crop: self.crop,
+            video_filter: self.video_filter.clone(),
+            tonemap: self.tonemap,
+            assume_colorspace: self.assume_colorspace,
+            assume_range: self.assume_range,
+            start: self.start,
+            end: self.end,
+        }
+    }
+    
+    fn network_timeout(&self) -> Option<Duration> {
+        (self.network_timeout > 0).then(|| Duration::from_secs(self.network_timeout))
+    }
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let args = Args::parse();
+    let mut args = Args::parse();
     
     // Initialize logging
     if args.verbose {
@@ -59,50 +556,146 @@ async fn main() -> Result<()> {
             .init();
     }
     
+    let mut playlist = Playlist::from_args(&args)?;
+    args.video_path = playlist.current().to_path_buf();
+    
     info!("Starting Metric Video Player");
     info!("Video file: {:?}", args.video_path);
+    if playlist.len() > 1 {
+        info!("Queued {} videos", playlist.len());
+    }
     info!("Target FPS: {}", if args.target_fps == 0 { "Maximum".to_string() } else { args.target_fps.to_string() });
     
-    // Validate video file exists
-    if !args.video_path.exists() {
+    // Validate video file exists (network URLs and stdin are left to ffmpeg)
+    let is_file = !video_player::is_url(&args.video_path) && !video_player::is_stdin(&args.video_path);
+    if is_file && !args.video_path.exists() {
         anyhow::bail!("Video file does not exist: {:?}", args.video_path);
     }
     
+    if args.list_streams {
+        for stream in video_player::list_streams(&args.video_path, args.network_timeout())? {
+            println!("{}", stream);
+        }
+        return Ok(());
+    }
+    
+    if let Some(out) = &args.thumbnail {
+        let videos = if args.video_path.is_dir() { video_files(&args.video_path)? } else { playlist.items().to_vec() };
+        return run_thumbnails(&videos, out, &args);
+    }
+    if let Some(out) = &args.gif {
+        return gif_export::write_gif(&args.video_path, out, &args);
+    }
+    
+    // The server stops when dropped at the end of main
+    let (live_metrics, _metrics_server) = match args.metrics_port {
+        Some(port) => {
+            let live: SharedLiveMetrics = Arc::new(Mutex::new(LiveMetrics::default()));
+            let server = MetricsServer::start(port, live.clone())?;
+            (Some(live), Some(server))
+        }
+        None => (None, None),
+    };
+    let csv_log = match &args.csv_live {
+        Some(path) => {
+            info!("Logging per-frame metrics to {:?}", path);
+            Some(Arc::new(Mutex::new(CsvLog::create(path)?)))
+        }
+        None => None,
+    };
+    let stream = match &args.metrics_stream {
+        Some(path) => {
+            info!("Streaming metrics to {:?}", path);
+            Some(Arc::new(MetricsStream::create(path)?))
+        }
+        None => None,
+    };
+    let sinks = MetricsSinks { live: live_metrics, csv_log, stream };
+    
+    if args.video_path.is_dir() {
+        if !args.benchmark {
+            anyhow::bail!("Directories are only supported in benchmark mode (--benchmark)");
+        }
+        info!("Benchmarking all videos in {:?}...", args.video_path);
+        handle_interrupt();
+        return run_benchmark_directory(&args, &sinks).await;
+    }
+    if args.benchmark && playlist.len() > 1 {
+        info!("Benchmarking {} videos...", playlist.len());
+        handle_interrupt();
+        return run_benchmark_files(playlist.items(), &args, &sinks).await;
+    }
+    
+    // Check for a display before eframe/SDL fail with something cryptic
+    let mut use_gui = args.gui.unwrap_or(true);
+    if use_gui && !args.benchmark && !args.offscreen && !display_available() {
+        if args.gui == Some(true) {
+            anyhow::bail!("GUI mode was requested but no display is available; use --gui false or --offscreen");
+        }
+        log::warn!("No display available, falling back to CLI mode");
+        use_gui = false;
+    }
+    
     // Initialize metrics collector
-    let mut metrics = MetricsCollector::new();
+    let mut metrics = sinks.new_collector(&args);
     
     // Create video player
-    let mut player = VideoPlayer::new(&args.video_path, args.target_fps)?;
+    let mut player = playlist.open_first(&args)?;
+    player.set_playback_speed(args.speed);
+    args.video_path = playlist.current().to_path_buf();
+    metrics.set_video_info(player.info().clone());
+    if playlist.len() > 1 {
+        metrics.start_file(playlist.current());
+    }
     
-    if args.benchmark {
+    if let Some(range) = args.dump_frames {
+        info!("Dumping frames {}..{} to {:?}", range.start, range.end, args.dump_dir);
+        run_dump_frames(&mut player, range, &args.dump_dir)?;
+    } else if let Some(reference) = &args.compare {
+        info!("Comparing against {:?}...", reference);
+        handle_interrupt();
+        let mut metrics = run_compare(&mut player, reference, &args, &sinks)?;
+        
+        // Export metrics if requested
+        if let Some(export_path) = &args.export_metrics {
+            info!("Exporting metrics to: {:?}", export_path);
+            metrics.export_to_file(export_path, args.metrics_format)?;
+        }
+    } else if args.benchmark {
         // Run in benchmark mode
         info!("Running in benchmark mode...");
-        run_benchmark(&mut player, &mut metrics).await?;
+        handle_interrupt();
+        run_benchmarks(player, &args, &sinks).await?;
+    } else if args.offscreen {
+        info!("Running egui GUI offscreen...");
+        let mut metrics = gui::run_offscreen(player, metrics, args.clone(), playlist);
+        metrics.print_summary();
         
         // Export metrics if requested
         if let Some(export_path) = &args.export_metrics {
             info!("Exporting metrics to: {:?}", export_path);
-            metrics.export_to_file(export_path)?;
+            metrics.export_to_file(export_path, args.metrics_format)?;
         }
-    } else if args.gui {
+    } else if use_gui {
         // Run with GUI - SDL2 is default for better video rendering
         info!("Starting GUI mode...");
         if args.egui {
             info!("Using egui for video display (experimental)...");
-            run_gui(player, metrics, args).await?;
+            run_gui(player, metrics, args, playlist).await?;
         } else {
             info!("Using SDL2 for video display...");
-            sdl_gui::run_sdl_gui(player, metrics, args)?;
+            sdl_gui::run_sdl_gui(player, metrics, args, playlist)?;
         }
     } else {
         // Run in CLI mode
         info!("Running in CLI mode...");
-        run_cli(&mut player, &mut metrics).await?;
+        handle_interrupt();
+        run_cli(&mut player, &mut metrics, &args, &mut playlist).await?;
         
         // Export metrics if requested
         if let Some(export_path) = &args.export_metrics {
             info!("Exporting metrics to: {:?}", export_path);
-            metrics.export_to_file(export_path)?;
+            metrics.export_to_file(export_path, args.metrics_format)?;
         }
     }
     
@@ -110,68 +703,724 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
-async fn run_benchmark(player: &mut VideoPlayer, metrics: &mut MetricsCollector) -> Result<()> {
+/// Where every metrics collector reports frames as they're recorded, shared across benchmark runs.
+struct MetricsSinks {
+    live: Option<SharedLiveMetrics>,
+    csv_log: Option<SharedCsvLog>,
+    stream: Option<SharedMetricsStream>,
+}
+
+impl MetricsSinks {
+    fn new_collector(&self, args: &Args) -> MetricsCollector {
+        let mut metrics = MetricsCollector::new(args.metrics_retention, Duration::from_millis(args.stats_interval_ms))
+            .with_window_size(args.fps_window);
+        metrics.set_segment(args.start, args.end);
+        metrics.set_run_label(args.run_label.clone().unwrap_or_else(|| {
+            args.video_path.file_name().map_or_else(|| args.video_path.display().to_string(), |name| name.to_string_lossy().into_owned())
+        }));
+        if let Some(live) = &self.live {
+            metrics.publish_to(live.clone());
+        }
+        if let Some(csv_log) = &self.csv_log {
+            metrics.log_csv_to(csv_log.clone());
+        }
+        if let Some(stream) = &self.stream {
+            metrics.stream_to(stream.clone(), args.metrics_stream_every);
+        }
+        if let Some(threshold) = args.detect_scenes {
+            metrics.detect_scenes(threshold);
+        }
+        if args.analyze_luma {
+            metrics.analyze_luma();
+        }
+        metrics
+    }
+}
+
+/// Turn the first Ctrl+C into a graceful stop (see `interrupted`); a second one quits immediately.
+fn handle_interrupt() {
+    tokio::spawn(async {
+        if tokio::signal::ctrl_c().await.is_err() {
+            log::warn!("Couldn't listen for Ctrl+C, interrupting will lose unsaved metrics");
+            return;
+        }
+        INTERRUPTED.store(true, Ordering::Relaxed);
+        eprintln!("\nInterrupted, finishing up (press Ctrl+C again to quit immediately)");
+        
+        if tokio::signal::ctrl_c().await.is_ok() {
+            std::process::exit(130);
+        }
+    });
+}
+
+/// Whether Ctrl+C was pressed since `handle_interrupt`.
+fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::Relaxed)
+}
+
+/// Whether a GUI window can be opened at all.
+fn display_available() -> bool {
+    if cfg!(all(unix, not(target_os = "macos"))) {
+        let has_var = |name: &str| std::env::var_os(name).is_some_and(|v| !v.is_empty());
+        return has_var("DISPLAY") || has_var("WAYLAND_DISPLAY");
+    }
+    
+    // Elsewhere there's no cheap check, so ask SDL
+    sdl2::init().and_then(|sdl| sdl.video()).is_ok()
+}
+
+async fn run_benchmarks(mut player: VideoPlayer, args: &Args, sinks: &MetricsSinks) -> Result<()> {
+    // Load it before benchmarking so a bad path doesn't waste a long run
+    let baseline_runs = args.baseline.as_deref().map(Baseline::load_runs).transpose()?;
+    let baseline = baseline_runs.as_deref().map(Baseline::from_runs);
+    let runs = args.runs.max(1);
+    let mut sessions = Vec::with_capacity(runs);
+    let mut summaries = Vec::with_capacity(runs);
+    let mut last_metrics = None;
+    
+    for run in 1..=runs {
+        if run > 1 {
+            // Start every run from a cold decoder
+            player = VideoPlayer::new(&args.video_path, &args.player_options())?;
+        }
+        if runs > 1 {
+            if run == 2 && video_player::is_stdin(&args.video_path) {
+                anyhow::bail!("--runs needs a re-readable input, standard input can only be read once");
+            }
+            report!(args, "\n=== Run {}/{} ===", run, runs);
+        }
+        
+        let mut metrics = run_benchmark(&mut player, args, sinks).await?;
+        let session = metrics.finalize_session();
+        summaries.push(RunSummary::new(&args.video_path, &player, &session, &metrics));
+        sessions.push(session);
+        last_metrics = Some(metrics);
+        
+        if interrupted() && run < runs {
+            report!(args, "Skipping the remaining {} run(s)", runs - run);
+            break;
+        }
+    }
+    
+    if let Some(path) = &args.markdown_report {
+        write_markdown_report(path, &sessions, baseline_runs.as_deref())?;
+    }
+    
+    let current = Baseline::from_runs(&sessions);
+    let total_frames = summaries.iter().map(|summary| summary.total_frames).sum();
+    if sessions.len() == 1 {
+        if args.report_format == ReportFormat::Json {
+            println!("{}", serde_json::to_string(&summaries[0])?);
+        }
+        
+        // Export metrics if requested
+        if let (Some(export_path), Some(metrics)) = (&args.export_metrics, last_metrics.as_mut()) {
+            info!("Exporting metrics to: {:?}", export_path);
+            metrics.export_to_file(export_path, args.metrics_format)?;
+        }
+        check_baseline(baseline, current, args)?;
+        return check_thresholds(total_frames, current.average_fps, args);
+    }
+    
+    let aggregate = BenchmarkAggregate::from_runs(&sessions);
+    report!(args, "\n=== Aggregate over {} runs ===", aggregate.runs);
+    for (i, session) in sessions.iter().enumerate() {
+        report!(args, "Run {}: {:.2} FPS", i + 1, session.average_fps);
+    }
+    report!(args, "Mean FPS: {:.2}", aggregate.mean_fps);
+    report!(args, "Std dev: {:.2}", aggregate.stddev_fps);
+    report!(args, "Min/Max FPS: {:.2} / {:.2}", aggregate.min_fps, aggregate.max_fps);
+    if args.report_format == ReportFormat::Json {
+        let output = serde_json::json!({
+            "runs": summaries,
+            "mean_fps": aggregate.mean_fps,
+            "stddev_fps": aggregate.stddev_fps,
+        });
+        println!("{}", output);
+    }
+    
+    if let Some(export_path) = &args.export_metrics {
+        info!("Exporting metrics to: {:?}", export_path);
+        warn_unless_json(export_path, args);
+        let report = BenchmarkReport { runs: sessions, aggregate };
+        std::fs::write(export_path, serde_json::to_string_pretty(&report)?)?;
+    }
+    
+    check_baseline(baseline, current, args)?;
+    check_thresholds(total_frames, current.average_fps, args)
+}
+
+/// Write `--report` for the run closest to the mean FPS, compared against the baseline's run closest
+/// to its mean, followed by every run's FPS when there were several.
+fn write_markdown_report(path: &Path, sessions: &[SessionMetrics], baseline: Option<&[SessionMetrics]>) -> Result<()> {
+    let aggregate = BenchmarkAggregate::from_runs(sessions);
+    let Some(session) = aggregate.representative_run(sessions) else {
+        return Ok(());
+    };
+    let baseline = baseline.and_then(|runs| BenchmarkAggregate::from_runs(runs).representative_run(runs));
+    let mut report = match baseline {
+        Some(baseline) => session.to_markdown_compared(baseline),
+        None => session.to_markdown(),
+    };
+    
+    if sessions.len() > 1 {
+        report += &format!("\n## Runs\n\n{:.2} ± {:.2} FPS over {} runs, the table above is the run closest to the mean.\n\n",
+            aggregate.mean_fps, aggregate.stddev_fps, aggregate.runs);
+        report += "| Run | Average FPS | Peak Memory (MB) |\n|---:|---:|---:|\n";
+        for (i, run) in sessions.iter().enumerate() {
+            report += &format!("| {} | {:.2} | {:.1} |\n", i + 1, run.average_fps, run.peak_memory_mb);
+        }
+    }
+    
+    std::fs::write(path, report).map_err(|e| anyhow::anyhow!("Failed to write report {:?}: {}", path, e))?;
+    info!("Wrote benchmark report to {:?}", path);
+    Ok(())
+}
+
+/// Fail when nothing was decoded, or when `--fail-below-fps` is given and `average_fps` is under it.
+fn check_thresholds(total_frames: u64, average_fps: f64, args: &Args) -> Result<()> {
+    if total_frames == 0 {
+        anyhow::bail!("No frames were decoded");
+    }
+    if let Some(min_fps) = args.fail_below_fps {
+        if average_fps < min_fps {
+            anyhow::bail!("Average FPS {:.2} is below --fail-below-fps {}", average_fps, min_fps);
+        }
+    }
+    Ok(())
+}
+
+/// Reports over several runs or files are always JSON, whatever the file name or --metrics-format say.
+fn warn_unless_json(export_path: &Path, args: &Args) {
+    if args.metrics_format.unwrap_or_else(|| MetricsFormat::from_path(export_path)) != MetricsFormat::Json {
+        log::warn!("Metrics of several runs or files are exported as JSON");
+    }
+}
+
+/// Compare the runs against `--baseline`, failing if they regressed beyond `--tolerance`.
+fn check_baseline(baseline: Option<Baseline>, current: Baseline, args: &Args) -> Result<()> {
+    let (Some(baseline), Some(path)) = (baseline, &args.baseline) else {
+        return Ok(());
+    };
+    
+    let (table, ok) = current.compare(&baseline, args.tolerance, args.check_memory);
+    report!(args, "{}", table);
+    if !ok {
+        anyhow::bail!("Benchmark regressed by more than {}% against {:?}", args.tolerance, path);
+    }
+    Ok(())
+}
+
+/// Headline numbers of one benchmark run, for the summary table and `--report-format json`.
+#[derive(Debug, Serialize)]
+struct RunSummary {
+    path: String,
+    resolution: String,
+    codec: String,
+    /// Hardware decoder used, or "software"
+    decoder: String,
+    total_frames: u64,
+    elapsed_seconds: f64,
+    average_fps: f64,
+    p50_frame_time_ms: f64,
+    p95_frame_time_ms: f64,
+    p99_frame_time_ms: f64,
+    peak_memory_mb: f64,
+    dropped_frames: u64,
+    interrupted: bool,
+}
+
+impl RunSummary {
+    fn new(path: &Path, player: &VideoPlayer, session: &SessionMetrics, metrics: &MetricsCollector) -> Self {
+        Self {
+            path: path.display().to_string(),
+            resolution: format!("{}x{}", player.get_width(), player.get_height()),
+            codec: player.get_codec_name().to_string(),
+            decoder: player.get_hwaccel_name().unwrap_or("software").to_string(),
+            total_frames: session.total_frames,
+            elapsed_seconds: session.total_duration_seconds,
+            average_fps: session.average_fps,
+            p50_frame_time_ms: metrics.get_frame_time_percentile(50.0),
+            p95_frame_time_ms: metrics.get_frame_time_percentile(95.0),
+            p99_frame_time_ms: metrics.get_frame_time_percentile(99.0),
+            peak_memory_mb: session.peak_memory_mb,
+            dropped_frames: session.dropped_frames,
+            interrupted: session.interrupted,
+        }
+    }
+}
+
+async fn run_benchmark_directory(args: &Args, sinks: &MetricsSinks) -> Result<()> {
+    let paths = video_files(&args.video_path)?;
+    run_benchmark_files(&paths, args, sinks).await
+}
+
+/// Videos directly inside `dir`, by extension, sorted by name.
+fn video_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.is_file()
+                && path
+                    .extension()
+                    .and_then(|ext| ext.to_str())
+                    .is_some_and(|ext| VIDEO_EXTENSIONS.iter().any(|v| v.eq_ignore_ascii_case(ext)))
+        })
+        .collect();
+    paths.sort();
+    
+    if paths.is_empty() {
+        anyhow::bail!("No video files found in {:?}", dir);
+    }
+    Ok(paths)
+}
+
+/// Write `--thumbnail` for one video, or `<name>.png` into the `out` directory for each of several,
+/// skipping ones that fail.
+fn run_thumbnails(videos: &[PathBuf], out: &Path, args: &Args) -> Result<()> {
+    if let [video] = videos {
+        return poster::write_poster(video, out, args);
+    }
+    
+    std::fs::create_dir_all(out)?;
+    let mut failures = 0;
+    for video in videos {
+        let name = video.file_stem().map_or("poster".into(), |stem| stem.to_string_lossy());
+        if let Err(e) = poster::write_poster(video, &out.join(format!("{}.png", name)), args) {
+            log::error!("No poster for {:?}: {:#}", video, e);
+            failures += 1;
+        }
+    }
+    println!("Wrote {} of {} posters to {:?}", videos.len() - failures, videos.len(), out);
+    if failures == videos.len() {
+        anyhow::bail!("No posters could be written");
+    }
+    Ok(())
+}
+
+/// Benchmark each video once and print a summary table, skipping ones that fail.
+async fn run_benchmark_files(paths: &[PathBuf], args: &Args, sinks: &MetricsSinks) -> Result<()> {
+    if args.baseline.is_some() {
+        anyhow::bail!("--baseline compares a single video, it isn't supported for several");
+    }
+    if args.markdown_report.is_some() {
+        anyhow::bail!("--report describes a single video, it isn't supported for several");
+    }
+    
+    let mut results = Vec::new();
+    let mut failures = Vec::new();
+    let mut sessions = BTreeMap::new();
+    
+    for path in paths {
+        if interrupted() {
+            break;
+        }
+        report!(args, "\n=== {} ===", path.display());
+        
+        // A broken file shouldn't abort the whole batch
+        let outcome = async {
+            let mut player = VideoPlayer::new(path, &args.player_options())?;
+            let mut metrics = run_benchmark(&mut player, args, sinks).await?;
+            Ok::<_, anyhow::Error>((player, metrics.finalize_session(), metrics))
+        }
+        .await;
+        
+        match outcome {
+            Ok((player, session, metrics)) => {
+                results.push(RunSummary::new(path, &player, &session, &metrics));
+                sessions.insert(path.display().to_string(), session);
+            }
+            Err(e) => {
+                log::error!("Skipping {:?}: {:#}", path, e);
+                failures.push((path, e));
+            }
+        }
+    }
+    
+    let file_name = |path: &str| Path::new(path).file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    let name_width = results.iter().map(|r| file_name(&r.path).len()).max().unwrap_or(0).max("File".len());
+    report!(args, "\n=== Benchmark Summary ===");
+    report!(
+        args,
+        "{:<name_width$}  {:>11}  {:<10}  {:>8}  {:>10}  {:>9}  {:>10}",
+        "File", "Resolution", "Codec", "Frames", "Avg FPS", "p99 (ms)", "Peak MB"
+    );
+    for r in &results {
+        report!(
+            args,
+            "{:<name_width$}  {:>11}  {:<10}  {:>8}  {:>10.2}  {:>9.2}  {:>10.1}",
+            file_name(&r.path), r.resolution, r.codec, r.total_frames, r.average_fps, r.p99_frame_time_ms, r.peak_memory_mb
+        );
+    }
+    for (path, e) in &failures {
+        report!(args, "FAILED: {} ({:#})", path.display(), e);
+    }
+    if args.report_format == ReportFormat::Json {
+        let failed: Vec<String> = failures.iter().map(|(path, _)| path.display().to_string()).collect();
+        println!("{}", serde_json::json!({ "files": results, "failed": failed }));
+    }
+    
+    if let Some(export_path) = &args.export_metrics {
+        info!("Exporting metrics to: {:?}", export_path);
+        warn_unless_json(export_path, args);
+        std::fs::write(export_path, serde_json::to_string_pretty(&sessions)?)?;
+    }
+    
+    // Every file has to be fast enough
+    let total_frames = results.iter().map(|r| r.total_frames).sum();
+    let slowest_fps = results.iter().map(|r| r.average_fps).fold(f64::INFINITY, f64::min);
+    check_thresholds(total_frames, slowest_fps, args)
+}
+
+async fn run_benchmark(
+    player: &mut VideoPlayer,
+    args: &Args,
+    sinks: &MetricsSinks,
+) -> Result<MetricsCollector> {
     info!("Starting benchmark...");
+    let warmup = args.warmup;
+    
+    // Frames are recycled for the whole run, so the measurements don't include per-frame allocations
+    let mut frames = player.frames();
+    
+    // Decode and discard warmup frames so cold-start costs don't skew the stats
+    if warmup > 0 {
+        info!("Warming up with {} frames...", warmup);
+        let mut measured_from = Duration::ZERO;
+        for _ in 0..warmup {
+            if interrupted() {
+                break;
+            }
+            let Some(frame) = frames.next().transpose()? else {
+                anyhow::bail!("Video ended during the {} warmup frames, nothing left to measure", warmup);
+            };
+            measured_from = frame.timestamp;
+            frames.recycle(frame);
+        }
+        // With --start the measured region begins after the warmup, not at the requested position
+        info!("Measuring from {}", video_player::format_timecode_ms(measured_from));
+    }
     
+    let mut metrics = sinks.new_collector(args);
+    metrics.set_video_info(frames.player().info().clone());
+    let mut frame_exporter = FrameExporter::from_args(args)?;
+    let mut frame_hashes = FrameHashes::from_args(args)?;
     let start_time = std::time::Instant::now();
     let mut frame_count = 0;
     
-    while let Some(frame) = player.next_frame()? {
+    while !interrupted() {
+        let Some(frame) = frames.next().transpose()? else {
+            break;
+        };
         frame_count += 1;
-        metrics.record_frame(frame_count, &frame);
+        if let Some(scene) = metrics.record_frame(frame_count, &frame) {
+            report!(args, "{}", scene);
+        }
+        metrics.record_decode_errors(frames.player().take_decode_errors());
+        metrics.record_corrupt_frames(frames.player().take_corrupt_frames());
+        if let Some(exporter) = &mut frame_exporter {
+            exporter.submit(&frame);
+        }
+        if let Some(hashes) = &mut frame_hashes {
+            hashes.record(&frame)?;
+        }
+        frames.recycle(frame);
         
         // Update metrics every 100 frames
         if frame_count % 100 == 0 {
             let elapsed = start_time.elapsed();
             let current_fps = frame_count as f64 / elapsed.as_secs_f64();
-            println!("Processed {} frames, Current FPS: {:.2}", frame_count, current_fps);
+            report!(args, "Processed {} frames, Current FPS: {:.2}", frame_count, current_fps);
+        }
+        if let Some(limit) = reached_limit(args, frame_count, start_time.elapsed()) {
+            metrics.mark_truncated(limit);
+            break;
         }
     }
     
     let total_time = start_time.elapsed();
     let average_fps = frame_count as f64 / total_time.as_secs_f64();
     
-    println!("\n=== Benchmark Results ===");
-    println!("Total frames: {}", frame_count);
-    println!("Total time: {:.2}s", total_time.as_secs_f64());
-    println!("Average FPS: {:.2}", average_fps);
-    println!("Maximum FPS achieved: {:.2}", metrics.get_max_fps());
-    println!("Memory usage: {:.2} MB", metrics.get_peak_memory_mb());
+    if interrupted() {
+        report!(args, "\nBenchmark interrupted, results cover the frames played so far");
+        metrics.mark_interrupted();
+    }
+    report!(args, "\n=== Benchmark Results ===");
+    report!(args, "Total frames: {}", frame_count);
+    if let Some(limit) = metrics.get_truncated() {
+        report!(args, "Truncated: stopped at {} after {} frames", limit, frame_count);
+    }
+    report!(args, "Total time: {:.2}s", total_time.as_secs_f64());
+    report!(args, "Average FPS: {:.2}", average_fps);
+    report!(args, "Maximum FPS achieved: {:.2}", metrics.get_max_render_fps());
+    report!(args, "Memory usage: {:.2} MB", metrics.get_peak_memory_mb());
     
+    // Errors after the last frame, e.g. in a truncated tail, weren't collected in the loop
+    metrics.record_decode_errors(player.take_decode_errors());
+    metrics.record_corrupt_frames(player.take_corrupt_frames());
+    report!(args, "Decode errors: {}", metrics.get_decode_errors());
+    report!(args, "Corrupt frames: {}", metrics.get_corrupt_frames());
+    if let Some(gop) = metrics.get_average_gop_length() {
+        match metrics.get_gop_length_range() {
+            Some((min, max)) => report!(args, "Keyframes: {} (average GOP {:.1} frames, {:.0} - {:.0} between keyframes)",
+                metrics.get_keyframes(), gop, min, max),
+            None => report!(args, "Keyframes: {} (average GOP {:.1} frames)", metrics.get_keyframes(), gop),
+        }
+    }
+    if let Some(exporter) = frame_exporter {
+        exporter.finish();
+    }
+    if let Some(hashes) = frame_hashes {
+        hashes.finish(args)?;
+    }
+    
+    Ok(metrics)
+}
+
+/// The `--max-frames` or `--max-duration` limit reached after `frames` frames in `elapsed`, if any.
+fn reached_limit(args: &Args, frames: u64, elapsed: Duration) -> Option<String> {
+    if let Some(max_frames) = args.max_frames.filter(|&max_frames| frames >= max_frames) {
+        return Some(format!("--max-frames {}", max_frames));
+    }
+    args.max_duration
+        .filter(|&max_duration| elapsed >= max_duration)
+        .map(|max_duration| format!("--max-duration {:.1}s", max_duration.as_secs_f64()))
+}
+
+fn run_dump_frames(player: &mut VideoPlayer, range: FrameRange, dir: &Path) -> Result<()> {
+    let total_frames = player.get_total_frames();
+    if total_frames > 0 && range.start >= total_frames {
+        anyhow::bail!("Start frame {} is past the end of the video ({} frames)", range.start, total_frames);
+    }
+    
+    std::fs::create_dir_all(dir)?;
+    
+    if range.start > 0 && player.is_seekable() {
+        player.seek_to_frame(range.start)?;
+    }
+    
+    let start_time = std::time::Instant::now();
+    let mut written = 0u64;
+    
+    while let Some(frame) = player.next_frame()? {
+        // Frame numbers are 1-based
+        let index = frame.frame_number - 1;
+        if index >= range.end {
+            break;
+        }
+        if index < range.start {
+            continue;
+        }
+        
+        frame.save_image(&dir.join(format!("frame_{:06}.png", index)))?;
+        written += 1;
+        
+        if written % 100 == 0 {
+            let fps = written as f64 / start_time.elapsed().as_secs_f64();
+            println!("Wrote {} frames, Current FPS: {:.2}", written, fps);
+        }
+    }
+    
+    if written == 0 {
+        anyhow::bail!("No frames found in range {}:{}", range.start, range.end);
+    }
+    
+    println!("Wrote {} frames to {} in {:.2}s", written, dir.display(), start_time.elapsed().as_secs_f64());
     Ok(())
 }
 
-async fn run_cli(player: &mut VideoPlayer, metrics: &mut MetricsCollector) -> Result<()> {
+/// Decode `player` and `reference` in lockstep, recording the PSNR (and SSIM) of every frame.
+fn run_compare(player: &mut VideoPlayer, reference: &Path, args: &Args, sinks: &MetricsSinks) -> Result<MetricsCollector> {
+    // Scale the reference to the compared video so the pixels line up
+    let (width, height) = player.get_output_size();
+    let reference_options = PlayerOptions {
+        output_width: Some(width),
+        output_height: Some(height),
+        scale_factor: None,
+        hash_frames: false,
+        frame_hash: FrameHash::None,
+        ..args.player_options()
+    };
+    let mut reference_player = VideoPlayer::new(reference, &reference_options)?;
+    if reference_player.get_output_size() != (width, height) {
+        anyhow::bail!(
+            "Reference frames are {:?} after scaling, expected {}x{}",
+            reference_player.get_output_size(),
+            width,
+            height
+        );
+    }
+    player.set_output_format(OutputFormat::Rgb24);
+    reference_player.set_output_format(OutputFormat::Rgb24);
+    
+    let mut metrics = sinks.new_collector(args);
+    metrics.set_video_info(player.info().clone());
+    let mut frame = VideoFrame::default();
+    let mut reference_frame = VideoFrame::default();
+    let mut frame_count = 0;
+    
+    while !interrupted() {
+        let decoded = player.next_frame_into(&mut frame)?;
+        let reference_decoded = reference_player.next_frame_into(&mut reference_frame)?;
+        metrics.record_decode_errors(player.take_decode_errors());
+        metrics.record_corrupt_frames(player.take_corrupt_frames());
+        match (decoded, reference_decoded) {
+            (true, true) => {}
+            (false, false) => break,
+            (true, false) => {
+                log::warn!("Reference ended after {} frames, the rest of the video isn't compared", frame_count);
+                break;
+            }
+            (false, true) => {
+                log::warn!("Video ended after {} frames, the rest of the reference isn't compared", frame_count);
+                break;
+            }
+        }
+        
+        frame_count += 1;
+        if let Some(scene) = metrics.record_frame(frame_count, &frame) {
+            report!(args, "{}", scene);
+        }
+        let (data, reference_data) = (frame.rgb_data(), reference_frame.rgb_data());
+        let psnr = quality::psnr(&reference_data, &data);
+        let ssim = args
+            .ssim
+            .then(|| quality::ssim(&reference_data, &data, frame.width as usize, frame.height as usize));
+        metrics.record_quality(psnr, ssim);
+        
+        if frame_count % 100 == 0 {
+            println!("Compared {} frames, PSNR: {:.2} dB", frame_count, psnr);
+        }
+    }
+    
+    if interrupted() {
+        println!("\nComparison interrupted, results cover the frames compared so far");
+        metrics.mark_interrupted();
+    }
+    println!("\n=== Quality Comparison ===");
+    println!("Frames compared: {}", frame_count);
+    metrics.print_summary();
+    Ok(metrics)
+}
+
+async fn run_cli(player: &mut VideoPlayer, metrics: &mut MetricsCollector, args: &Args, playlist: &mut Playlist) -> Result<()> {
     info!("Starting CLI playback...");
     
     let start_time = std::time::Instant::now();
     let mut frame_count = 0;
+    let mut frames_since_restart = 0;
+    let mut limit_reached = false;
+    let mut frame_exporter = FrameExporter::from_args(args)?;
+    let mut frame_hashes = FrameHashes::from_args(args)?;
     
     println!("Playing video... Press Ctrl+C to stop");
     
-    while let Some(frame) = player.next_frame()? {
-        frame_count += 1;
-        metrics.record_frame(frame_count, &frame);
+    while !interrupted() {
+        // Play the current video to its end; the player can't be swapped while its frames are borrowed
+        let mut frames = player.frames();
+        while !interrupted() {
+            let realtime = args.realtime && frames.player().get_target_fps() > 0;
+            let next = if realtime {
+                let target_interval = Duration::from_nanos(1_000_000_000 / frames.player().get_target_fps() as u64);
+                frames.player().next_frame_realtime(target_interval)?
+            } else {
+                frames.next().transpose()?
+            };
+            metrics.record_decode_errors(frames.player().take_decode_errors());
+            metrics.record_corrupt_frames(frames.player().take_corrupt_frames());
+            metrics.record_dropped_frames(frames.player().take_dropped_frames());
+            if let Some(error) = frames.player().take_pacing_error() {
+                metrics.record_pacing_error(error);
+            }
+            let Some(frame) = next else {
+                break;
+            };
+            
+            frame_count += 1;
+            frames_since_restart += 1;
+            if let Some(scene) = metrics.record_frame(frame_count, &frame) {
+                println!("{}", scene);
+            }
+            if let Some(exporter) = &mut frame_exporter {
+                exporter.submit(&frame);
+            }
+            if let Some(hashes) = &mut frame_hashes {
+                hashes.record(&frame)?;
+            }
+            frames.recycle(frame);
+            
+            // Display progress every second
+            let elapsed = start_time.elapsed();
+            if elapsed.as_secs() > 0 && frame_count % (metrics.get_average_fps() as u64).max(1) == 0 {
+                let current_fps = frame_count as f64 / elapsed.as_secs_f64();
+                println!("Frame: {}, FPS: {:.2}, Time: {}", 
+                    frame_count, current_fps, frames.player().format_position());
+            }
+            if let Some(limit) = reached_limit(args, frame_count, elapsed) {
+                metrics.mark_truncated(limit);
+                limit_reached = true;
+                break;
+            }
+            
+            // Sleep to maintain target FPS if specified (next_frame_realtime already waited)
+            if !realtime {
+                frames.player().maintain_target_fps();
+                if let Some(error) = frames.player().take_pacing_error() {
+                    metrics.record_pacing_error(error);
+                }
+            }
+        }
+        if interrupted() || limit_reached {
+            break;
+        }
         
-        // Display progress every second
-        let elapsed = start_time.elapsed();
-        if elapsed.as_secs() > 0 && frame_count % (metrics.get_average_fps() as u64).max(1) == 0 {
-            let current_fps = frame_count as f64 / elapsed.as_secs_f64();
-            println!("Frame: {}, FPS: {:.2}, Time: {:.1}s", 
-                frame_count, current_fps, elapsed.as_secs_f64());
+        if playlist.len() > 1 {
+            // With --loop, start the list over, unless that would just spin on empty files
+            match playlist.advance(true, args.loop_playback && frames_since_restart > 0, args, metrics) {
+                Some(next) => {
+                    *player = next;
+                    frames_since_restart = 0;
+                    continue;
+                }
+                None => break,
+            }
         }
         
-        // Sleep to maintain target FPS if specified
-        player.maintain_target_fps();
+        // Stop looping if a restart produced no frames at all
+        if !args.loop_playback || frames_since_restart == 0 || !player.is_seekable() {
+            break;
+        }
+        info!("End of video, restarting");
+        player.restart()?;
+        metrics.mark_discontinuity();
+        if args.reset_metrics_per_loop {
+            metrics.reset();
+        }
+        frames_since_restart = 0;
     }
     
     let total_time = start_time.elapsed();
-    println!("\nPlayback completed in {:.2}s", total_time.as_secs_f64());
+    if interrupted() {
+        println!("\nPlayback interrupted after {}", video_player::format_timecode_ms(total_time));
+        metrics.mark_interrupted();
+        metrics.print_summary();
+    } else if let Some(limit) = metrics.get_truncated() {
+        println!("\nPlayback stopped at {} after {} frames ({})", limit, frame_count, video_player::format_timecode_ms(total_time));
+        metrics.print_summary();
+    } else {
+        println!("\nPlayback completed in {}", video_player::format_timecode_ms(total_time));
+    }
+    if let Some(exporter) = frame_exporter {
+        exporter.finish();
+    }
+    if let Some(hashes) = frame_hashes {
+        hashes.finish(args)?;
+    }
     
     Ok(())
 }
 
-async fn run_gui(player: VideoPlayer, metrics: MetricsCollector, args: Args) -> Result<()> {
+async fn run_gui(player: VideoPlayer, metrics: MetricsCollector, args: Args, playlist: Playlist) -> Result<()> {
     log::info!("Setting up eframe options...");
     
     let options = eframe::NativeOptions {
@@ -183,7 +1432,7 @@ async fn run_gui(player: VideoPlayer, metrics: MetricsCollector, args: Args) ->
     };
     
     log::info!("Running eframe...");
-    let app = gui::MetricVideoPlayerApp::new(player, metrics, args);
+    let app = gui::MetricVideoPlayerApp::new(player, metrics, args, playlist);
     
     eframe::run_native(
         "Metric Video Player",
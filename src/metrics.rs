@@ -1,19 +1,227 @@
-use crate::video_player::VideoFrame;
-use anyhow::Result;
+use crate::gpu::GpuMonitor;
+use crate::video_player::{format_timecode_ms, OutputFormat, VideoInfo, VideoFrame};
+use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::VecDeque;
-use std::path::Path;
+use std::collections::{BTreeMap, VecDeque};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
 use std::time::{Duration, Instant};
 use sysinfo::{System, Pid, ProcessRefreshKind, RefreshKind};
 
+/// Frames taking this many times the mean processing time are kept even when downsampling
+const OUTLIER_FACTOR: f64 = 2.0;
+
+/// Size of the luma grid compared between frames for scene detection
+const SCENE_GRID: (u32, u32) = (64, 36);
+
+/// Rows written to the `--csv-live` log between flushes
+const CSV_FLUSH_ROWS: u64 = 30;
+
+const CSV_HEADER: &str = "frame_number,timestamp,processing_time_ms,conversion_time_ms,memory_usage_mb,cpu_usage_percent,packet_bytes,bitrate_kbps,frame_type,is_keyframe,stats_age_ms,filter_time_ms";
+
+/// Seconds of video the per-frame bitrate is measured over
+const BITRATE_WINDOW: f64 = 1.0;
+
+/// Frames "Current FPS" is averaged over unless configured otherwise
+const DEFAULT_FPS_WINDOW: usize = 60;
+
+/// Default for how often process memory and CPU are read, see `MetricsCollector::new`
+pub const DEFAULT_STATS_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How often the `--metrics-stream` writer flushes to disk
+const STREAM_FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Upper bounds in seconds of the frame time histogram shared through `LiveMetrics`
+pub const FRAME_TIME_BUCKETS: [f64; 10] = [0.001, 0.002, 0.005, 0.010, 0.0167, 0.0334, 0.050, 0.100, 0.250, 1.0];
+
+/// Chrome trace thread ids of the per-frame tracks.
+const TRACE_DECODE_TID: u32 = 1;
+const TRACE_SCALE_TID: u32 = 2;
+
+/// File format for `--export-metrics`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum MetricsFormat {
+    Json,
+    Csv,
+    /// Trace Event Format for chrome://tracing and Perfetto
+    Chrome,
+    /// Append to a SQLite database of runs (needs the `sqlite` feature)
+    Sqlite,
+}
+
+impl MetricsFormat {
+    /// Guess the format from the file name: `.trace.json` is a Chrome trace, `.csv` is CSV,
+    /// `.db`/`.sqlite` is SQLite, anything else JSON.
+    pub fn from_path(path: &Path) -> Self {
+        let name = path.file_name().map(|name| name.to_string_lossy().to_ascii_lowercase()).unwrap_or_default();
+        if name.ends_with(".trace.json") {
+            MetricsFormat::Chrome
+        } else if name.ends_with(".csv") {
+            MetricsFormat::Csv
+        } else if name.ends_with(".db") || name.ends_with(".sqlite") || name.ends_with(".sqlite3") {
+            MetricsFormat::Sqlite
+        } else {
+            MetricsFormat::Json
+        }
+    }
+}
+
+/// How many per-frame samples a session keeps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MetricsRetention {
+    /// Keep a sample for every frame
+    #[default]
+    All,
+    /// Keep at most this many samples, downsampling once the limit is reached
+    Limit(usize),
+}
+
+/// What "Current FPS" is averaged over.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FpsWindow {
+    /// The last N frames, which smooths over less time the faster the video plays
+    Frames(usize),
+    /// The frames of the last stretch of time, e.g. one second, whatever the frame rate
+    Time(Duration),
+}
+
+impl Default for FpsWindow {
+    fn default() -> Self {
+        FpsWindow::Frames(DEFAULT_FPS_WINDOW)
+    }
+}
+
+impl FromStr for FpsWindow {
+    type Err = String;
+    
+    /// A frame count like `60`, or a duration like `1s` or `500ms`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let millis = if let Some(ms) = s.strip_suffix("ms") {
+            Some(ms.trim().parse::<f64>().ok())
+        } else {
+            s.strip_suffix('s').map(|secs| secs.trim().parse::<f64>().ok().map(|secs| secs * 1000.0))
+        };
+        match millis {
+            Some(Some(millis)) if millis > 0.0 => Ok(Self::Time(Duration::from_secs_f64(millis / 1000.0))),
+            Some(_) => Err(format!("expected a positive duration like '1s' or '500ms', got '{}'", s)),
+            None => {
+                let frames: usize = s.parse().map_err(|_| format!("expected a frame count or a duration like '1s', got '{}'", s))?;
+                if frames < 2 {
+                    return Err(format!("frame count must be at least 2, got {}", frames));
+                }
+                Ok(Self::Frames(frames))
+            }
+        }
+    }
+}
+
+impl FromStr for MetricsRetention {
+    type Err = String;
+    
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.trim().eq_ignore_ascii_case("all") {
+            return Ok(Self::All);
+        }
+        let limit: usize = s.trim().parse().map_err(|_| format!("expected 'all' or a sample count, got '{}'", s))?;
+        if limit < 2 {
+            return Err(format!("sample count must be at least 2, got {}", limit));
+        }
+        Ok(Self::Limit(limit))
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FrameMetrics {
     pub frame_number: u64,
     pub timestamp: f64,
+    /// When the frame was recorded, in milliseconds since the session started
+    #[serde(default)]
+    pub session_time_ms: f64,
     pub processing_time_ms: f64,
+    /// Time spent converting the decoded frame to the output pixel format
+    #[serde(default)]
+    pub conversion_time_ms: f64,
+    /// Time spent in the deinterlacing and `--vf` filters
+    #[serde(default)]
+    pub filter_time_ms: f64,
     pub memory_usage_mb: f64,
     pub cpu_usage_percent: f64,
+    /// How old the memory and CPU reading was when the frame reused it, see `--stats-interval-ms`
+    #[serde(default)]
+    pub stats_age_ms: f64,
+    /// Average luma (0-255), only measured with `--analyze-luma`
+    #[serde(default)]
+    pub mean_luma: Option<f64>,
+    /// Compressed size of the packet matched to this frame in decode order, see `VideoFrame::packet_bytes`
+    #[serde(default)]
+    pub packet_bytes: u64,
+    /// Bitrate over the last second of video up to this frame, in kbit/s
+    #[serde(default)]
+    pub bitrate_kbps: f64,
+    /// Picture type: 'I', 'P', 'B', ... or '?' when the decoder doesn't say
+    #[serde(default)]
+    pub frame_type: char,
+    #[serde(default)]
+    pub is_keyframe: bool,
+    /// Latest device-wide GPU reading, None without `gpu-metrics` or a supported GPU
+    #[serde(default)]
+    pub gpu_usage_percent: Option<f64>,
+    #[serde(default)]
+    pub gpu_decoder_percent: Option<f64>,
+    #[serde(default)]
+    pub gpu_memory_mb: Option<f64>,
+    /// VRAM held by the player itself, None where the driver doesn't report it
+    #[serde(default)]
+    pub gpu_process_memory_mb: Option<f64>,
+    /// Time since the previous frame minus the ideal interval, None after a seek, pause or loop
+    #[serde(default)]
+    pub pacing_deviation_ms: Option<f64>,
+    /// Hex digest of the frame's pixels, only computed with `--hash-frames`
+    #[serde(default)]
+    pub frame_hash: Option<String>,
+    /// First frame after a cut found by `--detect-scenes`
+    #[serde(default)]
+    pub scene_change: bool,
+}
+
+/// The part of a session spent on one playlist item.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileSection {
+    pub path: String,
+    /// Frames recorded before this file started
+    pub first_frame: u64,
+    pub frames: u64,
+    pub duration_seconds: f64,
+    pub average_fps: f64,
+}
+
+/// A cut found by `--detect-scenes`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SceneChange {
+    pub frame: u64,
+    pub timestamp: f64,
+    /// Mean luma difference from the previous frame, on the 0-255 scale of the threshold
+    #[serde(default)]
+    pub difference: f64,
+}
+
+impl std::fmt::Display for SceneChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "Scene change at {} (frame {}, difference {:.1})",
+            format_timecode_ms(Duration::from_secs_f64(self.timestamp)),
+            self.frame,
+            self.difference
+        )
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -22,66 +230,804 @@ pub struct SessionMetrics {
     pub end_time: Option<DateTime<Utc>>,
     pub total_frames: u64,
     pub total_duration_seconds: f64,
+    /// Time spent playing, excluding pauses; `average_fps` is measured over this
+    #[serde(default)]
+    pub active_duration_seconds: f64,
     pub average_fps: f64,
+    /// Highest and lowest frame rate of the source between consecutive frames, from video timestamps
     pub max_fps: f64,
     pub min_fps: f64,
+    /// Highest and lowest rate frames were actually played at, from wall-clock time between them
+    #[serde(default)]
+    pub max_render_fps: f64,
+    #[serde(default)]
+    pub min_render_fps: f64,
+    /// Compressed video bitrate in kbit/s, from packet sizes over the video time played
+    #[serde(default)]
+    pub average_bitrate_kbps: f64,
+    /// Highest bitrate over one second of video
+    #[serde(default)]
+    pub peak_bitrate_kbps: f64,
+    /// Keyframes decoded, and frames per keyframe on average
+    #[serde(default)]
+    pub keyframes: u64,
+    #[serde(default)]
+    pub average_gop_length: Option<f64>,
+    /// Shortest and longest run of frames from one keyframe to the next, seeks and loops excluded
+    #[serde(default)]
+    pub min_gop_length: Option<f64>,
+    #[serde(default)]
+    pub max_gop_length: Option<f64>,
+    /// Mean processing time in milliseconds for each picture type, e.g. {"I": 12.5, "P": 4.1}
+    #[serde(default)]
+    pub average_frame_time_ms_by_type: BTreeMap<char, f64>,
+    /// Codec and container details of the video, the last one played for playlists
+    #[serde(default)]
+    pub video_info: Option<VideoInfo>,
+    /// Standard deviation of the wall-clock time between frames over the session; stutter raises it
+    /// even when the average FPS looks fine
+    #[serde(default)]
+    pub frametime_stddev_ms: f64,
     pub peak_memory_mb: f64,
     pub average_memory_mb: f64,
     pub average_cpu_percent: f64,
     pub peak_cpu_percent: f64,
+    /// Device-wide GPU utilization, decoder load and VRAM, if built with `gpu-metrics` and an NVIDIA GPU was found
+    #[serde(default)]
+    pub peak_gpu_percent: Option<f64>,
+    #[serde(default)]
+    pub average_gpu_percent: Option<f64>,
+    #[serde(default)]
+    pub peak_gpu_decoder_percent: Option<f64>,
+    #[serde(default)]
+    pub average_gpu_decoder_percent: Option<f64>,
+    #[serde(default)]
+    pub peak_vram_mb: Option<f64>,
+    #[serde(default)]
+    pub average_vram_mb: Option<f64>,
+    /// VRAM held by the player itself
+    #[serde(default)]
+    pub peak_process_vram_mb: Option<f64>,
+    #[serde(default)]
+    pub average_process_vram_mb: Option<f64>,
     pub dropped_frames: u64,
+    /// Packets skipped because the decoder rejected them (see `--strict`)
+    #[serde(default)]
+    pub decode_errors: u64,
+    /// Frames the decoder failed on (skipped) or flagged as damaged (shown concealed)
+    #[serde(default)]
+    pub corrupt_frames: u64,
+    /// Pixel format frames were converted to, e.g. "rgb24"
+    #[serde(default)]
+    pub output_format: String,
+    /// "swscale", or "yuv-direct" when decoded planes were used without conversion
+    #[serde(default)]
+    pub conversion_path: String,
+    #[serde(default)]
+    pub average_conversion_time_ms: f64,
+    /// Time per frame spent on `--hash-frames` digests, already part of the frame time
+    #[serde(default)]
+    pub average_hash_time_ms: Option<f64>,
+    /// Time per frame spent deinterlacing and in `--vf` filters, None when nothing was filtered
+    #[serde(default)]
+    pub average_filter_time_ms: Option<f64>,
+    /// Requested `--start` in seconds, if only part of the video was played
+    #[serde(default)]
+    pub segment_start_seconds: Option<f64>,
+    /// Requested `--end` in seconds
+    #[serde(default)]
+    pub segment_end_seconds: Option<f64>,
+    /// True when `frame_metrics` holds only a subset of frames (see `--metrics-retention`)
+    #[serde(default)]
+    pub downsampled: bool,
+    /// True when Ctrl+C stopped the session before the video ended
+    #[serde(default)]
+    pub interrupted: bool,
+    /// The `--max-frames` or `--max-duration` limit that stopped the session before the video ended
+    #[serde(default)]
+    pub truncated: Option<String>,
+    /// Frames identical to the one before, if `--detect-duplicates` was given
+    #[serde(default)]
+    pub duplicate_frames: Option<u64>,
+    /// Per-file breakdown when several videos were played in one session
+    #[serde(default)]
+    pub files: Vec<FileSection>,
+    /// Cuts found with `--detect-scenes`
+    #[serde(default)]
+    pub scene_changes: Vec<SceneChange>,
+    /// PSNR in dB against the `--compare` reference, if one was given
+    #[serde(default)]
+    pub mean_psnr_db: Option<f64>,
+    #[serde(default)]
+    pub min_psnr_db: Option<f64>,
+    /// SSIM against the `--compare` reference, if `--ssim` was given
+    #[serde(default)]
+    pub mean_ssim: Option<f64>,
+    #[serde(default)]
+    pub min_ssim: Option<f64>,
+    /// Average luma (0-255) over all frames, if `--analyze-luma` was given
+    #[serde(default)]
+    pub mean_luma: Option<f64>,
+    #[serde(default)]
+    pub min_luma: Option<f64>,
+    #[serde(default)]
+    pub max_luma: Option<f64>,
+    /// How late paced waits for `--target-fps` returned, in milliseconds
+    #[serde(default)]
+    pub mean_pacing_error_ms: Option<f64>,
+    #[serde(default)]
+    pub max_pacing_error_ms: Option<f64>,
+    /// Spread of the time between frames around the ideal interval (target FPS, else native FPS)
+    #[serde(default)]
+    pub pacing_jitter_ms: Option<f64>,
+    #[serde(default)]
+    pub mean_abs_pacing_deviation_ms: Option<f64>,
+    #[serde(default)]
+    pub max_pacing_deviation_ms: Option<f64>,
     pub frame_metrics: Vec<FrameMetrics>,
 }
 
+impl SessionMetrics {
+    /// Markdown summary of the session for pasting into PRs and issues.
+    pub fn to_markdown(&self) -> String {
+        self.markdown(None)
+    }
+    
+    /// Like `to_markdown`, with each statistic's change against `baseline`.
+    pub fn to_markdown_compared(&self, baseline: &SessionMetrics) -> String {
+        self.markdown(Some(baseline))
+    }
+    
+    fn markdown(&self, baseline: Option<&SessionMetrics>) -> String {
+        let mut out = String::from("# Benchmark Report\n\n");
+        out += &format!("Recorded {}", self.start_time.format("%Y-%m-%d %H:%M:%S UTC"));
+        if self.interrupted {
+            out += ", stopped early with Ctrl+C";
+        }
+        if let Some(limit) = &self.truncated {
+            out += &format!(", truncated at {} after {} frames", limit, self.total_frames);
+        }
+        out += ".\n\n";
+        
+        if let Some(info) = &self.video_info {
+            out += "## Video\n\n| | |\n|---|---|\n";
+            let codec = match (&info.profile, info.level) {
+                (Some(profile), Some(level)) => format!("{} ({} @ level {})", info.codec, profile, level),
+                (Some(profile), None) => format!("{} ({})", info.codec, profile),
+                (None, _) => info.codec.clone(),
+            };
+            out += &format!("| Codec | {} |\n", codec);
+            out += &format!("| Container | {} |\n", info.container_format);
+            out += &format!("| Resolution | {}x{} |\n", info.width, info.height);
+            out += &format!("| Pixel Format | {} ({}-bit) |\n", info.pixel_format, info.bit_depth);
+            if let Some(hdr) = &info.hdr {
+                out += &format!("| HDR | {}{} |\n", hdr, info.tonemap.as_ref().map_or(String::new(), |curve| format!(", tone mapped with {}", curve)));
+            }
+            out += &format!("| Native FPS | {:.2} |\n", info.native_fps);
+            out += &format!("| Duration | {:.2} s |\n", info.duration_seconds);
+            out += "\n";
+        }
+        
+        // Frame time percentiles turned around: the 1% low FPS comes from the 99th percentile frame time
+        let rows = [
+            ("Average FPS", Some(self.average_fps), 2),
+            ("Min FPS", Some(self.min_render_fps), 2),
+            ("Max FPS", Some(self.max_render_fps), 2),
+            ("1% Low FPS", self.fps_percentile(1.0), 2),
+            ("99th Percentile FPS", self.fps_percentile(99.0), 2),
+            ("Frame Time σ (ms)", Some(self.frametime_stddev_ms), 2),
+            ("Peak Memory (MB)", Some(self.peak_memory_mb), 1),
+            ("Average Memory (MB)", Some(self.average_memory_mb), 1),
+            ("Average CPU (%)", Some(self.average_cpu_percent), 1),
+            ("Peak CPU (%)", Some(self.peak_cpu_percent), 1),
+            ("Dropped Frames", Some(self.dropped_frames as f64), 0),
+            ("Total Frames", Some(self.total_frames as f64), 0),
+            ("Duration (s)", Some(self.active_duration_seconds), 2),
+        ];
+        let baseline_values = baseline.map(|baseline| [
+            Some(baseline.average_fps),
+            Some(baseline.min_render_fps),
+            Some(baseline.max_render_fps),
+            baseline.fps_percentile(1.0),
+            baseline.fps_percentile(99.0),
+            Some(baseline.frametime_stddev_ms),
+            Some(baseline.peak_memory_mb),
+            Some(baseline.average_memory_mb),
+            Some(baseline.average_cpu_percent),
+            Some(baseline.peak_cpu_percent),
+            Some(baseline.dropped_frames as f64),
+            Some(baseline.total_frames as f64),
+            Some(baseline.active_duration_seconds),
+        ]);
+        
+        let cell = |value: Option<f64>, decimals: usize| value.map_or("n/a".to_string(), |value| format!("{:.*}", decimals, value));
+        out += "## Results\n\n";
+        match &baseline_values {
+            Some(_) => out += "| Metric | Baseline | Current | Change |\n|---|---:|---:|---:|\n",
+            None => out += "| Metric | Value |\n|---|---:|\n",
+        }
+        for (index, (name, current, decimals)) in rows.into_iter().enumerate() {
+            match &baseline_values {
+                Some(baseline_values) => {
+                    let base = baseline_values[index];
+                    let change = match (current, base) {
+                        (Some(current), Some(base)) if base != 0.0 => format!("{:+.1}%", (current - base) / base * 100.0),
+                        (Some(current), Some(base)) => format!("{:+.*}", decimals, current - base),
+                        _ => "n/a".to_string(),
+                    };
+                    out += &format!("| {} | {} | {} | {} |\n", name, cell(base, decimals), cell(current, decimals), change);
+                }
+                None => out += &format!("| {} | {} |\n", name, cell(current, decimals)),
+            }
+        }
+        if self.downsampled {
+            out += "\nPercentiles are taken from a subset of frames (`--metrics-retention`).\n";
+        }
+        out
+    }
+    
+    /// FPS that `percentile` percent of frames were played below, from the recorded frame times.
+    fn fps_percentile(&self, percentile: f64) -> Option<f64> {
+        let mut times: Vec<f64> = self.frame_metrics.iter()
+            .map(|m| m.processing_time_ms)
+            .filter(|&ms| ms > 0.0)
+            .collect();
+        if times.is_empty() {
+            return None;
+        }
+        times.sort_by(f64::total_cmp);
+        // Slow frames are the low percentiles of FPS
+        let rank = ((100.0 - percentile) / 100.0 * (times.len() - 1) as f64).round() as usize;
+        Some(1000.0 / times[rank])
+    }
+}
+
+/// Statistics of average FPS across several benchmark runs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchmarkAggregate {
+    pub runs: usize,
+    pub mean_fps: f64,
+    pub stddev_fps: f64,
+    pub min_fps: f64,
+    pub max_fps: f64,
+}
+
+impl BenchmarkAggregate {
+    pub fn from_runs(runs: &[SessionMetrics]) -> Self {
+        let fps: Vec<f64> = runs.iter().map(|run| run.average_fps).collect();
+        let count = fps.len();
+        let mean_fps = if count > 0 { fps.iter().sum::<f64>() / count as f64 } else { 0.0 };
+        
+        // Sample standard deviation, zero for a single run
+        let stddev_fps = if count > 1 {
+            let variance = fps.iter().map(|f| (f - mean_fps).powi(2)).sum::<f64>() / (count - 1) as f64;
+            variance.sqrt()
+        } else {
+            0.0
+        };
+        
+        let min_fps = if count > 0 { fps.iter().copied().fold(f64::INFINITY, f64::min) } else { 0.0 };
+        
+        Self {
+            runs: count,
+            mean_fps,
+            stddev_fps,
+            min_fps,
+            max_fps: fps.iter().copied().fold(0.0, f64::max),
+        }
+    }
+    
+    /// The run whose average FPS is closest to the mean, to stand for all of them.
+    pub fn representative_run<'a>(&self, runs: &'a [SessionMetrics]) -> Option<&'a SessionMetrics> {
+        runs.iter().min_by(|a, b| (a.average_fps - self.mean_fps).abs().total_cmp(&(b.average_fps - self.mean_fps).abs()))
+    }
+}
+
+/// Exported JSON for a multi-run benchmark.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchmarkReport {
+    pub runs: Vec<SessionMetrics>,
+    pub aggregate: BenchmarkAggregate,
+}
+
+/// The figures `--baseline` compares between benchmarks.
+#[derive(Debug, Clone, Copy)]
+pub struct Baseline {
+    pub average_fps: f64,
+    pub peak_memory_mb: f64,
+}
+
+impl Baseline {
+    /// Mean FPS and highest peak memory over the runs.
+    pub fn from_runs(runs: &[SessionMetrics]) -> Self {
+        Self {
+            average_fps: BenchmarkAggregate::from_runs(runs).mean_fps,
+            peak_memory_mb: runs.iter().map(|run| run.peak_memory_mb).fold(0.0, f64::max),
+        }
+    }
+    
+    /// Read the runs of an `--export-metrics` JSON file from a single or multi-run benchmark.
+    pub fn load_runs(path: &Path) -> Result<Vec<SessionMetrics>> {
+        let json = std::fs::read_to_string(path).with_context(|| format!("Failed to read baseline {:?}", path))?;
+        if let Ok(report) = serde_json::from_str::<BenchmarkReport>(&json) {
+            return Ok(report.runs);
+        }
+        let session: SessionMetrics = serde_json::from_str(&json)
+            .with_context(|| format!("{:?} isn't a metrics file exported from a benchmark", path))?;
+        Ok(vec![session])
+    }
+    
+    /// Compare this run to `baseline`: a table to print, and whether it's within `tolerance_pct`.
+    pub fn compare(&self, baseline: &Baseline, tolerance_pct: f64, check_memory: bool) -> (String, bool) {
+        let change = |current: f64, base: f64| if base > 0.0 { (current - base) / base * 100.0 } else { 0.0 };
+        let fps_change = change(self.average_fps, baseline.average_fps);
+        let memory_change = change(self.peak_memory_mb, baseline.peak_memory_mb);
+        // Lower FPS and higher memory are the regressions
+        let fps_ok = fps_change >= -tolerance_pct;
+        let memory_ok = !check_memory || memory_change <= tolerance_pct;
+        
+        let mut table = format!("\n=== Baseline Comparison (tolerance {}%) ===\n", tolerance_pct);
+        table += &format!("{:<16}  {:>10}  {:>10}  {:>8}\n", "Metric", "Baseline", "Current", "Change");
+        table += &format!(
+            "{:<16}  {:>10.2}  {:>10.2}  {:>+7.1}%  {}\n",
+            "Average FPS", baseline.average_fps, self.average_fps, fps_change, if fps_ok { "OK" } else { "FAIL" }
+        );
+        table += &format!(
+            "{:<16}  {:>10.1}  {:>10.1}  {:>+7.1}%  {}",
+            "Peak Memory MB",
+            baseline.peak_memory_mb,
+            self.peak_memory_mb,
+            memory_change,
+            if !check_memory { "" } else if memory_ok { "OK" } else { "FAIL" }
+        );
+        
+        (table, fps_ok && memory_ok)
+    }
+}
+
+/// Mean, extremes and spread of a per-frame value, without keeping every sample.
+#[derive(Debug, Clone, Copy, Default)]
+struct RunningStat {
+    count: u64,
+    sum: f64,
+    sum_squares: f64,
+    min: f64,
+    max: f64,
+}
+
+impl RunningStat {
+    fn add(&mut self, value: f64) {
+        self.min = if self.count == 0 { value } else { self.min.min(value) };
+        self.max = if self.count == 0 { value } else { self.max.max(value) };
+        self.sum += value;
+        self.sum_squares += value * value;
+        self.count += 1;
+    }
+    
+    /// Population standard deviation.
+    fn stddev(&self) -> Option<f64> {
+        let mean = self.mean()?;
+        Some((self.sum_squares / self.count as f64 - mean * mean).max(0.0).sqrt())
+    }
+    
+    fn mean(&self) -> Option<f64> {
+        (self.count > 0).then(|| self.sum / self.count as f64)
+    }
+    
+    fn min(&self) -> Option<f64> {
+        (self.count > 0).then_some(self.min)
+    }
+    
+    fn max(&self) -> Option<f64> {
+        (self.count > 0).then_some(self.max)
+    }
+}
+
+/// Latest values, shared with readers on other threads such as the metrics endpoint.
+#[derive(Debug, Clone, Default)]
+pub struct LiveMetrics {
+    pub current_fps: f64,
+    pub average_fps: f64,
+    pub total_frames: u64,
+    pub dropped_frames: u64,
+    pub decode_errors: u64,
+    pub corrupt_frames: u64,
+    pub memory_mb: f64,
+    pub cpu_percent: f64,
+    /// Frames per `FRAME_TIME_BUCKETS` bound (not cumulative), the last entry counting slower ones
+    pub frame_time_buckets: [u64; FRAME_TIME_BUCKETS.len() + 1],
+    pub frame_time_sum_seconds: f64,
+}
+
+pub type SharedLiveMetrics = Arc<Mutex<LiveMetrics>>;
+
+/// Per-frame CSV written while frames are recorded, so an interrupted run keeps what it measured.
+pub struct CsvLog {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    unflushed_rows: u64,
+}
+
+impl CsvLog {
+    pub fn create(path: &Path) -> Result<Self> {
+        let file = File::create(path).with_context(|| format!("Failed to create {:?}", path))?;
+        let mut writer = BufWriter::new(file);
+        writeln!(writer, "{}", CSV_HEADER)?;
+        writer.flush()?;
+        Ok(Self { path: path.to_path_buf(), writer, unflushed_rows: 0 })
+    }
+    
+    fn write_row(&mut self, metrics: &FrameMetrics) -> std::io::Result<()> {
+        write_csv_row(&mut self.writer, metrics)?;
+        self.unflushed_rows += 1;
+        if self.unflushed_rows >= CSV_FLUSH_ROWS {
+            self.flush()?;
+        }
+        Ok(())
+    }
+    
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.unflushed_rows = 0;
+        self.writer.flush()
+    }
+}
+
+/// Shared so every collector of a multi-run benchmark appends to the same file.
+pub type SharedCsvLog = Arc<Mutex<CsvLog>>;
+
+/// JSON lines appended by a background thread, so a slow disk never stalls the decode loop.
+pub struct MetricsStream {
+    sender: Option<mpsc::Sender<String>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+pub type SharedMetricsStream = Arc<MetricsStream>;
+
+impl MetricsStream {
+    pub fn create(path: &Path) -> Result<Self> {
+        let file = File::create(path).with_context(|| format!("Failed to create {:?}", path))?;
+        let path = path.to_path_buf();
+        let (sender, receiver) = mpsc::channel::<String>();
+        let handle = std::thread::spawn(move || {
+            let mut writer = BufWriter::new(file);
+            let mut last_flush = Instant::now();
+            loop {
+                let result = match receiver.recv_timeout(STREAM_FLUSH_INTERVAL) {
+                    Ok(line) => writeln!(writer, "{}", line),
+                    Err(RecvTimeoutError::Timeout) => Ok(()),
+                    Err(RecvTimeoutError::Disconnected) => break,
+                };
+                let result = result.and_then(|()| {
+                    if last_flush.elapsed() < STREAM_FLUSH_INTERVAL {
+                        return Ok(());
+                    }
+                    last_flush = Instant::now();
+                    writer.flush()
+                });
+                if let Err(e) = result {
+                    // Dropping the receiver makes further sends no-ops
+                    log::warn!("Disabling metrics stream {:?}: {}", path, e);
+                    return;
+                }
+            }
+            if let Err(e) = writer.flush() {
+                log::warn!("Failed to flush metrics stream {:?}: {}", path, e);
+            }
+        });
+        Ok(Self { sender: Some(sender), handle: Some(handle) })
+    }
+    
+    fn send(&self, line: String) {
+        if let Some(sender) = &self.sender {
+            let _ = sender.send(line);
+        }
+    }
+}
+
+impl Drop for MetricsStream {
+    /// Write out everything queued before returning.
+    fn drop(&mut self) {
+        self.sender = None;
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+fn write_csv_row(writer: &mut impl Write, m: &FrameMetrics) -> std::io::Result<()> {
+    writeln!(
+        writer,
+        "{},{:.6},{:.3},{:.3},{:.2},{:.2},{},{:.1},{},{},{:.1},{:.3}",
+        m.frame_number, m.timestamp, m.processing_time_ms, m.conversion_time_ms, m.memory_usage_mb, m.cpu_usage_percent,
+        m.packet_bytes, m.bitrate_kbps, m.frame_type, m.is_keyframe, m.stats_age_ms, m.filter_time_ms
+    )
+}
+
+/// Turns recorded frames into FPS, frame time, memory and CPU statistics for one session.
 pub struct MetricsCollector {
     session_start: Instant,
     session_start_utc: DateTime<Utc>,
     frame_times: VecDeque<(Instant, u64)>, // (timestamp, frame_number)
     frame_metrics: Vec<FrameMetrics>,
+    retention: MetricsRetention,
+    // Only every Nth frame (plus outliers) is sampled once downsampling kicks in
+    sample_stride: u64,
+    downsampled: bool,
+    interrupted: bool,
+    truncated: Option<String>,
     
     // System monitoring
     system: System,
-    current_pid: Pid,
+    current_pid: Option<Pid>,
+    stats_interval: Duration,
+    // Last process reading: (when, memory MB, CPU percent)
+    process_stats: Option<(Instant, f64, f64)>,
     
     // Running statistics
     total_frames: u64,
     peak_memory_mb: f64,
     peak_cpu_percent: f64,
+    gpu: Option<GpuMonitor>,
+    gpu_usage: RunningStat,
+    gpu_decoder: RunningStat,
+    gpu_memory: RunningStat,
+    gpu_process_memory: RunningStat,
+    hash_time: RunningStat,
+    filter_time: RunningStat,
     dropped_frames: u64,
+    decode_errors: u64,
+    corrupt_frames: u64,
+    output_format: Option<OutputFormat>,
+    conversion_path: Option<&'static str>,
+    segment: (Option<Duration>, Option<Duration>),
+    live: Option<SharedLiveMetrics>,
+    csv_log: Option<SharedCsvLog>,
+    // --metrics-stream and how many frames apart its samples are
+    stream: Option<(SharedMetricsStream, u64)>,
+    
+    // Kept incrementally so they stay exact when samples are discarded
+    processing_time_sum_ms: f64,
+    conversion_time_sum_ms: f64,
+    memory_sum_mb: f64,
+    cpu_sum_percent: f64,
+    max_source_fps: f64,
+    min_source_fps: f64,
+    max_render_fps: f64,
+    min_render_fps: f64,
+    last_timestamp: Option<f64>,
+    frame_time_histogram: BTreeMap<u64, u64>, // processing time in µs -> frame count
+    frame_interval: RunningStat, // Wall-clock ms between consecutive frames, discontinuities excluded
+    frame_time_by_type: BTreeMap<char, RunningStat>, // Processing ms per picture type
+    keyframes: u64,
+    frames_since_keyframe: Option<u64>, // None before the first keyframe and after a discontinuity
+    gop_length: RunningStat,
+    bitrate_window: VecDeque<(f64, u64)>, // (video timestamp, packet bytes) over the last BITRATE_WINDOW
+    packet_bytes_sum: u64,
+    media_seconds_sum: f64, // Video time between recorded frames, for the average bitrate
+    peak_bitrate_kbps: f64,
+    current_bitrate_kbps: f64, // Latest frame's packet over the time since the previous one
+    smoothed_bitrate_kbps: f64, // Over the last BITRATE_WINDOW
+    video_info: Option<VideoInfo>,
+    run_label: String, // Names the run in SQLite exports
+    psnr: RunningStat,
+    ssim: RunningStat,
+    // Per-frame mean luma, only measured with --analyze-luma since it reads every pixel
+    analyze_luma: bool,
+    luma: RunningStat,
+    // Overshoot of each paced wait in ms, see VideoPlayer::take_pacing_error
+    pacing_error: RunningStat,
+    // Signed and absolute deviation in ms of each frame interval from the ideal one
+    pacing_deviation: RunningStat,
+    abs_pacing_deviation: RunningStat,
+    // Only counted when frames carry a content hash (--detect-duplicates)
+    duplicate_frames: Option<u64>,
+    last_content_hash: Option<u64>,
+    // Mean absolute luma difference (0-255) that counts as a cut, None when not detecting
+    scene_threshold: Option<f64>,
+    last_thumbnail: Option<Vec<u8>>,
+    scene_changes: Vec<SceneChange>,
+    // Playlist items finished so far, and the one playing: (path, frames before it, start time)
+    files: Vec<FileSection>,
+    current_file: Option<(String, u64, Instant)>,
     
     // FPS calculation window (last N frames)
-    fps_window_size: usize,
+    fps_window: FpsWindow,
     last_frame_time: Option<Instant>,
+    // Paused time is left out of the average FPS
+    paused_at: Option<Instant>,
+    paused_duration: Duration,
 }
 
 impl MetricsCollector {
-    pub fn new() -> Self {
+    /// Process memory and CPU are read at most every `stats_interval` and reused for the frames in
+    /// between: refreshing them costs enough to skew high frame rates, and CPU usage measured over
+    /// a few milliseconds is mostly noise. Shorter intervals catch brief spikes at that cost.
+    pub fn new(retention: MetricsRetention, stats_interval: Duration) -> Self {
         let mut system = System::new_with_specifics(
             RefreshKind::new().with_processes(ProcessRefreshKind::everything())
         );
         system.refresh_all();
         
-        let current_pid = sysinfo::get_current_pid().unwrap();
+        // Without a PID memory and CPU read as 0, the frame metrics still work
+        let current_pid = sysinfo::get_current_pid()
+            .map_err(|e| log::warn!("Can't monitor memory and CPU usage: {}", e))
+            .ok();
         
         Self {
             session_start: Instant::now(),
             session_start_utc: Utc::now(),
             frame_times: VecDeque::new(),
             frame_metrics: Vec::new(),
+            retention,
+            sample_stride: 1,
+            downsampled: false,
+            interrupted: false,
+            truncated: None,
             system,
             current_pid,
+            stats_interval,
+            process_stats: None,
             total_frames: 0,
             peak_memory_mb: 0.0,
             peak_cpu_percent: 0.0,
+            gpu: GpuMonitor::start(),
+            gpu_usage: RunningStat::default(),
+            gpu_decoder: RunningStat::default(),
+            gpu_memory: RunningStat::default(),
+            gpu_process_memory: RunningStat::default(),
+            hash_time: RunningStat::default(),
+            filter_time: RunningStat::default(),
             dropped_frames: 0,
-            fps_window_size: 60, // Calculate FPS over last 60 frames
+            decode_errors: 0,
+            corrupt_frames: 0,
+            output_format: None,
+            conversion_path: None,
+            segment: (None, None),
+            live: None,
+            csv_log: None,
+            stream: None,
+            processing_time_sum_ms: 0.0,
+            conversion_time_sum_ms: 0.0,
+            memory_sum_mb: 0.0,
+            cpu_sum_percent: 0.0,
+            max_source_fps: 0.0,
+            min_source_fps: f64::INFINITY,
+            max_render_fps: 0.0,
+            min_render_fps: f64::INFINITY,
+            last_timestamp: None,
+            frame_time_histogram: BTreeMap::new(),
+            frame_interval: RunningStat::default(),
+            frame_time_by_type: BTreeMap::new(),
+            keyframes: 0,
+            frames_since_keyframe: None,
+            gop_length: RunningStat::default(),
+            bitrate_window: VecDeque::new(),
+            packet_bytes_sum: 0,
+            media_seconds_sum: 0.0,
+            peak_bitrate_kbps: 0.0,
+            current_bitrate_kbps: 0.0,
+            smoothed_bitrate_kbps: 0.0,
+            video_info: None,
+            psnr: RunningStat::default(),
+            ssim: RunningStat::default(),
+            analyze_luma: false,
+            run_label: "run".to_string(),
+            luma: RunningStat::default(),
+            pacing_error: RunningStat::default(),
+            pacing_deviation: RunningStat::default(),
+            abs_pacing_deviation: RunningStat::default(),
+            duplicate_frames: None,
+            last_content_hash: None,
+            scene_threshold: None,
+            last_thumbnail: None,
+            scene_changes: Vec::new(),
+            files: Vec::new(),
+            current_file: None,
+            fps_window: FpsWindow::default(),
             last_frame_time: None,
+            paused_at: None,
+            paused_duration: Duration::ZERO,
         }
     }
     
-    pub fn record_frame(&mut self, frame_number: u64, frame: &VideoFrame) {
-        let now = Instant::now();
-        
+    /// Average "Current FPS" over `window` instead of the last 60 frames.
+    pub fn with_window_size(mut self, window: FpsWindow) -> Self {
+        // Fewer than two frames have no FPS
+        self.fps_window = match window {
+            FpsWindow::Frames(size) => FpsWindow::Frames(size.max(2)),
+            window => window,
+        };
+        self
+    }
+    
+    /// Clear all accumulated statistics and start a new session from now.
+    pub fn reset(&mut self) {
+        self.stream_summary();
+        self.session_start = Instant::now();
+        self.session_start_utc = Utc::now();
+        self.frame_times.clear();
+        self.frame_metrics.clear();
+        self.sample_stride = 1;
+        self.downsampled = false;
+        self.interrupted = false;
+        self.truncated = None;
+        self.total_frames = 0;
+        self.peak_memory_mb = 0.0;
+        self.peak_cpu_percent = 0.0;
+        self.gpu_usage = RunningStat::default();
+        self.gpu_decoder = RunningStat::default();
+        self.gpu_memory = RunningStat::default();
+        self.gpu_process_memory = RunningStat::default();
+        self.hash_time = RunningStat::default();
+        self.filter_time = RunningStat::default();
+        self.dropped_frames = 0;
+        self.decode_errors = 0;
+        self.corrupt_frames = 0;
+        self.output_format = None;
+        self.conversion_path = None;
+        self.processing_time_sum_ms = 0.0;
+        self.conversion_time_sum_ms = 0.0;
+        self.memory_sum_mb = 0.0;
+        self.cpu_sum_percent = 0.0;
+        self.max_source_fps = 0.0;
+        self.min_source_fps = f64::INFINITY;
+        self.max_render_fps = 0.0;
+        self.min_render_fps = f64::INFINITY;
+        self.last_timestamp = None;
+        self.frame_time_histogram.clear();
+        self.frame_interval = RunningStat::default();
+        self.frame_time_by_type.clear();
+        self.keyframes = 0;
+        self.frames_since_keyframe = None;
+        self.gop_length = RunningStat::default();
+        self.bitrate_window.clear();
+        self.packet_bytes_sum = 0;
+        self.media_seconds_sum = 0.0;
+        self.peak_bitrate_kbps = 0.0;
+        self.current_bitrate_kbps = 0.0;
+        self.smoothed_bitrate_kbps = 0.0;
+        self.psnr = RunningStat::default();
+        self.ssim = RunningStat::default();
+        self.luma = RunningStat::default();
+        self.pacing_error = RunningStat::default();
+        self.pacing_deviation = RunningStat::default();
+        self.abs_pacing_deviation = RunningStat::default();
+        self.duplicate_frames = None;
+        self.last_content_hash = None;
+        self.last_thumbnail = None;
+        self.scene_changes.clear();
+        self.files.clear();
+        self.current_file = None;
+        self.last_frame_time = None;
+        // A reset during a pause starts the new session paused
+        self.paused_at = self.paused_at.map(|_| Instant::now());
+        self.paused_duration = Duration::ZERO;
+        if let Some(live) = &self.live {
+            if let Ok(mut live) = live.lock() {
+                *live = LiveMetrics::default();
+            }
+        }
+    }
+    
+    /// Forget the previous frame after a seek or pause, so the gap isn't recorded as one slow frame.
+    pub fn mark_discontinuity(&mut self) {
+        self.last_frame_time = None;
+        self.last_content_hash = None;
+        // A seek isn't a cut
+        self.last_thumbnail = None;
+        self.last_timestamp = None;
+        self.frame_times.clear();
+        // Frames were skipped, so the current GOP's length is unknown
+        self.frames_since_keyframe = None;
+    }
+    
+    /// Returns the cut this frame starts when `--detect-scenes` finds one, for the caller to report.
+    pub fn record_frame(&mut self, frame_number: u64, frame: &VideoFrame) -> Option<SceneChange> {
+        self.record_frame_at(frame_number, frame, Instant::now())
+    }
+    
+    /// `record_frame` with the wall-clock time passed in, so tests can control frame spacing.
+    fn record_frame_at(&mut self, frame_number: u64, frame: &VideoFrame, now: Instant) -> Option<SceneChange> {
         // Calculate processing time (for now, just the time since last frame)
         let processing_time = if let Some(last_time) = self.last_frame_time {
             now.duration_since(last_time)
@@ -89,56 +1035,184 @@ impl MetricsCollector {
             Duration::from_millis(0)
         };
         
-        // Update system info
-        self.system.refresh_processes_specifics(ProcessRefreshKind::new().with_memory().with_cpu());
-        
-        let memory_usage_mb = if let Some(process) = self.system.process(self.current_pid) {
-            process.memory() as f64 / 1024.0 / 1024.0 // Convert from KB to MB
-        } else {
-            0.0
-        };
-        
-        let cpu_usage_percent = if let Some(process) = self.system.process(self.current_pid) {
-            process.cpu_usage() as f64
-        } else {
-            0.0
-        };
+        let (memory_usage_mb, cpu_usage_percent) = self.process_stats(now);
+        let stats_age = self.process_stats.map(|(read_at, ..)| now.saturating_duration_since(read_at)).unwrap_or_default();
         
         // Update peak values
         self.peak_memory_mb = self.peak_memory_mb.max(memory_usage_mb);
         self.peak_cpu_percent = self.peak_cpu_percent.max(cpu_usage_percent);
+        // The monitor polls in the background, frames between polls reuse its last reading
+        let gpu = self.gpu.as_ref().and_then(GpuMonitor::latest);
+        if let Some(sample) = gpu {
+            self.gpu_usage.add(sample.utilization_percent);
+            self.gpu_decoder.add(sample.decoder_percent);
+            self.gpu_memory.add(sample.memory_used_mb);
+            if let Some(process_memory_mb) = sample.process_memory_mb {
+                self.gpu_process_memory.add(process_memory_mb);
+            }
+        }
+        
+        if frame.digest.is_some() {
+            self.hash_time.add(frame.hash_time.as_secs_f64() * 1000.0);
+        }
+        // Frames yadif hands out without new input take no time, so once filtering starts all count
+        if !frame.filter_time.is_zero() || self.filter_time.mean().is_some() {
+            self.filter_time.add(frame.filter_time.as_secs_f64() * 1000.0);
+        }
+        
+        let mean_luma = self.analyze_luma.then(|| frame.mean_luma());
+        let scene_change = self.scene_threshold.and_then(|threshold| self.detect_scene_change(frame_number, frame, threshold));
+        if let Some(luma) = mean_luma {
+            self.luma.add(luma);
+        }
+        let bitrate_kbps = self.update_bitrate(frame.timestamp.as_secs_f64(), frame.packet_bytes as u64);
+        // The first frame after a discontinuity starts a new baseline instead of counting the gap
+        let pacing_deviation_ms = (!processing_time.is_zero())
+            .then(|| (processing_time.as_secs_f64() - frame.ideal_interval.as_secs_f64()) * 1000.0);
         
         // Record frame metrics
         let frame_metrics = FrameMetrics {
             frame_number,
             timestamp: frame.timestamp.as_secs_f64(),
+            session_time_ms: now.duration_since(self.session_start).as_secs_f64() * 1000.0,
             processing_time_ms: processing_time.as_secs_f64() * 1000.0,
+            conversion_time_ms: frame.conversion_time.as_secs_f64() * 1000.0,
+            filter_time_ms: frame.filter_time.as_secs_f64() * 1000.0,
             memory_usage_mb,
             cpu_usage_percent,
+            stats_age_ms: stats_age.as_secs_f64() * 1000.0,
+            mean_luma,
+            packet_bytes: frame.packet_bytes as u64,
+            bitrate_kbps,
+            frame_type: frame.frame_type,
+            is_keyframe: frame.is_keyframe,
+            gpu_usage_percent: gpu.map(|sample| sample.utilization_percent),
+            gpu_decoder_percent: gpu.map(|sample| sample.decoder_percent),
+            gpu_memory_mb: gpu.map(|sample| sample.memory_used_mb),
+            gpu_process_memory_mb: gpu.and_then(|sample| sample.process_memory_mb),
+            pacing_deviation_ms,
+            frame_hash: frame.digest.clone(),
+            scene_change: scene_change.is_some(),
         };
         
-        self.frame_metrics.push(frame_metrics);
+        self.update_statistics(&frame_metrics);
+        self.log_csv_row(&frame_metrics);
+        self.stream_frame(&frame_metrics);
+        self.store_sample(frame_metrics);
+        if let Some(hash) = frame.content_hash {
+            let duplicates = self.duplicate_frames.get_or_insert(0);
+            if self.last_content_hash == Some(hash) {
+                *duplicates += 1;
+            }
+            self.last_content_hash = Some(hash);
+        }
+        self.output_format = Some(frame.format);
+        self.conversion_path = Some(if frame.passthrough { "yuv-direct" } else { "swscale" });
         
         // Update FPS calculation window
         self.frame_times.push_back((now, frame_number));
-        if self.frame_times.len() > self.fps_window_size {
-            self.frame_times.pop_front();
+        match self.fps_window {
+            FpsWindow::Frames(size) => {
+                while self.frame_times.len() > size {
+                    self.frame_times.pop_front();
+                }
+            }
+            // Two frames are the least an FPS can be measured from, however old
+            FpsWindow::Time(window) => {
+                while self.frame_times.len() > 2
+                    && self.frame_times.front().is_some_and(|(time, _)| now.duration_since(*time) > window)
+                {
+                    self.frame_times.pop_front();
+                }
+            }
         }
         
         self.total_frames += 1;
         self.last_frame_time = Some(now);
+        
+        if let Some(live) = &self.live {
+            if let Ok(mut live) = live.lock() {
+                live.current_fps = self.get_current_fps();
+                live.average_fps = self.get_average_fps();
+                live.total_frames = self.total_frames;
+                live.dropped_frames = self.dropped_frames;
+                live.decode_errors = self.decode_errors;
+                live.corrupt_frames = self.corrupt_frames;
+                live.memory_mb = memory_usage_mb;
+                live.cpu_percent = cpu_usage_percent;
+                
+                let seconds = processing_time.as_secs_f64();
+                let bucket = FRAME_TIME_BUCKETS.iter().position(|&bound| seconds <= bound).unwrap_or(FRAME_TIME_BUCKETS.len());
+                live.frame_time_buckets[bucket] += 1;
+                live.frame_time_sum_seconds += seconds;
+            }
+        }
+        scene_change
+    }
+    
+    /// Keep `live` updated with every recorded frame.
+    pub fn publish_to(&mut self, live: SharedLiveMetrics) {
+        self.live = Some(live);
+    }
+    
+    /// Append a row to `log` for every recorded frame.
+    pub fn log_csv_to(&mut self, log: SharedCsvLog) {
+        self.csv_log = Some(log);
+    }
+    
+    /// Send every `every`th frame to `stream`, and the session summary when the session ends.
+    pub fn stream_to(&mut self, stream: SharedMetricsStream, every: u64) {
+        self.stream = Some((stream, every));
+    }
+    
+    fn stream_frame(&self, metrics: &FrameMetrics) {
+        let Some((stream, every)) = &self.stream else {
+            return;
+        };
+        if self.total_frames % every == 0 {
+            if let Ok(line) = serde_json::to_string(metrics) {
+                stream.send(line);
+            }
+        }
+    }
+    
+    /// Close the streamed session with its summary, i.e. `SessionMetrics` without `frame_metrics`.
+    fn stream_summary(&mut self) {
+        if self.stream.is_none() || self.total_frames == 0 {
+            return;
+        }
+        let Ok(serde_json::Value::Object(mut summary)) = serde_json::to_value(self.session_summary()) else {
+            return;
+        };
+        summary.remove("frame_metrics");
+        if let Some((stream, _)) = &self.stream {
+            stream.send(serde_json::Value::Object(summary).to_string());
+        }
+    }
+    
+    fn log_csv_row(&mut self, metrics: &FrameMetrics) {
+        let Some(csv_log) = &self.csv_log else {
+            return;
+        };
+        let Ok(mut csv_log) = csv_log.lock() else {
+            return;
+        };
+        if let Err(e) = csv_log.write_row(metrics) {
+            // A full disk won't fix itself, stop instead of warning on every frame
+            log::warn!("Disabling live CSV log {:?}: {}", csv_log.path, e);
+            drop(csv_log);
+            self.csv_log = None;
+        }
     }
     
     pub fn get_current_fps(&self) -> f64 {
-        if self.frame_times.len() < 2 {
+        let (Some((first_time, _)), Some((last_time, _))) = (self.frame_times.front(), self.frame_times.back()) else {
             return 0.0;
-        }
-        
-        let (first_time, first_frame) = self.frame_times.front().unwrap();
-        let (last_time, last_frame) = self.frame_times.back().unwrap();
+        };
         
+        // Count presented frames rather than diffing frame numbers, which jump on seeks and loops
         let time_diff = last_time.duration_since(*first_time).as_secs_f64();
-        let frame_diff = last_frame - first_frame;
+        let frame_diff = self.frame_times.len() - 1;
         
         if time_diff > 0.0 {
             frame_diff as f64 / time_diff
@@ -147,8 +1221,22 @@ impl MetricsCollector {
         }
     }
     
+    /// Standard deviation of the time between frames in the current FPS window, in milliseconds.
+    pub fn get_frametime_stddev_ms(&self) -> f64 {
+        let mut window = RunningStat::default();
+        for (previous, next) in self.frame_times.iter().zip(self.frame_times.iter().skip(1)) {
+            window.add(next.0.duration_since(previous.0).as_secs_f64() * 1000.0);
+        }
+        window.stddev().unwrap_or(0.0)
+    }
+    
+    /// Standard deviation of the time between frames over the whole session, in milliseconds.
+    pub fn get_session_frametime_stddev_ms(&self) -> f64 {
+        self.frame_interval.stddev().unwrap_or(0.0)
+    }
+    
     pub fn get_average_fps(&self) -> f64 {
-        let elapsed = self.session_start.elapsed().as_secs_f64();
+        let elapsed = self.get_active_duration().as_secs_f64();
         if elapsed > 0.0 {
             self.total_frames as f64 / elapsed
         } else {
@@ -156,32 +1244,255 @@ impl MetricsCollector {
         }
     }
     
-    pub fn get_max_fps(&self) -> f64 {
-        self.frame_metrics
-            .windows(2)
-            .map(|window| {
-                let time_diff = window[1].timestamp - window[0].timestamp;
-                if time_diff > 0.0 {
-                    1.0 / time_diff
-                } else {
-                    0.0
-                }
-            })
-            .fold(0.0, f64::max)
+    /// Add a frame's packet and return the bitrate over the last `BITRATE_WINDOW` of video, in kbit/s.
+    fn update_bitrate(&mut self, timestamp: f64, packet_bytes: u64) -> f64 {
+        self.packet_bytes_sum += packet_bytes;
+        
+        // Seeks and loops jump back in time, start the window over
+        if self.bitrate_window.back().is_some_and(|&(last, _)| timestamp < last) {
+            self.bitrate_window.clear();
+        }
+        // The packet over the time since the previous frame; keyframes spike far above the average
+        self.current_bitrate_kbps = match self.bitrate_window.back() {
+            Some(&(last, _)) if timestamp > last => packet_bytes as f64 * 8.0 / (timestamp - last) / 1000.0,
+            _ => 0.0,
+        };
+        self.bitrate_window.push_back((timestamp, packet_bytes));
+        while self.bitrate_window.front().is_some_and(|&(first, _)| timestamp - first > BITRATE_WINDOW) {
+            self.bitrate_window.pop_front();
+        }
+        
+        // Measured from the oldest frame, so its own packet falls before the interval
+        self.smoothed_bitrate_kbps = match self.bitrate_window.front() {
+            Some(&(first, _)) if timestamp > first => {
+                let bytes: u64 = self.bitrate_window.iter().skip(1).map(|&(_, bytes)| bytes).sum();
+                bytes as f64 * 8.0 / (timestamp - first) / 1000.0
+            }
+            _ => 0.0,
+        };
+        self.peak_bitrate_kbps = self.peak_bitrate_kbps.max(self.smoothed_bitrate_kbps);
+        self.smoothed_bitrate_kbps
     }
     
-    pub fn get_min_fps(&self) -> f64 {
-        self.frame_metrics
-            .windows(2)
-            .map(|window| {
-                let time_diff = window[1].timestamp - window[0].timestamp;
-                if time_diff > 0.0 {
-                    1.0 / time_diff
-                } else {
-                    f64::INFINITY
-                }
-            })
-            .fold(f64::INFINITY, f64::min)
+    pub fn get_keyframes(&self) -> u64 {
+        self.keyframes
+    }
+    
+    /// Frames per keyframe, None before the first keyframe.
+    pub fn get_average_gop_length(&self) -> Option<f64> {
+        (self.keyframes > 0).then(|| self.total_frames as f64 / self.keyframes as f64)
+    }
+    
+    /// Shortest and longest distance between consecutive keyframes in frames, None before the second keyframe.
+    pub fn get_gop_length_range(&self) -> Option<(f64, f64)> {
+        Some((self.gop_length.min()?, self.gop_length.max()?))
+    }
+    
+    /// Mean processing time in milliseconds for each picture type seen.
+    pub fn get_average_frame_time_by_type(&self) -> BTreeMap<char, f64> {
+        self.frame_time_by_type
+            .iter()
+            .filter_map(|(&frame_type, stat)| Some((frame_type, stat.mean()?)))
+            .collect()
+    }
+    
+    /// (processing time in ms, keyframe, scene change) of the last `count` kept samples, oldest first.
+    pub fn frame_time_series(&self, count: usize) -> Vec<(f64, bool, bool)> {
+        let skip = self.frame_metrics.len().saturating_sub(count);
+        self.frame_metrics.iter().skip(skip).map(|m| (m.processing_time_ms, m.is_keyframe, m.scene_change)).collect()
+    }
+    
+    /// Deviation in ms from the ideal frame interval of the last `count` kept samples, oldest first.
+    /// Frames after a discontinuity count as on time.
+    pub fn pacing_deviation_series(&self, count: usize) -> Vec<f64> {
+        let skip = self.frame_metrics.len().saturating_sub(count);
+        self.frame_metrics.iter().skip(skip).map(|m| m.pacing_deviation_ms.unwrap_or(0.0)).collect()
+    }
+    
+    /// Standard deviation of the frame interval around the ideal one, in milliseconds.
+    pub fn get_pacing_jitter_ms(&self) -> Option<f64> {
+        self.pacing_deviation.stddev()
+    }
+    
+    pub fn get_mean_abs_pacing_deviation_ms(&self) -> Option<f64> {
+        self.abs_pacing_deviation.mean()
+    }
+    
+    /// Worst deviation from the ideal frame interval either way, in milliseconds.
+    pub fn get_max_pacing_deviation_ms(&self) -> Option<f64> {
+        self.abs_pacing_deviation.max()
+    }
+    
+    /// Compressed bitrate over the video time played so far, in kbit/s.
+    pub fn get_average_bitrate_kbps(&self) -> f64 {
+        if self.media_seconds_sum > 0.0 {
+            self.packet_bytes_sum as f64 * 8.0 / self.media_seconds_sum / 1000.0
+        } else {
+            0.0
+        }
+    }
+    
+    pub fn get_peak_bitrate_kbps(&self) -> f64 {
+        self.peak_bitrate_kbps
+    }
+    
+    /// Bitrate of the latest frame alone: its packet over the time since the previous frame, in kbit/s.
+    pub fn get_current_bitrate_kbps(&self) -> f64 {
+        self.current_bitrate_kbps
+    }
+    
+    /// Bitrate over the last second of video, which evens out keyframe spikes, in kbit/s.
+    pub fn get_smoothed_bitrate_kbps(&self) -> f64 {
+        self.smoothed_bitrate_kbps
+    }
+    
+    /// Note the codec and container details of the video being played, for the exported metrics.
+    pub fn set_video_info(&mut self, info: VideoInfo) {
+        self.video_info = Some(info);
+    }
+    
+    /// Fold a frame into the running statistics, which cover every frame regardless of retention.
+    fn update_statistics(&mut self, metrics: &FrameMetrics) {
+        if let Some(last_timestamp) = self.last_timestamp {
+            let time_diff = metrics.timestamp - last_timestamp;
+            if time_diff > 0.0 {
+                self.max_source_fps = self.max_source_fps.max(1.0 / time_diff);
+                self.min_source_fps = self.min_source_fps.min(1.0 / time_diff);
+                self.media_seconds_sum += time_diff;
+            }
+        }
+        self.last_timestamp = Some(metrics.timestamp);
+        // The first frame after a discontinuity has no predecessor to measure against
+        if metrics.processing_time_ms > 0.0 {
+            let render_fps = 1000.0 / metrics.processing_time_ms;
+            self.max_render_fps = self.max_render_fps.max(render_fps);
+            self.min_render_fps = self.min_render_fps.min(render_fps);
+            self.frame_interval.add(metrics.processing_time_ms);
+            self.frame_time_by_type.entry(metrics.frame_type).or_default().add(metrics.processing_time_ms);
+        }
+        if let Some(deviation) = metrics.pacing_deviation_ms {
+            self.pacing_deviation.add(deviation);
+            self.abs_pacing_deviation.add(deviation.abs());
+        }
+        
+        if metrics.is_keyframe {
+            self.keyframes += 1;
+            if let Some(frames) = self.frames_since_keyframe {
+                self.gop_length.add(frames as f64);
+            }
+            self.frames_since_keyframe = Some(1);
+        } else if let Some(frames) = &mut self.frames_since_keyframe {
+            *frames += 1;
+        }
+        self.processing_time_sum_ms += metrics.processing_time_ms;
+        self.conversion_time_sum_ms += metrics.conversion_time_ms;
+        self.memory_sum_mb += metrics.memory_usage_mb;
+        self.cpu_sum_percent += metrics.cpu_usage_percent;
+        
+        let micros = (metrics.processing_time_ms * 1000.0).round() as u64;
+        *self.frame_time_histogram.entry(micros).or_insert(0) += 1;
+    }
+    
+    fn is_outlier(&self, metrics: &FrameMetrics) -> bool {
+        metrics.processing_time_ms > OUTLIER_FACTOR * self.get_average_frame_time_ms()
+    }
+    
+    /// Keep the per-frame sample if the retention policy allows it.
+    fn store_sample(&mut self, metrics: FrameMetrics) {
+        let MetricsRetention::Limit(limit) = self.retention else {
+            self.frame_metrics.push(metrics);
+            return;
+        };
+        
+        if self.total_frames % self.sample_stride != 0 && !self.is_outlier(&metrics) {
+            return;
+        }
+        self.frame_metrics.push(metrics);
+        
+        if self.frame_metrics.len() > limit {
+            self.downsample(limit);
+        }
+    }
+    
+    /// Halve the sampling rate, dropping every other stored sample except outliers.
+    fn downsample(&mut self, limit: usize) {
+        if !self.downsampled {
+            log::info!("More than {} frames recorded, downsampling per-frame metrics", limit);
+        }
+        self.sample_stride *= 2;
+        self.downsampled = true;
+        
+        let threshold = OUTLIER_FACTOR * self.get_average_frame_time_ms();
+        let mut index = 0;
+        self.frame_metrics.retain(|m| {
+            index += 1;
+            index % 2 == 1 || m.processing_time_ms > threshold
+        });
+        
+        // A run of outliers can still exceed the limit, so fall back to plain decimation
+        while self.frame_metrics.len() > limit {
+            let mut index = 0;
+            self.frame_metrics.retain(|_| {
+                index += 1;
+                index % 2 == 1
+            });
+        }
+    }
+    
+    /// Highest frame rate of the source between consecutive frames, from video timestamps.
+    /// This reflects how the video was encoded, not how fast it plays; see `get_max_render_fps`.
+    pub fn get_max_source_fps(&self) -> f64 {
+        self.max_source_fps
+    }
+    
+    /// Lowest source FPS between consecutive frames, 0.0 until two frames with distinct timestamps were seen.
+    pub fn get_min_source_fps(&self) -> f64 {
+        if self.min_source_fps.is_finite() { self.min_source_fps } else { 0.0 }
+    }
+    
+    /// Highest rate frames were played at, from the wall-clock time between consecutive frames.
+    pub fn get_max_render_fps(&self) -> f64 {
+        self.max_render_fps
+    }
+    
+    /// Lowest rate frames were played at, 0.0 until two consecutive frames were recorded.
+    pub fn get_min_render_fps(&self) -> f64 {
+        if self.min_render_fps.is_finite() { self.min_render_fps } else { 0.0 }
+    }
+    
+    /// Mean frame processing time over all frames.
+    pub fn get_average_frame_time_ms(&self) -> f64 {
+        if self.total_frames == 0 {
+            0.0
+        } else {
+            self.processing_time_sum_ms / self.total_frames as f64
+        }
+    }
+    
+    /// Frame processing time (ms) below which `percentile` percent of frames fall.
+    pub fn get_frame_time_percentile(&self, percentile: f64) -> f64 {
+        if self.total_frames == 0 {
+            return 0.0;
+        }
+        
+        let rank = (percentile / 100.0 * (self.total_frames - 1) as f64).round() as u64;
+        let mut seen = 0;
+        for (&micros, &count) in &self.frame_time_histogram {
+            seen += count;
+            if seen > rank {
+                return micros as f64 / 1000.0;
+            }
+        }
+        0.0
+    }
+    
+    /// Mean time per frame spent converting to the output pixel format.
+    pub fn get_average_conversion_time_ms(&self) -> f64 {
+        if self.total_frames == 0 {
+            0.0
+        } else {
+            self.conversion_time_sum_ms / self.total_frames as f64
+        }
     }
     
     pub fn get_peak_memory_mb(&self) -> f64 {
@@ -189,12 +1500,10 @@ impl MetricsCollector {
     }
     
     pub fn get_average_memory_mb(&self) -> f64 {
-        if self.frame_metrics.is_empty() {
+        if self.total_frames == 0 {
             0.0
         } else {
-            self.frame_metrics.iter()
-                .map(|m| m.memory_usage_mb)
-                .sum::<f64>() / self.frame_metrics.len() as f64
+            self.memory_sum_mb / self.total_frames as f64
         }
     }
     
@@ -203,12 +1512,10 @@ impl MetricsCollector {
     }
     
     pub fn get_average_cpu_percent(&self) -> f64 {
-        if self.frame_metrics.is_empty() {
+        if self.total_frames == 0 {
             0.0
         } else {
-            self.frame_metrics.iter()
-                .map(|m| m.cpu_usage_percent)
-                .sum::<f64>() / self.frame_metrics.len() as f64
+            self.cpu_sum_percent / self.total_frames as f64
         }
     }
     
@@ -220,70 +1527,626 @@ impl MetricsCollector {
         self.dropped_frames += 1;
     }
     
+    pub fn record_dropped_frames(&mut self, count: u64) {
+        self.dropped_frames += count;
+        if let Some(live) = &self.live {
+            if let Ok(mut live) = live.lock() {
+                live.dropped_frames = self.dropped_frames;
+            }
+        }
+    }
+    
+    pub fn get_decode_errors(&self) -> u64 {
+        self.decode_errors
+    }
+    
+    pub fn get_corrupt_frames(&self) -> u64 {
+        self.corrupt_frames
+    }
+    
+    /// Note the `--start`/`--end` range being played, for the exported metrics.
+    pub fn set_segment(&mut self, start: Option<Duration>, end: Option<Duration>) {
+        self.segment = (start, end);
+    }
+    
+    pub fn record_decode_errors(&mut self, count: u64) {
+        self.decode_errors += count;
+        if let Some(live) = &self.live {
+            if let Ok(mut live) = live.lock() {
+                live.decode_errors = self.decode_errors;
+            }
+        }
+    }
+    
+    pub fn record_corrupt_frames(&mut self, count: u64) {
+        self.corrupt_frames += count;
+        if let Some(live) = &self.live {
+            if let Ok(mut live) = live.lock() {
+                live.corrupt_frames = self.corrupt_frames;
+            }
+        }
+    }
+    
+    /// Start a per-file section for a playlist item, closing the previous one.
+    pub fn start_file(&mut self, path: &Path) {
+        if let Some(section) = self.current_file_section() {
+            self.files.push(section);
+        }
+        self.current_file = Some((path.display().to_string(), self.total_frames, Instant::now()));
+        // Switching files isn't a slow frame or a scene cut
+        self.mark_discontinuity();
+    }
+    
+    fn current_file_section(&self) -> Option<FileSection> {
+        let (path, first_frame, started) = self.current_file.as_ref()?;
+        let frames = self.total_frames - first_frame;
+        let duration_seconds = started.elapsed().as_secs_f64();
+        Some(FileSection {
+            path: path.clone(),
+            first_frame: *first_frame,
+            frames,
+            duration_seconds,
+            average_fps: if duration_seconds > 0.0 { frames as f64 / duration_seconds } else { 0.0 },
+        })
+    }
+    
+    /// Record frames whose luma differs from the previous frame's by more than `threshold` (0-255) on average.
+    pub fn detect_scenes(&mut self, threshold: f64) {
+        self.scene_threshold = Some(threshold);
+    }
+    
+    /// Cuts found so far, in the order they were played.
+    pub fn scene_changes(&self) -> &[SceneChange] {
+        &self.scene_changes
+    }
+    
+    /// Compare the frame with the previous one, recording and returning the cut if it starts a new scene.
+    fn detect_scene_change(&mut self, frame_number: u64, frame: &VideoFrame, threshold: f64) -> Option<SceneChange> {
+        let thumbnail = frame.luma_thumbnail(SCENE_GRID.0, SCENE_GRID.1);
+        let mut cut = None;
+        if let Some(previous) = &self.last_thumbnail {
+            let difference = thumbnail
+                .iter()
+                .zip(previous)
+                .map(|(&a, &b)| a.abs_diff(b) as u64)
+                .sum::<u64>() as f64
+                / thumbnail.len() as f64;
+            if difference > threshold {
+                let scene = SceneChange { frame: frame_number, timestamp: frame.timestamp.as_secs_f64(), difference };
+                self.scene_changes.push(scene.clone());
+                cut = Some(scene);
+            }
+        }
+        self.last_thumbnail = Some(thumbnail);
+        cut
+    }
+    
+    /// Share of frames identical to the previous one, None unless frames are hashed.
+    pub fn get_duplicate_frame_ratio(&self) -> Option<f64> {
+        self.duplicate_frames
+            .filter(|_| self.total_frames > 0)
+            .map(|duplicates| duplicates as f64 / self.total_frames as f64)
+    }
+    
+    /// Measure the mean luma of every recorded frame from now on.
+    pub fn analyze_luma(&mut self) {
+        self.analyze_luma = true;
+    }
+    
+    /// (timestamp in seconds, mean luma) of the kept samples, empty unless luma is analyzed.
+    pub fn average_luma_series(&self) -> Vec<(f64, f64)> {
+        self.frame_metrics
+            .iter()
+            .filter_map(|m| m.mean_luma.map(|luma| (m.timestamp, luma)))
+            .collect()
+    }
+    
+    /// Add one frame's quality against the `--compare` reference.
+    pub fn record_quality(&mut self, psnr_db: f64, ssim: Option<f64>) {
+        self.psnr.add(psnr_db);
+        if let Some(ssim) = ssim {
+            self.ssim.add(ssim);
+        }
+    }
+    
+    /// Add how far past its deadline a paced wait returned.
+    pub fn record_pacing_error(&mut self, error: Duration) {
+        self.pacing_error.add(error.as_secs_f64() * 1000.0);
+    }
+    
     pub fn get_session_duration(&self) -> Duration {
         self.session_start.elapsed()
     }
     
+    /// Flag the session as cut short by Ctrl+C, so exports don't pass for a full run.
+    pub fn mark_interrupted(&mut self) {
+        self.interrupted = true;
+    }
+    
+    /// Flag the session as stopped by `limit`, e.g. "--max-frames 2000", rather than the end of the video.
+    pub fn mark_truncated(&mut self, limit: String) {
+        self.truncated = Some(limit);
+    }
+    
+    /// The limit passed to `mark_truncated`, if any.
+    pub fn get_truncated(&self) -> Option<&str> {
+        self.truncated.as_deref()
+    }
+    
+    /// Stop counting time towards the average FPS until `resume`.
+    pub fn pause(&mut self) {
+        self.paused_at.get_or_insert_with(Instant::now);
+    }
+    
+    /// Count time towards the average FPS again, and don't record the pause as frame time.
+    pub fn resume(&mut self) {
+        if let Some(paused_at) = self.paused_at.take() {
+            self.paused_duration += paused_at.elapsed();
+            self.mark_discontinuity();
+        }
+    }
+    
+    /// Session time spent playing, i.e. without pauses.
+    pub fn get_active_duration(&self) -> Duration {
+        let current_pause = self.paused_at.map(|paused_at| paused_at.elapsed()).unwrap_or_default();
+        self.session_start.elapsed().saturating_sub(self.paused_duration + current_pause)
+    }
+    
     pub fn get_total_frames(&self) -> u64 {
         self.total_frames
     }
     
     pub fn finalize_session(&mut self) -> SessionMetrics {
+        let mut session = self.session_summary();
+        session.frame_metrics = self.frame_metrics.clone();
+        session
+    }
+    
+    /// The session statistics without the per-frame samples.
+    fn session_summary(&mut self) -> SessionMetrics {
+        if let Some(Ok(mut csv_log)) = self.csv_log.as_ref().map(|csv_log| csv_log.lock()) {
+            if let Err(e) = csv_log.flush() {
+                log::warn!("Failed to flush live CSV log {:?}: {}", csv_log.path, e);
+            }
+        }
+        
         SessionMetrics {
             start_time: self.session_start_utc,
             end_time: Some(Utc::now()),
             total_frames: self.total_frames,
             total_duration_seconds: self.session_start.elapsed().as_secs_f64(),
+            active_duration_seconds: self.get_active_duration().as_secs_f64(),
             average_fps: self.get_average_fps(),
-            max_fps: self.get_max_fps(),
-            min_fps: self.get_min_fps(),
+            max_fps: self.get_max_source_fps(),
+            min_fps: self.get_min_source_fps(),
+            max_render_fps: self.get_max_render_fps(),
+            min_render_fps: self.get_min_render_fps(),
+            frametime_stddev_ms: self.get_session_frametime_stddev_ms(),
+            keyframes: self.keyframes,
+            average_gop_length: self.get_average_gop_length(),
+            min_gop_length: self.gop_length.min(),
+            max_gop_length: self.gop_length.max(),
+            average_frame_time_ms_by_type: self.get_average_frame_time_by_type(),
+            average_bitrate_kbps: self.get_average_bitrate_kbps(),
+            peak_bitrate_kbps: self.peak_bitrate_kbps,
+            video_info: self.video_info.clone(),
             peak_memory_mb: self.peak_memory_mb,
             average_memory_mb: self.get_average_memory_mb(),
             average_cpu_percent: self.get_average_cpu_percent(),
             peak_cpu_percent: self.peak_cpu_percent,
+            peak_gpu_percent: self.gpu_usage.max(),
+            average_gpu_percent: self.gpu_usage.mean(),
+            peak_gpu_decoder_percent: self.gpu_decoder.max(),
+            average_gpu_decoder_percent: self.gpu_decoder.mean(),
+            peak_vram_mb: self.gpu_memory.max(),
+            average_vram_mb: self.gpu_memory.mean(),
+            peak_process_vram_mb: self.gpu_process_memory.max(),
+            average_process_vram_mb: self.gpu_process_memory.mean(),
             dropped_frames: self.dropped_frames,
-            frame_metrics: self.frame_metrics.clone(),
+            decode_errors: self.decode_errors,
+            corrupt_frames: self.corrupt_frames,
+            output_format: self.output_format.map(OutputFormat::name).unwrap_or_default().to_string(),
+            conversion_path: self.conversion_path.unwrap_or_default().to_string(),
+            average_conversion_time_ms: self.get_average_conversion_time_ms(),
+            average_hash_time_ms: self.hash_time.mean(),
+            average_filter_time_ms: self.filter_time.mean(),
+            segment_start_seconds: self.segment.0.map(|start| start.as_secs_f64()),
+            segment_end_seconds: self.segment.1.map(|end| end.as_secs_f64()),
+            downsampled: self.downsampled,
+            interrupted: self.interrupted,
+            truncated: self.truncated.clone(),
+            duplicate_frames: self.duplicate_frames,
+            files: self.files.iter().cloned().chain(self.current_file_section()).collect(),
+            scene_changes: self.scene_changes.clone(),
+            mean_psnr_db: self.psnr.mean(),
+            min_psnr_db: self.psnr.min(),
+            mean_ssim: self.ssim.mean(),
+            min_ssim: self.ssim.min(),
+            mean_luma: self.luma.mean(),
+            min_luma: self.luma.min(),
+            max_luma: self.luma.max(),
+            mean_pacing_error_ms: self.pacing_error.mean(),
+            max_pacing_error_ms: self.pacing_error.max(),
+            pacing_jitter_ms: self.get_pacing_jitter_ms(),
+            mean_abs_pacing_deviation_ms: self.get_mean_abs_pacing_deviation_ms(),
+            max_pacing_deviation_ms: self.get_max_pacing_deviation_ms(),
+            frame_metrics: Vec::new(),
         }
     }
     
-    pub fn export_to_file(&mut self, path: &Path) -> Result<()> {
+    /// Export metrics in `format`, or the one picked from the file name (see `MetricsFormat::from_path`).
+    pub fn export_to_file(&mut self, path: &Path, format: Option<MetricsFormat>) -> Result<()> {
+        match format.unwrap_or_else(|| MetricsFormat::from_path(path)) {
+            MetricsFormat::Json => self.export_to_json(path),
+            MetricsFormat::Csv => self.export_to_csv(path),
+            MetricsFormat::Chrome => self.export_to_chrome_trace(path),
+            MetricsFormat::Sqlite => self.export_sqlite(path, &self.run_label.clone()),
+        }
+    }
+    
+    /// Name this run gets in SQLite exports through `export_to_file`.
+    pub fn set_run_label(&mut self, label: impl Into<String>) {
+        self.run_label = label.into();
+    }
+    
+    /// Append this run to the SQLite database at `path` as a row of `sessions`, with its samples in
+    /// `frames`; both tables are created if missing. Needs the `sqlite` feature.
+    pub fn export_sqlite(&mut self, path: &Path, run_label: &str) -> Result<()> {
+        let session_metrics = self.finalize_session();
+        crate::sqlite::append_run(path, run_label, session_metrics)
+    }
+    
+    pub fn export_to_json(&mut self, path: &Path) -> Result<()> {
         let session_metrics = self.finalize_session();
         let json = serde_json::to_string_pretty(&session_metrics)?;
         std::fs::write(path, json)?;
         Ok(())
     }
     
+    /// Export the per-frame metrics as CSV, one row per frame.
+    pub fn export_to_csv(&self, path: &Path) -> Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "{}", CSV_HEADER)?;
+        for m in &self.frame_metrics {
+            write_csv_row(&mut writer, m)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+    
+    /// Export the kept samples in the Trace Event Format, for chrome://tracing or Perfetto.
+    ///
+    /// Each frame is a duration event on the "decode" track spanning the time since the previous
+    /// frame, with its pixel conversion on the "scale" track; memory and CPU are counters.
+    /// Timestamps are microseconds since the session started. Events are written one by one and
+    /// without whitespace, so 100k frames stay around 30 MB.
+    pub fn export_to_chrome_trace(&self, path: &Path) -> Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        write!(writer, r#"{{"displayTimeUnit":"ms","traceEvents":["#)?;
+        write!(writer, r#"{{"name":"process_name","ph":"M","pid":1,"args":{{"name":"metric-video-player"}}}}"#)?;
+        for (tid, name) in [(TRACE_DECODE_TID, "decode"), (TRACE_SCALE_TID, "scale")] {
+            write!(writer, r#",{{"name":"thread_name","ph":"M","pid":1,"tid":{},"args":{{"name":"{}"}}}}"#, tid, name)?;
+        }
+        
+        // Counters hold their value until the next event, so unchanged readings are skipped
+        let mut last_memory = None;
+        let mut last_cpu = None;
+        for m in &self.frame_metrics {
+            let end_us = m.session_time_ms * 1000.0;
+            let duration_us = m.processing_time_ms * 1000.0;
+            write!(
+                writer,
+                r#",{{"name":"frame","ph":"X","ts":{:.1},"dur":{:.1},"pid":1,"tid":{},"args":{{"frame":{}}}}}"#,
+                end_us - duration_us, duration_us, TRACE_DECODE_TID, m.frame_number
+            )?;
+            if m.conversion_time_ms > 0.0 {
+                // Conversion is the last step before a frame is recorded
+                let conversion_us = m.conversion_time_ms * 1000.0;
+                write!(
+                    writer,
+                    r#",{{"name":"convert","ph":"X","ts":{:.1},"dur":{:.1},"pid":1,"tid":{},"args":{{"frame":{}}}}}"#,
+                    end_us - conversion_us, conversion_us, TRACE_SCALE_TID, m.frame_number
+                )?;
+            }
+            if last_memory != Some(m.memory_usage_mb) {
+                write!(writer, r#",{{"name":"memory","ph":"C","ts":{:.1},"pid":1,"args":{{"MB":{:.2}}}}}"#, end_us, m.memory_usage_mb)?;
+                last_memory = Some(m.memory_usage_mb);
+            }
+            if last_cpu != Some(m.cpu_usage_percent) {
+                write!(writer, r#",{{"name":"cpu","ph":"C","ts":{:.1},"pid":1,"args":{{"percent":{:.1}}}}}"#, end_us, m.cpu_usage_percent)?;
+                last_cpu = Some(m.cpu_usage_percent);
+            }
+        }
+        writeln!(writer, "]}}")?;
+        writer.flush()?;
+        Ok(())
+    }
+    
     pub fn print_summary(&self) {
         println!("\n=== Performance Metrics Summary ===");
         println!("Session Duration: {:.2}s", self.session_start.elapsed().as_secs_f64());
+        if self.paused_at.is_some() || self.paused_duration > Duration::ZERO {
+            println!("Playing Time: {:.2}s (pauses excluded from average FPS)", self.get_active_duration().as_secs_f64());
+        }
         println!("Total Frames: {}", self.total_frames);
+        if let Some(limit) = &self.truncated {
+            println!("Truncated: stopped at {} after {} frames", limit, self.total_frames);
+        }
         println!("Average FPS: {:.2}", self.get_average_fps());
         println!("Current FPS: {:.2}", self.get_current_fps());
-        println!("Max FPS: {:.2}", self.get_max_fps());
-        println!("Min FPS: {:.2}", self.get_min_fps());
+        println!("Max FPS: {:.2}", self.get_max_render_fps());
+        println!("Min FPS: {:.2}", self.get_min_render_fps());
+        println!("Source FPS: {:.2} - {:.2}", self.get_min_source_fps(), self.get_max_source_fps());
+        println!("Frame Time σ: {:.2} ms", self.get_session_frametime_stddev_ms());
+        if let Some(gop) = self.get_average_gop_length() {
+            match self.get_gop_length_range() {
+                Some((min, max)) => println!("Keyframes: {} (average GOP {:.1} frames, {:.0} - {:.0} between keyframes)", self.keyframes, gop, min, max),
+                None => println!("Keyframes: {} (average GOP {:.1} frames)", self.keyframes, gop),
+            }
+        }
+        let by_type = self.get_average_frame_time_by_type();
+        if !by_type.is_empty() {
+            let times: Vec<String> = by_type.iter().map(|(frame_type, ms)| format!("{} {:.2} ms", frame_type, ms)).collect();
+            println!("Frame Time by Type: {}", times.join(", "));
+        }
+        if self.packet_bytes_sum > 0 {
+            println!("Bitrate: {:.0} kbps average, {:.0} kbps peak", self.get_average_bitrate_kbps(), self.peak_bitrate_kbps);
+        }
         println!("Peak Memory: {:.2} MB", self.peak_memory_mb);
         println!("Average Memory: {:.2} MB", self.get_average_memory_mb());
         println!("Peak CPU: {:.1}%", self.peak_cpu_percent);
         println!("Average CPU: {:.1}%", self.get_average_cpu_percent());
+        if let (Some(gpu), Some(decoder), Some(vram)) = (self.gpu_usage.max(), self.gpu_decoder.max(), self.gpu_memory.max()) {
+            println!("Peak GPU: {:.0}%, decoder {:.0}% ({:.0} MB VRAM, whole device)", gpu, decoder, vram);
+        }
+        if let (Some(peak), Some(average)) = (self.gpu_process_memory.max(), self.gpu_process_memory.mean()) {
+            println!("VRAM of this process: peak {:.0} MB, average {:.0} MB", peak, average);
+        }
+        if let (Some(gpu), Some(decoder), Some(vram)) = (self.gpu_usage.mean(), self.gpu_decoder.mean(), self.gpu_memory.mean()) {
+            println!("Average GPU: {:.0}%, decoder {:.0}% ({:.0} MB VRAM)", gpu, decoder, vram);
+        }
         println!("Dropped Frames: {}", self.dropped_frames);
+        println!("Decode Errors: {}", self.decode_errors);
+        println!("Corrupt Frames: {}", self.corrupt_frames);
+        if self.downsampled {
+            println!("Per-frame Samples: {} of {} frames (downsampled)", self.frame_metrics.len(), self.total_frames);
+        }
+        if let (Some(duplicates), Some(ratio)) = (self.duplicate_frames, self.get_duplicate_frame_ratio()) {
+            println!(
+                "Duplicate Frames: {} ({:.1}%, {:.2} unique FPS)",
+                duplicates,
+                ratio * 100.0,
+                self.get_average_fps() * (1.0 - ratio)
+            );
+        }
+        if self.scene_threshold.is_some() {
+            println!("Scene Changes: {}", self.scene_changes.len());
+        }
+        if let (Some(mean), Some(min)) = (self.psnr.mean(), self.psnr.min()) {
+            println!("PSNR: {:.2} dB mean, {:.2} dB min", mean, min);
+        }
+        if let (Some(mean), Some(min)) = (self.ssim.mean(), self.ssim.min()) {
+            println!("SSIM: {:.4} mean, {:.4} min", mean, min);
+        }
+        if let (Some(mean), Some(min), Some(max)) = (self.luma.mean(), self.luma.min(), self.luma.max()) {
+            println!("Luma: {:.1} mean, {:.1} min, {:.1} max", mean, min, max);
+        }
+        if let (Some(mean), Some(max)) = (self.pacing_error.mean(), self.pacing_error.max()) {
+            println!("Pacing Error: {:.3} ms mean, {:.3} ms max", mean, max);
+        }
+        if let (Some(jitter), Some(mean), Some(max)) = (
+            self.get_pacing_jitter_ms(),
+            self.get_mean_abs_pacing_deviation_ms(),
+            self.get_max_pacing_deviation_ms(),
+        ) {
+            println!("Pacing Jitter: {:.3} ms σ, {:.3} ms mean deviation, {:.3} ms worst", jitter, mean, max);
+        }
+        if let (Some(format), Some(path)) = (self.output_format, self.conversion_path) {
+            println!("Conversion to {} ({}): {:.3} ms/frame", format.name(), path, self.get_average_conversion_time_ms());
+        }
+        if let Some(filter_time) = self.filter_time.mean() {
+            println!("Filtering: {:.3} ms/frame (included in frame time)", filter_time);
+        }
+        if let Some(hash_time) = self.hash_time.mean() {
+            println!("Frame Hashing: {:.3} ms/frame (included in frame time)", hash_time);
+        }
     }
     
     // Real-time monitoring getters for GUI
     pub fn get_current_memory_mb(&mut self) -> f64 {
-        self.system.refresh_processes_specifics(ProcessRefreshKind::new().with_memory());
-        if let Some(process) = self.system.process(self.current_pid) {
-            process.memory() as f64 / 1024.0 / 1024.0
-        } else {
-            0.0
-        }
+        self.process_stats(Instant::now()).0
     }
     
     pub fn get_current_cpu_percent(&mut self) -> f64 {
-        self.system.refresh_processes_specifics(ProcessRefreshKind::new().with_cpu());
-        if let Some(process) = self.system.process(self.current_pid) {
-            process.cpu_usage() as f64
-        } else {
-            0.0
+        self.process_stats(Instant::now()).1
+    }
+    
+    /// Memory in MB and CPU percent of this process, refreshed at most every `stats_interval`.
+    fn process_stats(&mut self, now: Instant) -> (f64, f64) {
+        if let Some((read_at, memory_mb, cpu_percent)) = self.process_stats {
+            if now.saturating_duration_since(read_at) < self.stats_interval {
+                return (memory_mb, cpu_percent);
+            }
         }
+        
+        self.system.refresh_processes_specifics(ProcessRefreshKind::new().with_memory().with_cpu());
+        let (memory_mb, cpu_percent) = match self.current_pid.and_then(|pid| self.system.process(pid)) {
+            Some(process) => (process.memory() as f64 / 1024.0 / 1024.0, process.cpu_usage() as f64),
+            None => (0.0, 0.0),
+        };
+        self.process_stats = Some((now, memory_mb, cpu_percent));
+        (memory_mb, cpu_percent)
+    }
+    
+    /// Device-wide GPU utilization, None without `gpu-metrics` or a supported GPU.
+    pub fn get_current_gpu_percent(&self) -> Option<f64> {
+        self.gpu.as_ref().and_then(GpuMonitor::latest).map(|sample| sample.utilization_percent)
+    }
+    
+    /// Device-wide VRAM in use, None without `gpu-metrics` or a supported GPU.
+    pub fn get_current_vram_mb(&self) -> Option<f64> {
+        self.gpu.as_ref().and_then(GpuMonitor::latest).map(|sample| sample.memory_used_mb)
+    }
+    
+    /// Device-wide load on the video decode engine, None without `gpu-metrics` or a supported GPU.
+    pub fn get_current_gpu_decoder_percent(&self) -> Option<f64> {
+        self.gpu.as_ref().and_then(GpuMonitor::latest).map(|sample| sample.decoder_percent)
+    }
+    
+    pub fn get_peak_gpu_percent(&self) -> Option<f64> {
+        self.gpu_usage.max()
+    }
+    
+    pub fn get_peak_gpu_decoder_percent(&self) -> Option<f64> {
+        self.gpu_decoder.max()
+    }
+    
+    pub fn get_peak_vram_mb(&self) -> Option<f64> {
+        self.gpu_memory.max()
+    }
+    
+    /// VRAM held by the player itself, None without `gpu-metrics` or where the driver doesn't report it.
+    pub fn get_current_process_vram_mb(&self) -> Option<f64> {
+        self.gpu.as_ref().and_then(GpuMonitor::latest).and_then(|sample| sample.process_memory_mb)
+    }
+}
+impl Drop for MetricsCollector {
+    /// A session that ends cleanly gets its summary in the metrics stream; after a crash the last line is a frame.
+    fn drop(&mut self) {
+        self.stream_summary();
+    }
+}
+
+impl Default for MetricsCollector {
+    /// Keeps every frame sample and reads memory and CPU every `DEFAULT_STATS_INTERVAL`.
+    fn default() -> Self {
+        Self::new(MetricsRetention::All, DEFAULT_STATS_INTERVAL)
+    }
+}
+
+impl std::fmt::Debug for MetricsCollector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MetricsCollector")
+            .field("total_frames", &self.total_frames)
+            .field("session_duration", &self.get_session_duration())
+            .field("retention", &self.retention)
+            .field("fps_window", &self.fps_window)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn collector(stats_interval: Duration) -> MetricsCollector {
+        MetricsCollector::new(MetricsRetention::All, stats_interval)
     }
-}
\ No newline at end of file
+    
+    /// Record frames with the given source timestamps, played `interval` apart on the wall clock.
+    fn play(metrics: &mut MetricsCollector, timestamps: &[f64], interval: Duration) {
+        let start = Instant::now();
+        for (index, &timestamp) in timestamps.iter().enumerate() {
+            let frame = VideoFrame { timestamp: Duration::from_secs_f64(timestamp), frame_number: index as u64 + 1, ..VideoFrame::default() };
+            metrics.record_frame_at(index as u64 + 1, &frame, start + interval * index as u32);
+        }
+    }
+    
+    /// Timestamps of `count` frames at a constant `fps`.
+    fn constant_rate(count: usize, fps: f64) -> Vec<f64> {
+        (0..count).map(|index| index as f64 / fps).collect()
+    }
+    
+    #[test]
+    fn process_stats_refresh_at_most_every_stats_interval() {
+        let mut metrics = collector(Duration::from_millis(100));
+        // One second at 1000 FPS
+        play(&mut metrics, &constant_rate(1000, 1000.0), Duration::from_millis(1));
+        
+        // A refresh stamps the frame it happened on with age 0, the first frame included
+        let refreshes = metrics.frame_metrics.iter().filter(|m| m.stats_age_ms == 0.0).count();
+        assert_eq!(refreshes, 10);
+        assert!(metrics.frame_metrics.iter().all(|m| m.stats_age_ms < 100.0));
+    }
+    
+    #[test]
+    fn min_fps_is_zero_without_frames() {
+        let mut metrics = collector(DEFAULT_STATS_INTERVAL);
+        assert_eq!(metrics.get_min_source_fps(), 0.0);
+        assert_eq!(metrics.get_min_render_fps(), 0.0);
+        let session = metrics.finalize_session();
+        assert_eq!((session.min_fps, session.min_render_fps), (0.0, 0.0));
+    }
+    
+    #[test]
+    fn min_fps_is_zero_after_a_single_frame() {
+        let mut metrics = collector(DEFAULT_STATS_INTERVAL);
+        play(&mut metrics, &[0.0], Duration::from_millis(10));
+        assert_eq!(metrics.get_min_source_fps(), 0.0);
+        assert_eq!(metrics.get_min_render_fps(), 0.0);
+    }
+    
+    #[test]
+    fn identical_timestamps_leave_source_fps_unset() {
+        let mut metrics = collector(DEFAULT_STATS_INTERVAL);
+        play(&mut metrics, &[1.0, 1.0, 1.0], Duration::from_millis(10));
+        assert_eq!(metrics.get_min_source_fps(), 0.0);
+        assert_eq!(metrics.get_max_source_fps(), 0.0);
+        // The frames were still played 10 ms apart
+        assert!((metrics.get_min_render_fps() - 100.0).abs() < 1e-6);
+    }
+    
+    #[test]
+    fn render_and_source_fps_are_measured_separately() {
+        let mut metrics = collector(DEFAULT_STATS_INTERVAL);
+        // A 30 FPS video decoded as fast as possible, a frame every 2 ms
+        play(&mut metrics, &constant_rate(30, 30.0), Duration::from_millis(2));
+        
+        assert!((metrics.get_min_source_fps() - 30.0).abs() < 0.01);
+        assert!((metrics.get_max_source_fps() - 30.0).abs() < 0.01);
+        assert!((metrics.get_min_render_fps() - 500.0).abs() < 0.01);
+        assert!((metrics.get_max_render_fps() - 500.0).abs() < 0.01);
+    }
+    
+    #[test]
+    fn frametime_stddev_separates_even_and_bursty_pacing() {
+        let timestamps = constant_rate(41, 30.0);
+        
+        let mut even = collector(DEFAULT_STATS_INTERVAL);
+        play(&mut even, &timestamps, Duration::from_millis(10));
+        assert!(even.get_frametime_stddev_ms() < 1e-6);
+        assert!(even.get_session_frametime_stddev_ms() < 1e-6);
+        
+        // The same 10 ms average, but frames arrive in pairs: 2 ms apart, then 18 ms
+        let mut bursty = collector(DEFAULT_STATS_INTERVAL);
+        let start = Instant::now();
+        let mut now = start;
+        for (index, &timestamp) in timestamps.iter().enumerate() {
+            let frame = VideoFrame { timestamp: Duration::from_secs_f64(timestamp), frame_number: index as u64 + 1, ..VideoFrame::default() };
+            bursty.record_frame_at(index as u64 + 1, &frame, now);
+            now += Duration::from_millis(if index % 2 == 0 { 2 } else { 18 });
+        }
+        assert!((bursty.get_frametime_stddev_ms() - 8.0).abs() < 1e-6);
+        assert!((bursty.get_session_frametime_stddev_ms() - 8.0).abs() < 1e-6);
+        assert!((bursty.finalize_session().frametime_stddev_ms - 8.0).abs() < 1e-6);
+    }
+    
+    #[test]
+    fn gop_lengths_match_the_encoder_keyframe_interval() {
+        use crate::test_clips::{self, Clip};
+        use crate::video_player::{PlayerOptions, VideoPlayer};
+        
+        let dir = tempfile::tempdir().unwrap();
+        // Keyframes at 0, 7, 14, 21 and 28; the last GOP is cut short and not counted
+        let path = test_clips::write_clip(dir.path(), "gop", &Clip { frames: 30, gop: 7, ..Clip::default() });
+        let mut player = VideoPlayer::new(&path, &PlayerOptions::default()).unwrap();
+        let mut metrics = collector(DEFAULT_STATS_INTERVAL);
+        for frame in player.frames() {
+            let frame = frame.unwrap();
+            metrics.record_frame(frame.frame_number, &frame);
+        }
+        
+        assert_eq!(metrics.get_keyframes(), 5);
+        assert_eq!(metrics.get_gop_length_range(), Some((7.0, 7.0)));
+    }
+}
@@ -0,0 +1,123 @@
+use crate::metrics::{LiveMetrics, SharedLiveMetrics, FRAME_TIME_BUCKETS};
+use anyhow::{Context, Result};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// How often the accept loop checks for shutdown
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Serves `/metrics` in Prometheus text format on a background thread until dropped.
+pub struct MetricsServer {
+    shutdown: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl MetricsServer {
+    pub fn start(port: u16, live: SharedLiveMetrics) -> Result<Self> {
+        let listener = TcpListener::bind(("0.0.0.0", port))
+            .with_context(|| format!("Failed to listen on port {} for --metrics-port", port))?;
+        // Non-blocking so the thread notices shutdown instead of waiting for a scrape
+        listener.set_nonblocking(true)?;
+        log::info!("Serving Prometheus metrics on http://0.0.0.0:{}/metrics", port);
+        
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let handle = std::thread::spawn({
+            let shutdown = shutdown.clone();
+            move || {
+                while !shutdown.load(Ordering::Relaxed) {
+                    match listener.accept() {
+                        Ok((stream, _)) => {
+                            if let Err(e) = handle_connection(stream, &live) {
+                                log::debug!("Metrics request failed: {}", e);
+                            }
+                        }
+                        Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => std::thread::sleep(POLL_INTERVAL),
+                        Err(e) => log::warn!("Metrics server accept failed: {}", e),
+                    }
+                }
+            }
+        });
+        
+        Ok(Self { shutdown, handle: Some(handle) })
+    }
+}
+
+impl Drop for MetricsServer {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+fn handle_connection(stream: TcpStream, live: &Mutex<LiveMetrics>) -> std::io::Result<()> {
+    // Accepted sockets inherit non-blocking mode on some platforms
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    
+    let mut reader = BufReader::new(&stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    
+    // Skip the headers, nothing in them matters here
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+    
+    let path = request_line.split_whitespace().nth(1).unwrap_or("");
+    let (status, body) = if path == "/metrics" {
+        // Copy the values out so playback never waits on a slow client
+        let snapshot = live.lock().map(|live| live.clone()).unwrap_or_default();
+        ("200 OK", render(&snapshot))
+    } else {
+        ("404 Not Found", "Not found, try /metrics\n".to_string())
+    };
+    
+    let mut stream = &stream;
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+/// Prometheus text exposition of the latest values.
+fn render(live: &LiveMetrics) -> String {
+    let metrics: [(&str, &str, &str, f64); 8] = [
+        ("mvp_current_fps", "gauge", "Frames per second over the --fps-window (default: the last 60 frames)", live.current_fps),
+        ("mvp_avg_fps", "gauge", "Average frames per second since the session started", live.average_fps),
+        ("mvp_frames_total", "counter", "Frames played in this session", live.total_frames as f64),
+        ("mvp_dropped_frames_total", "counter", "Frames dropped in this session", live.dropped_frames as f64),
+        ("mvp_decode_errors_total", "counter", "Corrupted packets skipped in this session", live.decode_errors as f64),
+        ("mvp_corrupt_frames_total", "counter", "Frames the decoder failed on or flagged as damaged in this session", live.corrupt_frames as f64),
+        ("mvp_memory_mb", "gauge", "Resident memory of the player in MB", live.memory_mb),
+        ("mvp_cpu_percent", "gauge", "CPU usage of the player in percent", live.cpu_percent),
+    ];
+    
+    let mut out = String::new();
+    for (name, kind, help, value) in metrics {
+        out.push_str(&format!("# HELP {} {}\n# TYPE {} {}\n{} {}\n", name, help, name, kind, name, value));
+    }
+    
+    // Prometheus buckets are cumulative
+    let name = "mvp_frame_time_seconds";
+    out.push_str(&format!("# HELP {} Time between played frames\n# TYPE {} histogram\n", name, name));
+    let mut count = 0;
+    for (bound, frames) in FRAME_TIME_BUCKETS.iter().zip(&live.frame_time_buckets) {
+        count += frames;
+        out.push_str(&format!("{}_bucket{{le=\"{}\"}} {}\n", name, bound, count));
+    }
+    count += live.frame_time_buckets[FRAME_TIME_BUCKETS.len()];
+    out.push_str(&format!("{}_bucket{{le=\"+Inf\"}} {}\n", name, count));
+    out.push_str(&format!("{}_sum {}\n{}_count {}\n", name, live.frame_time_sum_seconds, name, count));
+    out
+}
@@ -0,0 +1,123 @@
+use crate::{metrics::MetricsCollector, video_player::{self, VideoPlayer}, Args};
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+/// Videos queued with repeated `-i` or `--playlist`, played one after another.
+pub struct Playlist {
+    items: Vec<PathBuf>,
+    current: usize,
+}
+
+impl Playlist {
+    /// The `-i` inputs followed by the lines of `--playlist`.
+    pub fn from_args(args: &Args) -> Result<Self> {
+        let mut items = args.inputs.clone();
+        if let Some(list) = &args.playlist {
+            let contents = std::fs::read_to_string(list)
+                .with_context(|| format!("Failed to read playlist {:?}", list))?;
+            // Relative entries are relative to the list, not to wherever the player was started.
+            // URLs and `-` are passed through as is.
+            let base = list.parent().unwrap_or(Path::new(""));
+            items.extend(
+                contents
+                    .lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                    .map(Path::new)
+                    .map(|item| {
+                        if video_player::is_url(item) || video_player::is_stdin(item) {
+                            item.to_path_buf()
+                        } else {
+                            base.join(item)
+                        }
+                    }),
+            );
+        }
+        
+        if items.is_empty() {
+            anyhow::bail!("No videos to play, pass -i <VIDEO_PATH> or a non-empty --playlist");
+        }
+        Ok(Self { items, current: 0 })
+    }
+    
+    pub fn items(&self) -> &[PathBuf] {
+        &self.items
+    }
+    
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+    
+    pub fn current(&self) -> &Path {
+        &self.items[self.current]
+    }
+    
+    /// "3/7 – name" for window titles, None when only one video is queued.
+    pub fn title(&self) -> Option<String> {
+        if self.items.len() < 2 {
+            return None;
+        }
+        let path = self.current();
+        let name = path.file_name().map_or_else(|| path.to_string_lossy(), |name| name.to_string_lossy());
+        Some(format!("{}/{} – {}", self.current + 1, self.items.len(), name))
+    }
+    
+    /// Open the first item that can be opened. A single video's error is returned as is.
+    pub fn open_first(&mut self, args: &Args) -> Result<VideoPlayer> {
+        if self.items.len() == 1 {
+            return VideoPlayer::new(&self.items[0], &args.player_options());
+        }
+        
+        for index in 0..self.items.len() {
+            if let Some(player) = self.open(index, args) {
+                return Ok(player);
+            }
+        }
+        anyhow::bail!("None of the {} queued videos could be opened", self.items.len())
+    }
+    
+    /// Move to the next (or previous) item that opens, skipping broken ones. With `wrap`, going past
+    /// either end continues from the other one; otherwise None is returned there.
+    ///
+    /// `metrics` gets a new per-file section, or is reset with `--reset-metrics-per-file`.
+    pub fn advance(&mut self, forward: bool, wrap: bool, args: &Args, metrics: &mut MetricsCollector) -> Option<VideoPlayer> {
+        let len = self.items.len();
+        let mut index = self.current;
+        for _ in 1..len {
+            index = match (forward, index) {
+                (true, i) if i + 1 < len => i + 1,
+                (true, _) if wrap => 0,
+                (false, 0) if wrap => len - 1,
+                (false, i) if i > 0 => i - 1,
+                _ => return None,
+            };
+            
+            if let Some(player) = self.open(index, args) {
+                if args.reset_metrics_per_file {
+                    metrics.print_summary();
+                    metrics.reset();
+                }
+                metrics.start_file(self.current());
+                metrics.set_video_info(player.info().clone());
+                return Some(player);
+            }
+        }
+        None
+    }
+    
+    fn open(&mut self, index: usize, args: &Args) -> Option<VideoPlayer> {
+        let path = &self.items[index];
+        match VideoPlayer::new(path, &args.player_options()) {
+            Ok(mut player) => {
+                log::info!("Playing {} of {}: {:?}", index + 1, self.items.len(), path);
+                player.set_playback_speed(args.speed);
+                self.current = index;
+                Some(player)
+            }
+            Err(e) => {
+                log::warn!("Skipping {:?}: {:#}", path, e);
+                None
+            }
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use crate::{video_player::{format_timecode_ms, FrameHash, OutputFormat, PlayerOptions, VideoFrame, VideoPlayer}, Args};
+use anyhow::{Context, Result};
+use image::imageops::FilterType;
+use std::path::Path;
+use std::time::Duration;
+
+/// Frames decoded past a mostly black one while looking for a better poster
+const MAX_SCANNED_FRAMES: usize = 30;
+
+/// Mean luma (0-255) below which a frame counts as black, e.g. a fade or a title card gap
+const BLACK_LUMA: f64 = 20.0;
+
+/// Write a poster frame of `video` to `out`: from `--at`, or 10% into the video, the first frame that
+/// isn't mostly black, scaled down to `--thumb-width` if given.
+pub fn write_poster(video: &Path, out: &Path, args: &Args) -> Result<()> {
+    let options = PlayerOptions {
+        target_fps: 0,
+        output_format: OutputFormat::Rgb24,
+        yuv_direct: false,
+        probe: false,
+        hash_frames: false,
+        frame_hash: FrameHash::None,
+        ..args.player_options()
+    };
+    let mut player = VideoPlayer::new(video, &options)?;
+    
+    let position = match args.at {
+        Some(at) => at,
+        None if player.has_duration() => player.get_duration().mul_f64(0.1),
+        None => Duration::ZERO,
+    };
+    if !position.is_zero() {
+        if player.is_seekable() {
+            player.seek_to_time(position)?;
+        } else {
+            log::warn!("{:?} isn't seekable, taking the poster from the start", video);
+        }
+    }
+    
+    // Keep the brightest frame in case they're all dark, e.g. a night scene
+    let mut poster: Option<(VideoFrame, f64)> = None;
+    for _ in 0..MAX_SCANNED_FRAMES {
+        let Some(frame) = player.next_frame()? else {
+            break;
+        };
+        let luma = frame.mean_luma();
+        if poster.as_ref().is_none_or(|(_, best)| luma > *best) {
+            poster = Some((frame, luma));
+        }
+        if luma >= BLACK_LUMA {
+            break;
+        }
+    }
+    let Some((frame, luma)) = poster else {
+        anyhow::bail!("No frames at {:.3}s in {:?}", position.as_secs_f64(), video);
+    };
+    if luma < BLACK_LUMA {
+        log::warn!("Only dark frames after {:.3}s in {:?}, using the brightest", position.as_secs_f64(), video);
+    }
+    
+    let mut image = image::RgbImage::from_raw(frame.width, frame.height, frame.rgb_data().into_owned())
+        .context("Decoded frame is smaller than its size says")?;
+    if let Some(max_size) = args.thumb_width {
+        let longest = frame.width.max(frame.height);
+        // Only ever scaled down, a larger poster would just be blurry
+        if max_size > 0 && max_size < longest {
+            let scale = max_size as f64 / longest as f64;
+            let width = ((frame.width as f64 * scale).round() as u32).max(1);
+            let height = ((frame.height as f64 * scale).round() as u32).max(1);
+            image = image::imageops::resize(&image, width, height, FilterType::CatmullRom);
+        }
+    }
+    image.save(out).with_context(|| format!("Failed to save {:?}", out))?;
+    
+    log::info!("Wrote {}x{} poster from {} to {:?}", image.width(), image.height(),
+        format_timecode_ms(frame.timestamp), out);
+    Ok(())
+}
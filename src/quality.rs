@@ -0,0 +1,75 @@
+/// PSNR reported for identical frames, where the real value is infinite (and JSON can't hold that)
+pub const MAX_PSNR_DB: f64 = 100.0;
+
+/// Side of the square windows SSIM is averaged over
+const SSIM_WINDOW: usize = 8;
+
+/// Peak signal-to-noise ratio in dB between two RGB24 images of the same size.
+pub fn psnr(reference: &[u8], distorted: &[u8]) -> f64 {
+    let squared_error: u64 = reference
+        .iter()
+        .zip(distorted)
+        .map(|(&a, &b)| {
+            let diff = a as i64 - b as i64;
+            (diff * diff) as u64
+        })
+        .sum();
+    if squared_error == 0 || reference.is_empty() {
+        return MAX_PSNR_DB;
+    }
+    
+    let mse = squared_error as f64 / reference.len() as f64;
+    (10.0 * (255.0 * 255.0 / mse).log10()).min(MAX_PSNR_DB)
+}
+
+/// Structural similarity (0..1) between two RGB24 images, computed on luma over 8x8 windows.
+pub fn ssim(reference: &[u8], distorted: &[u8], width: usize, height: usize) -> f64 {
+    const C1: f64 = (0.01 * 255.0) * (0.01 * 255.0);
+    const C2: f64 = (0.03 * 255.0) * (0.03 * 255.0);
+    
+    let reference = luma(reference);
+    let distorted = luma(distorted);
+    
+    let mut total = 0.0;
+    let mut windows = 0;
+    for top in (0..height.saturating_sub(SSIM_WINDOW - 1)).step_by(SSIM_WINDOW) {
+        for left in (0..width.saturating_sub(SSIM_WINDOW - 1)).step_by(SSIM_WINDOW) {
+            let (mut sum_a, mut sum_b, mut sum_aa, mut sum_bb, mut sum_ab) = (0.0, 0.0, 0.0, 0.0, 0.0);
+            for row in top..top + SSIM_WINDOW {
+                for col in left..left + SSIM_WINDOW {
+                    let a = reference[row * width + col];
+                    let b = distorted[row * width + col];
+                    sum_a += a;
+                    sum_b += b;
+                    sum_aa += a * a;
+                    sum_bb += b * b;
+                    sum_ab += a * b;
+                }
+            }
+            
+            let n = (SSIM_WINDOW * SSIM_WINDOW) as f64;
+            let (mean_a, mean_b) = (sum_a / n, sum_b / n);
+            let var_a = sum_aa / n - mean_a * mean_a;
+            let var_b = sum_bb / n - mean_b * mean_b;
+            let covariance = sum_ab / n - mean_a * mean_b;
+            
+            total += ((2.0 * mean_a * mean_b + C1) * (2.0 * covariance + C2))
+                / ((mean_a * mean_a + mean_b * mean_b + C1) * (var_a + var_b + C2));
+            windows += 1;
+        }
+    }
+    
+    // Images smaller than one window are compared as identical rather than dividing by zero
+    if windows == 0 {
+        1.0
+    } else {
+        total / windows as f64
+    }
+}
+
+/// BT.601 luma of each RGB24 pixel.
+fn luma(rgb: &[u8]) -> Vec<f64> {
+    rgb.chunks_exact(3)
+        .map(|pixel| 0.299 * pixel[0] as f64 + 0.587 * pixel[1] as f64 + 0.114 * pixel[2] as f64)
+        .collect()
+}
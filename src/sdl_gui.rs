@@ -1,20 +1,124 @@
-use crate::{video_player::VideoPlayer, metrics::MetricsCollector, Args};
+use crate::{video_player::{OutputFormat, VideoFrame, VideoPlayer}, frame_export::{self, FrameExporter}, metrics::MetricsCollector, playlist::Playlist, window_title::{PlaybackState, WindowTitle}, Args};
 use anyhow::Result;
-use sdl2::event::Event;
+use sdl2::event::{Event, WindowEvent};
 use sdl2::keyboard::Keycode;
-use sdl2::pixels::PixelFormatEnum;
+use sdl2::messagebox::{show_simple_message_box, MessageBoxFlag};
+use sdl2::pixels::{Color, PixelFormatEnum};
 use sdl2::rect::Rect;
-use std::time::Instant;
+use sdl2::ttf::{Font, Sdl2TtfContext};
+use sdl2::video::{FullscreenType, Window};
+use std::path::{Path, PathBuf};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
 
-pub fn run_sdl_gui(mut player: VideoPlayer, mut metrics: MetricsCollector, args: Args) -> Result<()> {
+/// Fonts tried for the stats overlay when `--font` isn't given
+const FALLBACK_FONTS: &[&str] = &[
+    "/usr/share/fonts/truetype/dejavu/DejaVuSansMono.ttf",
+    "/usr/share/fonts/TTF/DejaVuSansMono.ttf",
+    "/usr/share/fonts/dejavu-sans-mono-fonts/DejaVuSansMono.ttf",
+    "/usr/share/fonts/truetype/liberation/LiberationMono-Regular.ttf",
+    "/System/Library/Fonts/Menlo.ttc",
+    "C:\\Windows\\Fonts\\consola.ttf",
+];
+
+const OSD_FONT_SIZE: u16 = 16;
+/// How often the overlay numbers are refreshed; faster just makes them unreadable
+const OSD_REFRESH_INTERVAL: Duration = Duration::from_millis(250);
+/// How far the left/right arrow keys seek
+const SEEK_STEP: Duration = Duration::from_secs(5);
+/// How far the up/down arrow keys seek
+const SEEK_STEP_LARGE: Duration = Duration::from_secs(60);
+/// How much `[` and `]` change the target FPS
+const FPS_STEP: u32 = 5;
+/// Minimum time between seeks, so holding an arrow key doesn't queue up hundreds of them
+const SEEK_DEBOUNCE: Duration = Duration::from_millis(150);
+/// Overlay lines only break at newlines, this is just wider than any of them
+const OSD_WRAP_WIDTH: u32 = 1000;
+/// How long confirmation messages (e.g. a saved screenshot) stay visible
+const STATUS_MESSAGE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// SDL texture format matching `format`'s memory layout.
+fn texture_format(format: OutputFormat) -> PixelFormatEnum {
+    match format {
+        OutputFormat::Rgb24 => PixelFormatEnum::RGB24,
+        OutputFormat::Rgba => PixelFormatEnum::RGBA32,
+        // Planar YUV is converted to RGB on the GPU
+        OutputFormat::Yuv420p => PixelFormatEnum::IYUV,
+        // SDL has no 8-bit gray texture, luma is repeated into RGB on upload
+        OutputFormat::Gray8 => PixelFormatEnum::RGB24,
+    }
+}
+
+/// Switch between windowed and desktop fullscreen, restoring `windowed_size` when leaving fullscreen.
+fn toggle_fullscreen(window: &mut Window, windowed_size: &mut (u32, u32)) -> Result<()> {
+    if window.fullscreen_state() == FullscreenType::Off {
+        *windowed_size = window.size();
+        window.set_fullscreen(FullscreenType::Desktop).map_err(|e| anyhow::anyhow!("{}", e))?;
+    } else {
+        window.set_fullscreen(FullscreenType::Off).map_err(|e| anyhow::anyhow!("{}", e))?;
+        window.set_size(windowed_size.0, windowed_size.1)?;
+    }
+    Ok(())
+}
+
+/// Where a seek key jumps to: arrows step, Home restarts and 0-9 jump to that tenth of the video.
+fn seek_key_target(keycode: Keycode, player: &VideoPlayer) -> Option<Duration> {
+    let position = player.get_position();
+    let (start, end) = player.get_segment();
+    match keycode {
+        Keycode::Left => Some(position.saturating_sub(SEEK_STEP)),
+        Keycode::Right => Some(position + SEEK_STEP),
+        Keycode::Down => Some(position.saturating_sub(SEEK_STEP_LARGE)),
+        Keycode::Up => Some(position + SEEK_STEP_LARGE),
+        Keycode::Home => Some(start),
+        _ => {
+            let digits = [
+                Keycode::Num0, Keycode::Num1, Keycode::Num2, Keycode::Num3, Keycode::Num4,
+                Keycode::Num5, Keycode::Num6, Keycode::Num7, Keycode::Num8, Keycode::Num9,
+            ];
+            let tenth = digits.iter().position(|&digit| digit == keycode)?;
+            if !player.has_duration() {
+                return None;
+            }
+            Some(start + (end - start) * tenth as u32 / 10)
+        }
+    }
+}
+
+fn load_osd_font<'ttf>(ttf_context: &'ttf Sdl2TtfContext, font_path: Option<&Path>) -> Option<Font<'ttf, 'static>> {
+    if let Some(path) = font_path {
+        match ttf_context.load_font(path, OSD_FONT_SIZE) {
+            Ok(font) => return Some(font),
+            Err(e) => log::warn!("Failed to load font {:?}: {}", path, e),
+        }
+    }
+    
+    let font = FALLBACK_FONTS
+        .iter()
+        .find_map(|path| ttf_context.load_font(path, OSD_FONT_SIZE).ok());
+    if font.is_none() {
+        log::warn!("No usable font found, the stats overlay is disabled (pass --font <path>)");
+    }
+    font
+}
+
+pub fn run_sdl_gui(mut player: VideoPlayer, mut metrics: MetricsCollector, mut args: Args, mut playlist: Playlist) -> Result<()> {
     let sdl_context = sdl2::init().map_err(|e| anyhow::anyhow!("SDL init failed: {}", e))?;
     let video_subsystem = sdl_context.video().map_err(|e| anyhow::anyhow!("Video subsystem failed: {}", e))?;
 
-    let width = player.get_width();
-    let height = player.get_height();
+    let (width, height) = player.get_output_size();
+
+    // Size the window for the displayed shape, which differs from the pixel size for anamorphic
+    // video, at the source height so downscaled (--scale) frames still fill a full-size window
+    let window_height = player.get_height();
+    let window_width = (window_height as f64 * player.get_display_aspect_ratio()).round() as u32;
 
+    let title = match playlist.title() {
+        Some(item) => format!("{} - Metric Video Player (SDL2)", item),
+        None => "Metric Video Player (SDL2)".to_string(),
+    };
     let window = video_subsystem
-        .window("Metric Video Player (SDL2)", width, height)
+        .window(&title, window_width, window_height)
         .position_centered()
         .resizable()
         .build()?;
@@ -23,14 +127,40 @@ pub fn run_sdl_gui(mut player: VideoPlayer, mut metrics: MetricsCollector, args:
     let texture_creator = canvas.texture_creator();
 
     let mut texture = texture_creator
-        .create_texture_streaming(PixelFormatEnum::RGB24, width, height)
+        .create_texture_streaming(texture_format(player.get_output_format()), width, height)
         .map_err(|e| anyhow::anyhow!("Texture creation failed: {}", e))?;
+    let mut texture_layout = (width, height, player.get_output_format());
 
     let mut event_pump = sdl_context.event_pump().map_err(|e| anyhow::anyhow!("Event pump failed: {}", e))?;
     let mut is_playing = true;
+    let mut playback_finished = false;
+    let mut window_title = WindowTitle::default();
     let mut last_frame_time = Instant::now();
+    let mut last_frame: Option<VideoFrame> = None;
+    // Previously shown frame, recycled as the next decode target
+    let mut spare_frame: Option<VideoFrame> = None;
+    let mut needs_redraw = false;
+    let mut last_seek: Option<Instant> = None;
+    // Decode one frame after seeking while paused, so the new position is visible
+    let mut show_seeked_frame = false;
+    let mut windowed_size = (window_width, window_height);
+    // Total texture upload time, to compare pixel formats (--output-format)
+    let mut upload_time = Duration::ZERO;
+    let mut uploads = 0u32;
+    let mut frame_exporter = FrameExporter::from_args(&args)?;
 
-    log::info!("SDL2 GUI started. Press SPACE to pause/play, ESC to quit.");
+    // Stats overlay: the text texture is only re-rendered when the displayed numbers change
+    let ttf_context = sdl2::ttf::init().map_err(|e| anyhow::anyhow!("SDL_ttf init failed: {}", e))?;
+    let osd_font = load_osd_font(&ttf_context, args.font.as_deref());
+    let mut show_osd = args.overlay && osd_font.is_some();
+    let mut osd_text = String::new();
+    let mut osd_texture = None;
+    let mut osd_updated: Option<Instant> = None;
+    // Screenshots still being encoded, and the confirmation shown at the bottom of the video
+    let mut pending_screenshots: Vec<(PathBuf, JoinHandle<Result<()>>)> = Vec::new();
+    let mut status_message: Option<(Instant, sdl2::render::Texture)> = None;
+
+    log::info!("SDL2 GUI started. Press SPACE to pause/play, F to toggle fullscreen, S to save a screenshot, M/F1/Tab to toggle stats, arrows/Home/0-9 to seek, [/] to change the target FPS, N/P for the next/previous video, ESC to quit, drop a file to open it.");
 
     'running: loop {
         // Handle events
@@ -46,67 +176,231 @@ pub fn run_sdl_gui(mut player: VideoPlayer, mut metrics: MetricsCollector, args:
                     ..
                 } => {
                     is_playing = !is_playing;
+                    if is_playing {
+                        playback_finished = false;
+                        player.reset_realtime_clock();
+                        metrics.resume();
+                    } else {
+                        metrics.pause();
+                    }
                     log::info!("Playback {}", if is_playing { "resumed" } else { "paused" });
                 }
+                Event::KeyDown {
+                    keycode: Some(Keycode::S),
+                    ..
+                } => match &last_frame {
+                    Some(frame) => pending_screenshots.push(frame_export::save_screenshot(
+                        frame.clone(),
+                        &args.screenshot_dir,
+                        player.get_source(),
+                    )),
+                    None => log::warn!("No frame decoded yet, nothing to save"),
+                },
+                Event::KeyDown {
+                    keycode: Some(Keycode::M | Keycode::F1 | Keycode::Tab),
+                    repeat: false,
+                    ..
+                } => {
+                    show_osd = !show_osd && osd_font.is_some();
+                    osd_updated = None;
+                    needs_redraw = last_frame.is_some();
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::F | Keycode::F11),
+                    repeat: false,
+                    ..
+                } => {
+                    if let Err(e) = toggle_fullscreen(canvas.window_mut(), &mut windowed_size) {
+                        log::error!("Failed to toggle fullscreen: {:#}", e);
+                    }
+                }
+                Event::Window {
+                    win_event: WindowEvent::SizeChanged(..) | WindowEvent::Exposed,
+                    ..
+                } => {
+                    needs_redraw = last_frame.is_some();
+                }
+                Event::KeyDown {
+                    keycode: Some(keycode @ (Keycode::N | Keycode::P)),
+                    repeat: false,
+                    ..
+                } => match playlist.advance(keycode == Keycode::N, true, &args, &mut metrics) {
+                    Some(mut next_player) => {
+                        next_player.set_playback_speed(player.get_playback_speed());
+                        player = next_player;
+                        is_playing = true;
+                        playback_finished = false;
+                        metrics.resume();
+                    }
+                    None => log::info!("No other video to switch to"),
+                },
+                Event::DropFile { filename, .. } => {
+                    log::info!("Opening dropped file: {}", filename);
+                    match VideoPlayer::new(Path::new(&filename), &args.player_options()) {
+                        Ok(mut new_player) => {
+                            new_player.set_playback_speed(player.get_playback_speed());
+                            metrics.set_video_info(new_player.info().clone());
+                            player = new_player;
+                            is_playing = true;
+                            playback_finished = false;
+                            metrics.resume();
+                        }
+                        Err(e) => {
+                            log::error!("Failed to open {}: {:#}", filename, e);
+                            let message = format!("Failed to open {}:\n{:#}", filename, e);
+                            if let Err(e) = show_simple_message_box(
+                                MessageBoxFlag::ERROR,
+                                "Metric Video Player",
+                                &message,
+                                canvas.window(),
+                            ) {
+                                log::error!("Failed to show error dialog: {}", e);
+                            }
+                        }
+                    }
+                }
+                Event::KeyDown {
+                    keycode: Some(keycode @ (Keycode::LeftBracket | Keycode::RightBracket)),
+                    ..
+                } => {
+                    // Step from the video's own rate when playing unlimited
+                    let current = match player.get_target_fps() {
+                        0 => player.get_native_fps().round() as u32,
+                        fps => fps,
+                    };
+                    let fps = if keycode == Keycode::RightBracket {
+                        current + FPS_STEP
+                    } else {
+                        current.saturating_sub(FPS_STEP).max(1)
+                    };
+                    player.set_target_fps(fps);
+                    // Videos dropped onto the window keep the new rate
+                    args.target_fps = fps;
+                    log::info!("Target FPS: {}", fps);
+                }
+                Event::KeyDown {
+                    keycode: Some(keycode),
+                    ..
+                } => {
+                    let Some(target) = seek_key_target(keycode, &player) else {
+                        continue;
+                    };
+                    if last_seek.is_some_and(|seeked_at| seeked_at.elapsed() < SEEK_DEBOUNCE) {
+                        continue;
+                    }
+                    last_seek = Some(Instant::now());
+                    
+                    match player.seek_to_time(target) {
+                        Ok(()) => {
+                            metrics.mark_discontinuity();
+                            show_seeked_frame = !is_playing;
+                        }
+                        Err(e) => log::warn!("Failed to seek: {:#}", e),
+                    }
+                }
                 _ => {}
             }
         }
 
-        if is_playing {
+        let (finished, pending): (Vec<_>, Vec<_>) =
+            pending_screenshots.drain(..).partition(|(_, handle)| handle.is_finished());
+        pending_screenshots = pending;
+        for (path, handle) in finished {
+            let message = match handle.join().unwrap_or_else(|_| Err(anyhow::anyhow!("Screenshot thread panicked"))) {
+                Ok(()) => {
+                    log::info!("Saved screenshot to {:?}", path);
+                    format!("Screenshot saved to {}", path.display())
+                }
+                Err(e) => {
+                    log::error!("Failed to save screenshot: {:#}", e);
+                    format!("Failed to save screenshot: {:#}", e)
+                }
+            };
+            if let Some(font) = &osd_font {
+                let surface = font
+                    .render(&message)
+                    .blended(Color::RGB(255, 255, 255))
+                    .map_err(|e| anyhow::anyhow!("Text rendering failed: {}", e))?;
+                let status_texture = texture_creator
+                    .create_texture_from_surface(&surface)
+                    .map_err(|e| anyhow::anyhow!("Texture creation failed: {}", e))?;
+                status_message = Some((Instant::now(), status_texture));
+                needs_redraw = last_frame.is_some();
+            }
+        }
+        if status_message.as_ref().is_some_and(|(shown_at, _)| shown_at.elapsed() >= STATUS_MESSAGE_TIMEOUT) {
+            status_message = None;
+            needs_redraw = last_frame.is_some();
+        }
+
+        if is_playing || show_seeked_frame {
             // Check if it's time for the next frame
-            let should_advance = if args.target_fps > 0 {
-                let target_interval = std::time::Duration::from_nanos(1_000_000_000 / args.target_fps as u64);
+            let seeked = std::mem::take(&mut show_seeked_frame);
+            let should_advance = if seeked {
+                true
+            } else if player.get_target_fps() > 0 {
+                let target_interval = std::time::Duration::from_nanos(1_000_000_000 / player.get_target_fps() as u64)
+                    .div_f32(player.get_playback_speed());
                 last_frame_time.elapsed() >= target_interval
             } else {
                 true // Maximum FPS
             };
 
             if should_advance {
-                if let Ok(Some(frame)) = player.next_frame() {
-                    metrics.record_frame(frame.frame_number, &frame);
+                let mut frame = spare_frame.take().unwrap_or_default();
+                let decoded = if args.realtime && is_playing && !seeked && player.get_target_fps() > 0 {
+                    let target_interval = std::time::Duration::from_nanos(1_000_000_000 / player.get_target_fps() as u64);
+                    player.next_frame_realtime(target_interval).map(|next| match next {
+                        Some(next) => {
+                            frame = next;
+                            true
+                        }
+                        None => false,
+                    })
+                } else {
+                    player.next_frame_into(&mut frame)
+                };
+                metrics.record_decode_errors(player.take_decode_errors());
+                metrics.record_corrupt_frames(player.take_corrupt_frames());
+                metrics.record_dropped_frames(player.take_dropped_frames());
+                if let Some(error) = player.take_pacing_error() {
+                    metrics.record_pacing_error(error);
+                }
+                if let Ok(true) = decoded {
+                    if let Some(scene) = metrics.record_frame(frame.frame_number, &frame) {
+                        log::info!("{}", scene);
+                    }
+                    if let Some(exporter) = &mut frame_exporter {
+                        exporter.submit(&frame);
+                    }
 
-                    // Update texture with frame data
-                    texture
-                        .update(None, &frame.data, (frame.width * 3) as usize)
-                        .map_err(|e| anyhow::anyhow!("Texture update failed: {}", e))?;
+                    // Cropped or newly opened videos can differ from the texture size; uploading
+                    // with a mismatched pitch would shear the image, so recreate it instead
+                    if texture_layout != (frame.width, frame.height, frame.format) {
+                        texture = texture_creator
+                            .create_texture_streaming(texture_format(frame.format), frame.width, frame.height)
+                            .map_err(|e| anyhow::anyhow!("Texture creation failed: {}", e))?;
+                        texture_layout = (frame.width, frame.height, frame.format);
+                    }
 
-                    // Clear and render
-                    canvas.clear();
-                    
-                    // Calculate aspect ratio preserving size
-                    let (window_width, window_height) = canvas.output_size().map_err(|e| anyhow::anyhow!("{}", e))?;
-                    let aspect_ratio = width as f32 / height as f32;
-                    let window_aspect = window_width as f32 / window_height as f32;
-                    
-                    let (dst_width, dst_height) = if window_aspect > aspect_ratio {
-                        let h = window_height;
-                        let w = (h as f32 * aspect_ratio) as u32;
-                        (w, h)
-                    } else {
-                        let w = window_width;
-                        let h = (w as f32 / aspect_ratio) as u32;
-                        (w, h)
-                    };
-                    
-                    let x = (window_width - dst_width) / 2;
-                    let y = (window_height - dst_height) / 2;
-                    
-                    canvas.copy(&texture, None, Rect::new(x as i32, y as i32, dst_width, dst_height)).map_err(|e| anyhow::anyhow!("{}", e))?;
-                    canvas.present();
+                    // Update texture with frame data
+                    let upload_started = Instant::now();
+                    match frame.yuv_planes() {
+                        Some([(y, y_pitch), (u, u_pitch), (v, v_pitch)]) => texture
+                            .update_yuv(None, y, y_pitch, u, u_pitch, v, v_pitch)
+                            .map_err(|e| anyhow::anyhow!("Texture update failed: {}", e))?,
+                        None if frame.format == OutputFormat::Gray8 => texture
+                            .update(None, &frame.rgb_data(), frame.width as usize * 3)
+                            .map_err(|e| anyhow::anyhow!("Texture update failed: {}", e))?,
+                        None => texture
+                            .update(None, &frame.data, frame.strides[0])
+                            .map_err(|e| anyhow::anyhow!("Texture update failed: {}", e))?,
+                    }
+                    upload_time += upload_started.elapsed();
+                    uploads += 1;
 
                     last_frame_time = Instant::now();
 
-                    // Update window title with FPS every 30 frames
-                    if frame.frame_number % 30 == 0 {
-                        let title = format!(
-                            "Metric Video Player - Frame {} - FPS: {:.2} (avg: {:.2})",
-                            frame.frame_number,
-                            metrics.get_current_fps(),
-                            metrics.get_average_fps()
-                        );
-                        canvas.window_mut().set_title(&title).map_err(|e| anyhow::anyhow!("{}", e))?;
-                    }
-
                     if frame.frame_number % 100 == 0 {
                         log::info!(
                             "Frame {}: {:.2} FPS (avg: {:.2})",
@@ -115,26 +409,165 @@ pub fn run_sdl_gui(mut player: VideoPlayer, mut metrics: MetricsCollector, args:
                             metrics.get_average_fps()
                         );
                     }
+
+                    // Keep the pixels around for screenshots, recycling the previous frame's buffer
+                    spare_frame = last_frame.replace(frame);
+                    needs_redraw = true;
                 } else {
-                    // End of video
-                    is_playing = false;
-                    log::info!("Video playback completed");
+                    spare_frame = Some(frame);
                     
-                    // Show final metrics
-                    let session = metrics.finalize_session();
-                    log::info!("\n=== Final Metrics ===");
-                    log::info!("Total frames: {}", session.total_frames);
-                    log::info!("Average FPS: {:.2}", session.average_fps);
-                    log::info!("Max FPS: {:.2}", session.max_fps);
-                    log::info!("Peak Memory: {:.1} MB", session.peak_memory_mb);
-                    log::info!("Session Duration: {:.2}s", session.total_duration_seconds);
+                    let next_player = if playlist.len() > 1 {
+                        playlist.advance(true, args.loop_playback, &args, &mut metrics)
+                    } else {
+                        None
+                    };
+                    if let Some(mut next_player) = next_player {
+                        next_player.set_playback_speed(player.get_playback_speed());
+                        player = next_player;
+                        metrics.resume();
+                    } else if args.loop_playback && player.is_seekable() {
+                        log::info!("End of video, restarting");
+                        player.restart()?;
+                        metrics.mark_discontinuity();
+                        if args.reset_metrics_per_loop {
+                            metrics.reset();
+                        }
+                    } else {
+                        // End of video
+                        is_playing = false;
+                        playback_finished = true;
+                        metrics.pause();
+                        log::info!("Video playback completed");
+                        
+                        // Show final metrics
+                        let session = metrics.finalize_session();
+                        log::info!("\n=== Final Metrics ===");
+                        log::info!("Total frames: {}", session.total_frames);
+                        log::info!("Average FPS: {:.2}", session.average_fps);
+                        log::info!("Max FPS: {:.2}", session.max_render_fps);
+                        log::info!("Peak Memory: {:.1} MB", session.peak_memory_mb);
+                        log::info!("Session Duration: {:.2}s", session.total_duration_seconds);
+                        log::info!("Decode Errors: {}", session.decode_errors);
+                        log::info!("Corrupt Frames: {}", session.corrupt_frames);
+                        if uploads > 0 {
+                            log::info!("Texture upload ({}): {:.3} ms/frame",
+                                texture_layout.2.name(), upload_time.as_secs_f64() * 1000.0 / uploads as f64);
+                        }
+                    }
                 }
             }
         }
 
+        // Redraw after a new frame, or when the window changed (e.g. fullscreen) while paused
+        if needs_redraw {
+            needs_redraw = false;
+            canvas.clear();
+            
+            // Calculate aspect ratio preserving size (pixels aren't square in anamorphic video)
+            let (window_width, window_height) = canvas.output_size().map_err(|e| anyhow::anyhow!("{}", e))?;
+            let aspect_ratio = player.get_display_aspect_ratio() as f32;
+            let window_aspect = window_width as f32 / window_height as f32;
+            
+            let (dst_width, dst_height) = if window_aspect > aspect_ratio {
+                let h = window_height;
+                let w = (h as f32 * aspect_ratio) as u32;
+                (w, h)
+            } else {
+                let w = window_width;
+                let h = (w as f32 / aspect_ratio) as u32;
+                (w, h)
+            };
+            
+            let x = (window_width - dst_width) / 2;
+            let y = (window_height - dst_height) / 2;
+            
+            canvas.copy(&texture, None, Rect::new(x as i32, y as i32, dst_width, dst_height)).map_err(|e| anyhow::anyhow!("{}", e))?;
+            
+            if let (true, Some(font)) = (show_osd, &osd_font) {
+                if !osd_updated.is_some_and(|updated| updated.elapsed() < OSD_REFRESH_INTERVAL) {
+                    osd_updated = Some(Instant::now());
+                    let current_fps = metrics.get_current_fps();
+                    let total_frames = player.get_total_frames();
+                    let text = format!(
+                        "FPS: {:.1}  Avg: {:.1}\nTime: {}\nFrame: {} / {}\nFrame time: {:.2} ms\nMemory: {:.1} MB  CPU: {:.1}%\nDropped: {}",
+                        current_fps,
+                        metrics.get_average_fps(),
+                        player.format_position(),
+                        player.get_current_frame(),
+                        if total_frames > 0 { total_frames.to_string() } else { "?".to_string() },
+                        if current_fps > 0.0 { 1000.0 / current_fps } else { 0.0 },
+                        metrics.get_current_memory_mb(),
+                        metrics.get_current_cpu_percent(),
+                        metrics.get_dropped_frames()
+                    );
+                    if text != osd_text {
+                        let surface = font
+                            .render(&text)
+                            .blended_wrapped(Color::RGB(255, 255, 255), OSD_WRAP_WIDTH)
+                            .map_err(|e| anyhow::anyhow!("Text rendering failed: {}", e))?;
+                        osd_texture = Some(
+                            texture_creator
+                                .create_texture_from_surface(&surface)
+                                .map_err(|e| anyhow::anyhow!("Texture creation failed: {}", e))?,
+                        );
+                        osd_text = text;
+                    }
+                }
+                
+                if let Some(osd_texture) = &osd_texture {
+                    let query = osd_texture.query();
+                    let target = Rect::new(x as i32 + 8, y as i32 + 8, query.width, query.height);
+                    canvas.set_draw_color(Color::RGBA(0, 0, 0, 160));
+                    canvas.set_blend_mode(sdl2::render::BlendMode::Blend);
+                    canvas
+                        .fill_rect(Rect::new(target.x() - 4, target.y() - 2, target.width() + 8, target.height() + 4))
+                        .map_err(|e| anyhow::anyhow!("{}", e))?;
+                    canvas.set_draw_color(Color::RGB(0, 0, 0));
+                    canvas.copy(osd_texture, None, target).map_err(|e| anyhow::anyhow!("{}", e))?;
+                }
+            }
+            
+            if let Some((_, status_texture)) = &status_message {
+                let query = status_texture.query();
+                let target = Rect::new(
+                    x as i32 + 8,
+                    (y + dst_height) as i32 - query.height as i32 - 8,
+                    query.width,
+                    query.height,
+                );
+                canvas.set_draw_color(Color::RGBA(0, 0, 0, 160));
+                canvas.set_blend_mode(sdl2::render::BlendMode::Blend);
+                canvas
+                    .fill_rect(Rect::new(target.x() - 4, target.y() - 2, target.width() + 8, target.height() + 4))
+                    .map_err(|e| anyhow::anyhow!("{}", e))?;
+                canvas.set_draw_color(Color::RGB(0, 0, 0));
+                canvas.copy(status_texture, None, target).map_err(|e| anyhow::anyhow!("{}", e))?;
+            }
+            
+            canvas.present();
+        }
+
+        let state = if is_playing {
+            PlaybackState::Playing
+        } else if playback_finished {
+            PlaybackState::Finished
+        } else {
+            PlaybackState::Paused
+        };
+        if let Some(title) = window_title.update(&player, &playlist, player.get_position(), metrics.get_current_fps(), state) {
+            canvas.window_mut().set_title(title).map_err(|e| anyhow::anyhow!("{}", e))?;
+        }
+
         // Small delay to prevent maxing out CPU when paused
         std::thread::sleep(std::time::Duration::from_millis(1));
     }
+    
+    // Closing the window ends the session, so that's when metrics are written
+    metrics.print_summary();
+    if let Some(export_path) = &args.export_metrics {
+        log::info!("Exporting metrics to: {:?}", export_path);
+        metrics.export_to_file(export_path, args.metrics_format)?;
+    }
 
     Ok(())
 }
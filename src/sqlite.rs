@@ -0,0 +1,145 @@
+use crate::metrics::SessionMetrics;
+use anyhow::Result;
+use std::path::Path;
+
+/// Only created when missing, so one database collects any number of runs
+#[cfg(feature = "sqlite")]
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS sessions (
+    id INTEGER PRIMARY KEY,
+    label TEXT NOT NULL,
+    exported_at TEXT NOT NULL,
+    start_time TEXT NOT NULL,
+    codec TEXT,
+    width INTEGER,
+    height INTEGER,
+    total_frames INTEGER NOT NULL,
+    duration_seconds REAL NOT NULL,
+    average_fps REAL NOT NULL,
+    min_fps REAL NOT NULL,
+    max_fps REAL NOT NULL,
+    frametime_stddev_ms REAL NOT NULL,
+    peak_memory_mb REAL NOT NULL,
+    average_cpu_percent REAL NOT NULL,
+    dropped_frames INTEGER NOT NULL,
+    decode_errors INTEGER NOT NULL,
+    summary TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS frames (
+    session_id INTEGER NOT NULL REFERENCES sessions(id),
+    frame_number INTEGER NOT NULL,
+    timestamp REAL NOT NULL,
+    processing_time_ms REAL NOT NULL,
+    conversion_time_ms REAL NOT NULL,
+    memory_usage_mb REAL NOT NULL,
+    cpu_usage_percent REAL NOT NULL,
+    packet_bytes INTEGER NOT NULL,
+    bitrate_kbps REAL NOT NULL,
+    frame_type TEXT NOT NULL,
+    is_keyframe INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS frames_session ON frames (session_id);
+";
+
+/// Add `session` as a new row of `sessions` and its samples to `frames`, in one transaction.
+/// The full summary is kept as JSON in `sessions.summary` for fields without a column.
+#[cfg(feature = "sqlite")]
+pub fn append_run(path: &Path, label: &str, mut session: SessionMetrics) -> Result<()> {
+    use anyhow::Context;
+    use rusqlite::{params, Connection};
+    
+    let frames = std::mem::take(&mut session.frame_metrics);
+    let summary = serde_json::to_string(&session)?;
+    
+    let mut connection = Connection::open(path).with_context(|| format!("Failed to open database {:?}", path))?;
+    connection.execute_batch(SCHEMA).context("Failed to create metrics tables")?;
+    let transaction = connection.transaction()?;
+    let info = session.video_info.as_ref();
+    transaction.execute(
+        "INSERT INTO sessions (label, exported_at, start_time, codec, width, height, total_frames, duration_seconds,
+            average_fps, min_fps, max_fps, frametime_stddev_ms, peak_memory_mb, average_cpu_percent,
+            dropped_frames, decode_errors, summary)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)",
+        params![
+            label,
+            chrono::Utc::now().to_rfc3339(),
+            session.start_time.to_rfc3339(),
+            info.map(|info| info.codec.as_str()),
+            info.map(|info| info.width),
+            info.map(|info| info.height),
+            session.total_frames as i64,
+            session.total_duration_seconds,
+            session.average_fps,
+            session.min_fps,
+            session.max_fps,
+            session.frametime_stddev_ms,
+            session.peak_memory_mb,
+            session.average_cpu_percent,
+            session.dropped_frames as i64,
+            session.decode_errors as i64,
+            summary,
+        ],
+    )?;
+    let session_id = transaction.last_insert_rowid();
+    
+    {
+        let mut insert = transaction.prepare(
+            "INSERT INTO frames (session_id, frame_number, timestamp, processing_time_ms, conversion_time_ms,
+                memory_usage_mb, cpu_usage_percent, packet_bytes, bitrate_kbps, frame_type, is_keyframe)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+        )?;
+        for m in &frames {
+            insert.execute(params![
+                session_id,
+                m.frame_number as i64,
+                m.timestamp,
+                m.processing_time_ms,
+                m.conversion_time_ms,
+                m.memory_usage_mb,
+                m.cpu_usage_percent,
+                m.packet_bytes as i64,
+                m.bitrate_kbps,
+                m.frame_type.to_string(),
+                m.is_keyframe,
+            ])?;
+        }
+    }
+    transaction.commit().context("Failed to write metrics to the database")?;
+    
+    log::info!("Appended run '{}' ({} frames) to {:?}", label, frames.len(), path);
+    Ok(())
+}
+
+#[cfg(not(feature = "sqlite"))]
+pub fn append_run(path: &Path, _label: &str, _session: SessionMetrics) -> Result<()> {
+    anyhow::bail!("Can't export to {:?}: built without the `sqlite` feature (cargo build --features sqlite)", path)
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use super::*;
+    use crate::metrics::{MetricsCollector, MetricsRetention, DEFAULT_STATS_INTERVAL};
+    use crate::video_player::VideoFrame;
+    
+    fn session(frames: u64) -> SessionMetrics {
+        let mut metrics = MetricsCollector::new(MetricsRetention::All, DEFAULT_STATS_INTERVAL);
+        for frame_number in 1..=frames {
+            metrics.record_frame(frame_number, &VideoFrame { frame_number, ..VideoFrame::default() });
+        }
+        metrics.finalize_session()
+    }
+    
+    #[test]
+    fn runs_are_appended_to_one_database() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("runs.db");
+        append_run(&path, "first", session(3)).unwrap();
+        append_run(&path, "second", session(5)).unwrap();
+        
+        let connection = rusqlite::Connection::open(&path).unwrap();
+        let count = |sql: &str| connection.query_row(sql, [], |row| row.get::<_, i64>(0)).unwrap();
+        assert_eq!(count("SELECT COUNT(*) FROM sessions"), 2);
+        assert_eq!(count("SELECT COUNT(*) FROM frames"), 8);
+        assert_eq!(count("SELECT COUNT(*) FROM frames JOIN sessions ON sessions.id = session_id WHERE label = 'second'"), 5);
+    }
+}
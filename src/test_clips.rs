@@ -0,0 +1,137 @@
+//! Small clips encoded on the fly with FFmpeg, so tests don't need fixture files in the repo.
+
+use ffmpeg_next as ffmpeg;
+use std::path::{Path, PathBuf};
+
+/// What `write_clip` encodes. Frames are YUV420P with pts 0, 1, 2, ... in 1/`fps` units.
+pub struct Clip {
+    pub width: u32,
+    pub height: u32,
+    pub frames: u32,
+    pub fps: i32,
+    /// Encoder name, e.g. "mpeg4", or "ffv1" for lossless
+    pub codec: &'static str,
+    /// Picks the container
+    pub extension: &'static str,
+    /// Keyframe every `gop` frames, scene cut detection is disabled
+    pub gop: u32,
+    /// Counterclockwise display matrix angle, as `av_display_rotation_set` takes it
+    pub rotation: Option<f64>,
+    pub colorspace: ffmpeg::color::Space,
+    pub range: ffmpeg::color::Range,
+    /// Fills the planes of frame `index`
+    pub fill: fn(&mut ffmpeg::frame::Video, u32),
+}
+
+impl Default for Clip {
+    fn default() -> Self {
+        Self {
+            width: 64,
+            height: 48,
+            frames: 10,
+            fps: 30,
+            codec: "mpeg4",
+            extension: "mp4",
+            gop: 12,
+            rotation: None,
+            colorspace: ffmpeg::color::Space::Unspecified,
+            range: ffmpeg::color::Range::Unspecified,
+            fill: gradient,
+        }
+    }
+}
+
+/// A diagonal gradient that moves one pixel per frame, so no two frames are the same.
+pub fn gradient(frame: &mut ffmpeg::frame::Video, index: u32) {
+    for plane in 0..3 {
+        let (width, height) = if plane == 0 {
+            (frame.width(), frame.height())
+        } else {
+            (frame.width().div_ceil(2), frame.height().div_ceil(2))
+        };
+        let stride = frame.stride(plane);
+        let data = frame.data_mut(plane);
+        for y in 0..height {
+            for x in 0..width {
+                data[y as usize * stride + x as usize] = (x + y * 2 + index + plane as u32 * 64) as u8;
+            }
+        }
+    }
+}
+
+/// Encode `clip` into `dir`, returning the file's path.
+pub fn write_clip(dir: &Path, name: &str, clip: &Clip) -> PathBuf {
+    ffmpeg::init().unwrap();
+    let path = dir.join(format!("{}.{}", name, clip.extension));
+    let mut output = ffmpeg::format::output(&path).unwrap();
+    let codec = ffmpeg::encoder::find_by_name(clip.codec).expect("encoder missing from this FFmpeg build");
+    let global_header = output.format().flags().contains(ffmpeg::format::Flags::GLOBAL_HEADER);
+    
+    let time_base = ffmpeg::Rational(1, clip.fps);
+    let mut encoder = ffmpeg::codec::context::Context::new_with_codec(codec).encoder().video().unwrap();
+    encoder.set_width(clip.width);
+    encoder.set_height(clip.height);
+    encoder.set_format(ffmpeg::format::Pixel::YUV420P);
+    encoder.set_time_base(time_base);
+    encoder.set_frame_rate(Some(ffmpeg::Rational(clip.fps, 1)));
+    encoder.set_gop(clip.gop);
+    encoder.set_max_b_frames(0);
+    encoder.set_colorspace(clip.colorspace);
+    encoder.set_color_range(clip.range);
+    if global_header {
+        encoder.set_flags(ffmpeg::codec::Flags::GLOBAL_HEADER);
+    }
+    let mut options = ffmpeg::Dictionary::new();
+    options.set("sc_threshold", "1000000000");
+    let mut encoder = encoder.open_as_with(codec, options).unwrap();
+    
+    {
+        let mut stream = output.add_stream(codec).unwrap();
+        stream.set_parameters(&encoder);
+        stream.set_time_base(time_base);
+        if let Some(angle) = clip.rotation {
+            unsafe {
+                let parameters = (*stream.as_mut_ptr()).codecpar;
+                let side_data = ffmpeg_sys_next::av_packet_side_data_new(
+                    &mut (*parameters).coded_side_data,
+                    &mut (*parameters).nb_coded_side_data,
+                    ffmpeg_sys_next::AVPacketSideDataType::AV_PKT_DATA_DISPLAYMATRIX,
+                    9 * std::mem::size_of::<i32>(),
+                    0,
+                );
+                ffmpeg_sys_next::av_display_rotation_set((*side_data).data as *mut i32, angle);
+            }
+        }
+    }
+    output.write_header().unwrap();
+    let stream_time_base = output.stream(0).unwrap().time_base();
+    
+    for index in 0..clip.frames {
+        // A fresh frame each time, the encoder may still hold a reference to the last one
+        let mut frame = ffmpeg::frame::Video::new(ffmpeg::format::Pixel::YUV420P, clip.width, clip.height);
+        frame.set_color_space(clip.colorspace);
+        frame.set_color_range(clip.range);
+        (clip.fill)(&mut frame, index);
+        frame.set_pts(Some(index as i64));
+        encoder.send_frame(&frame).unwrap();
+        write_packets(&mut encoder, &mut output, time_base, stream_time_base);
+    }
+    encoder.send_eof().unwrap();
+    write_packets(&mut encoder, &mut output, time_base, stream_time_base);
+    output.write_trailer().unwrap();
+    path
+}
+
+fn write_packets(
+    encoder: &mut ffmpeg::encoder::Video,
+    output: &mut ffmpeg::format::context::Output,
+    time_base: ffmpeg::Rational,
+    stream_time_base: ffmpeg::Rational,
+) {
+    let mut packet = ffmpeg::Packet::empty();
+    while encoder.receive_packet(&mut packet).is_ok() {
+        packet.set_stream(0);
+        packet.rescale_ts(time_base, stream_time_base);
+        packet.write_interleaved(output).unwrap();
+    }
+}
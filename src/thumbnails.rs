@@ -0,0 +1,143 @@
+use crate::video_player::{FrameHash, HwAccel, OutputFormat, PlayerOptions, VideoFrame, VideoPlayer};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::time::Duration;
+
+/// Height previews are decoded at, the width follows the aspect ratio
+const THUMBNAIL_HEIGHT: u32 = 90;
+
+/// Positions along the seek bar previews are made for. Hovering snaps to the nearest one,
+/// so moving the mouse back and forth reuses the same few frames
+const THUMBNAIL_SLOTS: u32 = 200;
+
+/// Seek bar previews, decoded at low resolution by a second player on a worker thread.
+pub struct Thumbnails {
+    range: (Duration, Duration),
+    requests: Sender<u32>,
+    results: Receiver<(u32, Option<VideoFrame>)>,
+    requested: Option<u32>, // Slot sent to the worker and not answered yet
+    cache: HashMap<u32, Option<egui::TextureHandle>>, // None when the slot couldn't be decoded
+    failed: bool, // The worker couldn't open the video
+}
+
+impl Thumbnails {
+    /// Open `source` again for previews over `range`, with `options` of the main player.
+    pub fn new(source: &Path, options: &PlayerOptions, range: (Duration, Duration), ctx: egui::Context) -> Self {
+        // Software decoding and no extra analysis, so previews stay out of the measured player's way
+        let options = PlayerOptions {
+            target_fps: 0,
+            output_width: None,
+            output_height: Some(THUMBNAIL_HEIGHT),
+            scale_factor: None,
+            output_format: OutputFormat::Rgba,
+            yuv_direct: false,
+            hwaccel: HwAccel::None,
+            probe: false,
+            start: None,
+            end: None,
+            hash_frames: false,
+            frame_hash: FrameHash::None,
+            ..options.clone()
+        };
+        let (requests, request_receiver) = mpsc::channel::<u32>();
+        let (result_sender, results) = mpsc::channel();
+        
+        std::thread::spawn({
+            let source = source.to_path_buf();
+            move || {
+                let mut player = match VideoPlayer::new(&source, &options) {
+                    Ok(player) => player,
+                    Err(e) => {
+                        log::warn!("Seek bar previews disabled: {:#}", e);
+                        return;
+                    }
+                };
+                while let Ok(mut slot) = request_receiver.recv() {
+                    // Only the latest position matters, skip the ones the mouse has already left
+                    while let Ok(newer) = request_receiver.try_recv() {
+                        slot = newer;
+                    }
+                    let frame = player
+                        .seek_to_keyframe(slot_time(range, slot))
+                        .and_then(|()| player.next_frame())
+                        .unwrap_or_else(|e| {
+                            log::debug!("No preview for slot {}: {:#}", slot, e);
+                            None
+                        });
+                    if result_sender.send((slot, frame)).is_err() {
+                        return;
+                    }
+                    ctx.request_repaint();
+                }
+            }
+        });
+        
+        Self {
+            range,
+            requests,
+            results,
+            requested: None,
+            cache: HashMap::new(),
+            failed: false,
+        }
+    }
+    
+    /// Show the preview for `position` in seconds, or a placeholder while it's decoded.
+    pub fn show(&mut self, ui: &mut egui::Ui, position: f64) {
+        self.collect(ui.ctx());
+        if self.failed {
+            return;
+        }
+        
+        let (start, end) = self.range;
+        let span = (end - start).as_secs_f64();
+        let fraction = if span > 0.0 { (position - start.as_secs_f64()) / span } else { 0.0 };
+        let slot = (fraction.clamp(0.0, 1.0) * THUMBNAIL_SLOTS as f64).round() as u32;
+        
+        match self.cache.get(&slot) {
+            Some(Some(texture)) => {
+                ui.add(egui::Image::new(texture).fit_to_exact_size(texture.size_vec2()));
+            }
+            Some(None) => {}
+            None => {
+                if self.requested != Some(slot) {
+                    // Only fails once the worker is gone, which collect notices next frame
+                    let _ = self.requests.send(slot);
+                    self.requested = Some(slot);
+                }
+                ui.add_sized([THUMBNAIL_HEIGHT as f32 * 16.0 / 9.0, THUMBNAIL_HEIGHT as f32], egui::Spinner::new());
+            }
+        }
+    }
+    
+    /// Turn decoded previews into textures.
+    fn collect(&mut self, ctx: &egui::Context) {
+        loop {
+            match self.results.try_recv() {
+                Ok((slot, frame)) => {
+                    if self.requested == Some(slot) {
+                        self.requested = None;
+                    }
+                    let texture = frame.map(|frame| {
+                        let size = [frame.width as usize, frame.height as usize];
+                        // Video is opaque, so the RGBA data is already premultiplied
+                        let image = egui::ColorImage::from_rgba_premultiplied(size, &frame.data);
+                        ctx.load_texture(format!("thumbnail_{}", slot), image, egui::TextureOptions::LINEAR)
+                    });
+                    self.cache.insert(slot, texture);
+                }
+                Err(TryRecvError::Empty) => return,
+                Err(TryRecvError::Disconnected) => {
+                    self.failed = true;
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// Position in the video a preview slot stands for.
+fn slot_time((start, end): (Duration, Duration), slot: u32) -> Duration {
+    start + (end - start).mul_f64(slot as f64 / THUMBNAIL_SLOTS as f64)
+}
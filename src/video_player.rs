@@ -1,61 +1,1450 @@
 use anyhow::{Context, Result};
 use ffmpeg_next as ffmpeg;
-use std::path::Path;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::ffi::CStr;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use std::time::{Duration, Instant};
 
+/// Pixel layout of `VideoFrame::data`, planes one after another with rows `VideoFrame::strides` apart.
+/// Converted frames are tightly packed; `--yuv-direct` passthrough keeps the decoder's padding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum OutputFormat {
+    #[default]
+    Rgb24,
+    Rgba,
+    /// Planar Y, U, V with chroma at half resolution
+    Yuv420p,
+    /// Luma only, one byte per pixel
+    Gray8,
+}
+
+impl OutputFormat {
+    pub fn pixel(self) -> ffmpeg::format::Pixel {
+        match self {
+            OutputFormat::Rgb24 => ffmpeg::format::Pixel::RGB24,
+            OutputFormat::Rgba => ffmpeg::format::Pixel::RGBA,
+            OutputFormat::Yuv420p => ffmpeg::format::Pixel::YUV420P,
+            OutputFormat::Gray8 => ffmpeg::format::Pixel::GRAY8,
+        }
+    }
+    
+    pub fn name(self) -> &'static str {
+        match self {
+            OutputFormat::Rgb24 => "rgb24",
+            OutputFormat::Rgba => "rgba",
+            OutputFormat::Yuv420p => "yuv420p",
+            OutputFormat::Gray8 => "gray8",
+        }
+    }
+    
+    /// Bytes per pixel of the first plane.
+    pub fn bytes_per_pixel(self) -> usize {
+        match self {
+            OutputFormat::Rgb24 => 3,
+            OutputFormat::Rgba => 4,
+            OutputFormat::Yuv420p | OutputFormat::Gray8 => 1,
+        }
+    }
+    
+    /// (width, height) in pixels of each plane for a `width`x`height` frame.
+    pub fn planes(self, width: u32, height: u32) -> impl Iterator<Item = (u32, u32)> + Clone {
+        let chroma = (width.div_ceil(2), height.div_ceil(2));
+        let count = match self {
+            OutputFormat::Rgb24 | OutputFormat::Rgba | OutputFormat::Gray8 => 1,
+            OutputFormat::Yuv420p => 3,
+        };
+        [(width, height), chroma, chroma].into_iter().take(count)
+    }
+    
+    /// Size in bytes of a tightly packed `width`x`height` frame.
+    pub fn frame_bytes(self, width: u32, height: u32) -> usize {
+        self.planes(width, height).map(|(w, h)| w as usize * self.bytes_per_pixel() * h as usize).sum()
+    }
+}
+
+/// A decoded frame converted to `format`, reused between decodes by `VideoPlayer::next_frame_into`.
+#[derive(Clone, Default)]
 pub struct VideoFrame {
     pub data: Vec<u8>,
+    pub format: OutputFormat,
     pub width: u32,
     pub height: u32,
+    /// Row length in bytes of each plane, including any padding
+    pub strides: Vec<usize>,
     pub timestamp: Duration,
     pub frame_number: u64,
+    /// Time spent converting the decoded frame into `format`
+    pub conversion_time: Duration,
+    /// The decoder's planes were passed through without swscale (`--yuv-direct`)
+    pub passthrough: bool,
+    /// Hash of the visible pixels, only computed with `--detect-duplicates`
+    pub content_hash: Option<u64>,
+    /// Hex digest of the visible pixels for `--hash-frames`
+    pub digest: Option<String>,
+    /// Time spent computing `digest`
+    pub hash_time: Duration,
+    /// Time spent in the deinterlacing and `--vf` filters since the previous frame
+    pub filter_time: Duration,
+    /// Size of the compressed packet. Packets are matched to frames in decode order, so with
+    /// B-frames this is a nearby packet's size rather than exactly this frame's
+    pub packet_bytes: usize,
+    /// Picture type the decoder reports: 'I', 'P', 'B', 'S' and so on, '?' when unknown
+    pub frame_type: char,
+    pub is_keyframe: bool,
+    /// Time the frame should be shown for, see `VideoPlayer::ideal_frame_interval`
+    pub ideal_interval: Duration,
+    /// Matrix and full range of YUV `data`, which `rgb_data` converts with; None for RGB formats
+    pub yuv_colors: Option<(ColorMatrix, bool)>,
+}
+
+impl std::fmt::Debug for VideoFrame {
+    /// Leaves out the pixels, which would be megabytes of numbers.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("VideoFrame")
+            .field("frame_number", &self.frame_number)
+            .field("timestamp", &self.timestamp)
+            .field("format", &self.format)
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .field("data_len", &self.data.len())
+            .finish_non_exhaustive()
+    }
 }
 
+impl VideoFrame {
+    /// Luma of a `cols`x`rows` grid of pixels sampled across the frame, a cheap stand-in for the whole image.
+    pub fn luma_thumbnail(&self, cols: u32, rows: u32) -> Vec<u8> {
+        let mut thumbnail = Vec::with_capacity((cols * rows) as usize);
+        for row in 0..rows {
+            let y = ((row as u64 * 2 + 1) * self.height as u64 / (rows as u64 * 2)) as usize;
+            for col in 0..cols {
+                let x = ((col as u64 * 2 + 1) * self.width as u64 / (cols as u64 * 2)) as usize;
+                let luma = match self.format {
+                    OutputFormat::Yuv420p | OutputFormat::Gray8 => self.data[y * self.strides[0] + x],
+                    OutputFormat::Rgb24 | OutputFormat::Rgba => {
+                        let i = y * self.strides[0] + x * self.format.bytes_per_pixel();
+                        let (r, g, b) = (self.data[i] as u32, self.data[i + 1] as u32, self.data[i + 2] as u32);
+                        // BT.601 weights in fixed point
+                        ((77 * r + 150 * g + 29 * b) >> 8) as u8
+                    }
+                };
+                thumbnail.push(luma);
+            }
+        }
+        thumbnail
+    }
+    
+    /// Average luma (0-255) over every visible pixel, with BT.601 weights for RGB frames.
+    pub fn mean_luma(&self) -> f64 {
+        let pixels = self.width as u64 * self.height as u64;
+        if pixels == 0 {
+            return 0.0;
+        }
+        
+        let row_bytes = self.width as usize * self.format.bytes_per_pixel();
+        let rows = self.data.chunks(self.strides[0]).take(self.height as usize).map(|row| &row[..row_bytes]);
+        let sum = match self.format {
+            // The Y plane comes first and already is luma
+            OutputFormat::Yuv420p | OutputFormat::Gray8 => rows.map(|row| row.iter().map(|&y| y as u64).sum::<u64>()).sum::<u64>() as f64,
+            OutputFormat::Rgb24 | OutputFormat::Rgba => {
+                let weighted: u64 = rows
+                    .flat_map(|row| row.chunks_exact(self.format.bytes_per_pixel()))
+                    .map(|pixel| 77 * pixel[0] as u64 + 150 * pixel[1] as u64 + 29 * pixel[2] as u64)
+                    .sum();
+                weighted as f64 / 256.0
+            }
+        };
+        sum / pixels as f64
+    }
+    
+    /// Rows of every plane without their padding, i.e. just the visible pixels.
+    fn visible_rows(&self) -> impl Iterator<Item = &[u8]> {
+        let mut offset = 0;
+        self.format.planes(self.width, self.height).zip(&self.strides).flat_map(move |((plane_width, plane_height), &stride)| {
+            let row_bytes = plane_width as usize * self.format.bytes_per_pixel();
+            let plane_start = offset;
+            offset += stride * plane_height as usize;
+            (0..plane_height as usize).map(move |row| &self.data[plane_start + row * stride..][..row_bytes])
+        })
+    }
+    
+    /// Hash of the visible pixels, ignoring row padding, for spotting repeated frames.
+    pub fn compute_content_hash(&self) -> u64 {
+        self.visible_rows().fold(0, hash_bytes)
+    }
+    
+    /// Hex digest of the visible pixels with `kind`, None for `FrameHash::None` or when FFmpeg
+    /// lacks the algorithm. Padding is skipped, so the same picture always gives the same digest.
+    pub fn compute_digest(&self, kind: FrameHash) -> Option<String> {
+        let name = match kind {
+            FrameHash::None => return None,
+            FrameHash::Xxhash => {
+                let mut hasher = xxhash_rust::xxh64::Xxh64::new(0);
+                self.visible_rows().for_each(|row| hasher.update(row));
+                return Some(format!("{:016x}", hasher.digest()));
+            }
+            FrameHash::Crc32 => c"CRC32",
+            FrameHash::Md5 => c"MD5",
+        };
+        
+        // libavutil's generic hash API for the algorithms FFmpeg has
+        let mut hex = [0u8; 2 * ffmpeg_sys_next::AV_HASH_MAX_SIZE as usize + 1];
+        unsafe {
+            let mut context = std::ptr::null_mut();
+            if ffmpeg_sys_next::av_hash_alloc(&mut context, name.as_ptr()) < 0 {
+                return None;
+            }
+            ffmpeg_sys_next::av_hash_init(context);
+            for row in self.visible_rows() {
+                ffmpeg_sys_next::av_hash_update(context, row.as_ptr(), row.len() as _);
+            }
+            ffmpeg_sys_next::av_hash_final_hex(context, hex.as_mut_ptr(), hex.len() as i32);
+            ffmpeg_sys_next::av_hash_freep(&mut context);
+        }
+        CStr::from_bytes_until_nul(&hex).ok().map(|digest| digest.to_string_lossy().into_owned())
+    }
+    
+    /// Write the frame to `path`, with the image format picked from its extension.
+    pub fn save_image(&self, path: &Path) -> Result<()> {
+        image::save_buffer(path, &self.rgb_data(), self.width, self.height, image::ColorType::Rgb8)
+            .with_context(|| format!("Failed to save {:?}", path))
+    }
+    
+    /// Pixels as RGB24, converting if needed. Video is opaque, so dropping alpha loses
+    /// nothing and keeps formats without an alpha channel (JPEG, BMP) working.
+    pub fn rgb_data(&self) -> Cow<'_, [u8]> {
+        match self.format {
+            OutputFormat::Rgb24 => Cow::Borrowed(&self.data),
+            OutputFormat::Rgba => Cow::Owned(self.data.chunks_exact(4).flat_map(|pixel| [pixel[0], pixel[1], pixel[2]]).collect()),
+            OutputFormat::Yuv420p => Cow::Owned(self.yuv_to_rgb()),
+            OutputFormat::Gray8 => Cow::Owned(self.data.iter().flat_map(|&luma| [luma; 3]).collect()),
+        }
+    }
+    
+    /// The Y, U and V planes with their strides in bytes, or None for packed RGB formats.
+    pub fn yuv_planes(&self) -> Option<[(&[u8], usize); 3]> {
+        if self.format != OutputFormat::Yuv420p {
+            return None;
+        }
+        
+        let chroma_rows = self.height.div_ceil(2) as usize;
+        let (y, rest) = self.data.split_at(self.strides[0] * self.height as usize);
+        let (u, v) = rest.split_at(self.strides[1] * chroma_rows);
+        Some([
+            (y, self.strides[0]),
+            (u, self.strides[1]),
+            (&v[..self.strides[2] * chroma_rows], self.strides[2]),
+        ])
+    }
+    
+    /// Convert YUV420P data to RGB24 with `yuv_colors` (BT.601 limited range when unknown) for saving.
+    fn yuv_to_rgb(&self) -> Vec<u8> {
+        let Some([(y_plane, y_stride), (u_plane, u_stride), (v_plane, v_stride)]) = self.yuv_planes() else {
+            return Vec::new();
+        };
+        let (matrix, full_range) = self.yuv_colors.unwrap_or((ColorMatrix::Bt601, false));
+        let [y_scale, v_to_r, u_to_g, v_to_g, u_to_b] = matrix.rgb_factors(full_range);
+        let black = if full_range { 0 } else { 16 };
+        
+        let mut rgb = Vec::with_capacity(self.width as usize * self.height as usize * 3);
+        for row in 0..self.height as usize {
+            for col in 0..self.width as usize {
+                let c = y_plane[row * y_stride + col] as i32 - black;
+                let d = u_plane[row / 2 * u_stride + col / 2] as i32 - 128;
+                let e = v_plane[row / 2 * v_stride + col / 2] as i32 - 128;
+                
+                let r = (y_scale * c + v_to_r * e + 128) >> 8;
+                let g = (y_scale * c - u_to_g * d - v_to_g * e + 128) >> 8;
+                let b = (y_scale * c + u_to_b * d + 128) >> 8;
+                rgb.extend([r, g, b].map(|channel| channel.clamp(0, 255) as u8));
+            }
+        }
+        rgb
+    }
+}
+
+/// Fold `bytes` into `hash` a word at a time (the FxHash mix). Not collision resistant,
+/// but fast enough to run on every frame and plenty for telling frames apart.
+fn hash_bytes(mut hash: u64, bytes: &[u8]) -> u64 {
+    const SEED: u64 = 0x51_7c_c1_b7_27_22_0a_95;
+    let mut mix = |word: u64| hash = (hash.rotate_left(5) ^ word).wrapping_mul(SEED);
+    
+    let mut words = bytes.chunks_exact(8);
+    for word in &mut words {
+        mix(u64::from_le_bytes(word.try_into().unwrap()));
+    }
+    let mut tail = [0u8; 8];
+    tail[..words.remainder().len()].copy_from_slice(words.remainder());
+    mix(u64::from_le_bytes(tail));
+    hash
+}
+
+/// Per-frame digest for `--hash-frames`, to check decoder output is unchanged between builds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum FrameHash {
+    #[default]
+    None,
+    /// XXH64 with seed 0, the cheapest; matches `xxhsum` over the same bytes
+    Xxhash,
+    Crc32,
+    Md5,
+}
+
+impl FrameHash {
+    pub fn name(self) -> &'static str {
+        match self {
+            FrameHash::None => "none",
+            FrameHash::Xxhash => "xxhash",
+            FrameHash::Crc32 => "crc32",
+            FrameHash::Md5 => "md5",
+        }
+    }
+}
+
+/// File extensions treated as videos when picking or scanning for files
+pub const VIDEO_EXTENSIONS: &[&str] = &["mp4", "mkv", "avi", "mov", "webm", "m4v", "flv", "wmv", "ts"];
+
+pub const MIN_PLAYBACK_SPEED: f32 = 0.25;
+pub const MAX_PLAYBACK_SPEED: f32 = 4.0;
+
+/// Relative gap between average and base frame rate above which a stream counts as VFR
+const VFR_TOLERANCE: f64 = 0.01;
+
+/// Settings for opening a video that aren't derived from the file itself.
+#[derive(Debug, Clone, Default)]
+pub struct PlayerOptions {
+    /// Target FPS (0 = maximum possible)
+    pub target_fps: u32,
+    /// Refuse videos whose frames, once converted to `output_format` at the output size, would be
+    /// larger than this (None = no limit)
+    pub max_frame_bytes: Option<usize>,
+    /// Socket I/O timeout for network inputs (None = ffmpeg's default)
+    pub network_timeout: Option<Duration>,
+    /// Container stream to decode (None = ffmpeg's best video stream)
+    pub stream_index: Option<usize>,
+    /// Show frames as stored, ignoring the stream's display matrix
+    pub ignore_rotation: bool,
+    /// Resample anamorphic video to square pixels so frames (and screenshots) look right as-is
+    pub square_pixels: bool,
+    /// Width of the returned frames (None = source width, or derived from `output_height`)
+    pub output_width: Option<u32>,
+    /// Height of the returned frames (None = source height, or derived from `output_width`)
+    pub output_height: Option<u32>,
+    /// Scale frames by this factor, instead of `output_width`/`output_height`
+    pub scale_factor: Option<f64>,
+    /// Pixel layout of the returned frames
+    pub output_format: OutputFormat,
+    /// Return YUV420P video exactly as decoded, without going through swscale
+    pub yuv_direct: bool,
+    /// Hardware decoding device to use, if any
+    pub hwaccel: HwAccel,
+    /// swscale algorithm for pixel format conversion and resampling
+    pub scaler: Scaler,
+    /// Fail on the first packet the decoder rejects instead of skipping it
+    pub strict: bool,
+    /// Read the whole file to count frames when the container doesn't store the count
+    pub probe: bool,
+    /// Start playback here instead of at the beginning
+    pub start: Option<Duration>,
+    /// End playback after the last frame before this timestamp
+    pub end: Option<Duration>,
+    /// Hash every frame so repeated frames can be counted
+    pub hash_frames: bool,
+    /// Digest every frame's pixels, see `VideoFrame::digest`
+    pub frame_hash: FrameHash,
+    /// How to wait out the rest of a frame's interval when pacing to `target_fps`
+    pub pacing: PacingMode,
+    /// Deinterlace frames between decoding and conversion
+    pub deinterlace: Deinterlace,
+    /// Keep only this region of each frame, cut out before scaling
+    pub crop: Option<Crop>,
+    /// FFmpeg filter chain (`-vf` syntax) run on decoded frames ahead of cropping and scaling
+    pub video_filter: Option<String>,
+    /// Curve HDR sources are tone mapped to SDR with
+    pub tonemap: Tonemap,
+    /// Convert YUV with this matrix whatever the metadata says
+    pub assume_colorspace: ColorMatrix,
+    /// Treat YUV as this range whatever the metadata says
+    pub assume_range: YuvRange,
+}
+
+/// Rectangle for `--crop`, written as `x:y:width:height` in pixels of the upright (rotated) frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Crop {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl FromStr for Crop {
+    type Err = String;
+    
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let values = s
+            .split(':')
+            .map(|value| value.trim().parse::<u32>().map_err(|e| format!("invalid crop value '{}': {}", value, e)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let [x, y, width, height] = values[..] else {
+            return Err(format!("expected <x>:<y>:<width>:<height>, got '{}'", s));
+        };
+        if width == 0 || height == 0 {
+            return Err("crop width and height must be at least 1".to_string());
+        }
+        Ok(Self { x, y, width, height })
+    }
+}
+
+impl Crop {
+    /// The same region in the stored frame, which is `width`x`height` and shown turned clockwise by `rotation`.
+    fn unrotate(self, width: u32, height: u32, rotation: u32) -> Self {
+        match rotation {
+            90 => Self { x: self.y, y: height - self.x - self.width, width: self.height, height: self.width },
+            180 => Self { x: width - self.x - self.width, y: height - self.y - self.height, ..self },
+            270 => Self { x: width - self.y - self.height, y: self.x, width: self.height, height: self.width },
+            _ => self,
+        }
+    }
+    
+    /// Cut the region out of `frame` in place by moving its data pointers, without copying pixels.
+    fn apply(self, frame: &mut ffmpeg::frame::Video) -> Result<()> {
+        let (frame_width, frame_height) = (frame.width(), frame.height());
+        // The decoder can shrink frames mid-stream, the region was only checked against the first size
+        if self.x + self.width > frame_width || self.y + self.height > frame_height {
+            anyhow::bail!("Crop region {}x{} at {},{} doesn't fit the {}x{} frame",
+                self.width, self.height, self.x, self.y, frame_width, frame_height);
+        }
+        unsafe {
+            let raw = frame.as_mut_ptr();
+            (*raw).crop_left = self.x as usize;
+            (*raw).crop_top = self.y as usize;
+            (*raw).crop_right = (frame_width - self.x - self.width) as usize;
+            (*raw).crop_bottom = (frame_height - self.y - self.height) as usize;
+            // Unaligned so the region isn't widened to keep the data pointers aligned
+            let result = ffmpeg_sys_next::av_frame_apply_cropping(raw, AV_FRAME_CROP_UNALIGNED);
+            if result < 0 {
+                return Err(ffmpeg::Error::from(result)).context("Failed to crop frame");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// How the player waits for the next frame's deadline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum PacingMode {
+    /// Plain thread sleep, only as precise as the OS timer (often 1-15 ms)
+    Sleep,
+    /// Sleep until shortly before the deadline, then spin for the rest
+    #[default]
+    Hybrid,
+}
+
+/// Final stretch before a deadline that `PacingMode::Hybrid` spins through instead of sleeping
+const SPIN_THRESHOLD: Duration = Duration::from_millis(1);
+
+impl PacingMode {
+    /// Block the thread until `deadline`.
+    fn wait_until(self, deadline: Instant) {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        match self {
+            PacingMode::Sleep => std::thread::sleep(remaining),
+            PacingMode::Hybrid => {
+                if remaining > SPIN_THRESHOLD {
+                    std::thread::sleep(remaining - SPIN_THRESHOLD);
+                }
+                while Instant::now() < deadline {
+                    std::hint::spin_loop();
+                }
+            }
+        }
+    }
+    
+    pub fn name(self) -> &'static str {
+        match self {
+            PacingMode::Sleep => "sleep",
+            PacingMode::Hybrid => "hybrid",
+        }
+    }
+}
+
+/// Resampling algorithm used by swscale when converting frames.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum Scaler {
+    /// Fastest, but blurry and aliased when downscaling
+    #[default]
+    #[value(name = "fast_bilinear")]
+    FastBilinear,
+    Bilinear,
+    Bicubic,
+    Lanczos,
+}
+
+impl Scaler {
+    /// swscale flags for converting frames in `format`.
+    fn flags(self, format: ffmpeg::format::Pixel) -> ffmpeg::software::scaling::Flags {
+        use ffmpeg::software::scaling::Flags;
+        let flags = match self {
+            Scaler::FastBilinear => Flags::FAST_BILINEAR,
+            Scaler::Bilinear => Flags::BILINEAR,
+            Scaler::Bicubic => Flags::BICUBIC,
+            Scaler::Lanczos => Flags::LANCZOS,
+        };
+        // The fast paths drop the extra bits of 10-bit and deeper sources before dithering,
+        // which shows as banding in gradients
+        if pixel_bit_depth(format) > 8 {
+            flags | Flags::ACCURATE_RND | Flags::FULL_CHR_H_INT
+        } else {
+            flags
+        }
+    }
+    
+    pub fn name(self) -> &'static str {
+        match self {
+            Scaler::FastBilinear => "fast_bilinear",
+            Scaler::Bilinear => "bilinear",
+            Scaler::Bicubic => "bicubic",
+            Scaler::Lanczos => "lanczos",
+        }
+    }
+}
+
+/// Hardware decoder selection for `--hwaccel`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum HwAccel {
+    /// Try the platform's usual devices in turn, falling back to software
+    Auto,
+    /// Always decode in software
+    #[default]
+    None,
+    Vaapi,
+    Cuda,
+    Videotoolbox,
+}
+
+impl HwAccel {
+    /// Device types to try, in order of preference.
+    fn device_types(self) -> Vec<ffmpeg_sys_next::AVHWDeviceType> {
+        use ffmpeg_sys_next::AVHWDeviceType::*;
+        match self {
+            HwAccel::None => vec![],
+            HwAccel::Vaapi => vec![AV_HWDEVICE_TYPE_VAAPI],
+            HwAccel::Cuda => vec![AV_HWDEVICE_TYPE_CUDA],
+            HwAccel::Videotoolbox => vec![AV_HWDEVICE_TYPE_VIDEOTOOLBOX],
+            HwAccel::Auto if cfg!(target_os = "macos") => vec![AV_HWDEVICE_TYPE_VIDEOTOOLBOX],
+            HwAccel::Auto => vec![AV_HWDEVICE_TYPE_CUDA, AV_HWDEVICE_TYPE_VAAPI],
+        }
+    }
+}
+
+/// From an anonymous enum in codec.h, which the generated bindings don't expose by name
+const AV_CODEC_HW_CONFIG_METHOD_HW_DEVICE_CTX: i32 = 0x01;
+
+/// From an anonymous enum in frame.h, likewise
+const AV_FRAME_CROP_UNALIGNED: i32 = 0x01;
+
+/// Hardware device attached to a decoder context, and the pixel format its frames arrive in.
+struct HwDecoder {
+    name: String,
+    pixel_format: i32,
+}
+
+/// Pick the hardware pixel format stashed in `opaque` by `attach_hw_device`, or let
+/// ffmpeg choose a software format if the decoder doesn't offer it for this stream.
+unsafe extern "C" fn get_hw_format(
+    context: *mut ffmpeg_sys_next::AVCodecContext,
+    formats: *const ffmpeg_sys_next::AVPixelFormat,
+) -> ffmpeg_sys_next::AVPixelFormat {
+    let wanted = (*context).opaque as isize as i32;
+    let mut format = formats;
+    while *format != ffmpeg_sys_next::AVPixelFormat::AV_PIX_FMT_NONE {
+        if *format as i32 == wanted {
+            return *format;
+        }
+        format = format.add(1);
+    }
+    log::warn!("Decoder didn't offer the hardware pixel format, falling back to software decoding");
+    ffmpeg_sys_next::avcodec_default_get_format(context, formats)
+}
+
+/// Create a hardware device for `requested` and attach it to the (not yet opened) decoder context.
+fn attach_hw_device(context: &mut ffmpeg::codec::context::Context, requested: HwAccel) -> Option<HwDecoder> {
+    let codec = ffmpeg::decoder::find(context.id())?;
+    
+    for device_type in requested.device_types() {
+        let name = unsafe {
+            std::ffi::CStr::from_ptr(ffmpeg_sys_next::av_hwdevice_get_type_name(device_type))
+                .to_string_lossy()
+                .into_owned()
+        };
+        
+        // Find the pixel format this codec produces on the device, if it supports it at all
+        let mut pixel_format = None;
+        for index in 0.. {
+            let config = unsafe { ffmpeg_sys_next::avcodec_get_hw_config(codec.as_ptr(), index) };
+            if config.is_null() {
+                break;
+            }
+            let config = unsafe { &*config };
+            if config.methods & AV_CODEC_HW_CONFIG_METHOD_HW_DEVICE_CTX != 0
+                && config.device_type == device_type
+            {
+                pixel_format = Some(config.pix_fmt as i32);
+                break;
+            }
+        }
+        let Some(pixel_format) = pixel_format else {
+            log::debug!("{} can't decode {} in hardware", name, codec.name());
+            continue;
+        };
+        
+        let mut device = std::ptr::null_mut();
+        let result = unsafe {
+            ffmpeg_sys_next::av_hwdevice_ctx_create(&mut device, device_type, std::ptr::null(), std::ptr::null_mut(), 0)
+        };
+        if result < 0 {
+            log::debug!("Couldn't create {} device: {}", name, ffmpeg::Error::from(result));
+            continue;
+        }
+        
+        // The codec context takes ownership of the device reference and frees it on close
+        unsafe {
+            let raw = context.as_mut_ptr();
+            (*raw).hw_device_ctx = device;
+            (*raw).opaque = pixel_format as isize as *mut std::ffi::c_void;
+            (*raw).get_format = Some(get_hw_format);
+        }
+        return Some(HwDecoder { name, pixel_format });
+    }
+    None
+}
+
+/// Deinterlacing for `--deinterlace`, done by FFmpeg's yadif filter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum Deinterlace {
+    #[default]
+    None,
+    /// Like yadif, but only once a frame is flagged as interlaced, and only for flagged frames
+    Auto,
+    /// One frame per frame, rebuilding the missing field from its neighbours
+    Yadif,
+    /// One frame per field, doubling the frame rate
+    Bob,
+}
+
+impl Deinterlace {
+    /// yadif options, None when not deinterlacing.
+    fn filter_options(self) -> Option<&'static str> {
+        match self {
+            Deinterlace::None => None,
+            Deinterlace::Auto => Some("mode=send_frame:deint=interlaced"),
+            Deinterlace::Yadif => Some("mode=send_frame"),
+            Deinterlace::Bob => Some("mode=send_field"),
+        }
+    }
+    
+    pub fn name(self) -> &'static str {
+        match self {
+            Deinterlace::None => "none",
+            Deinterlace::Auto => "auto",
+            Deinterlace::Yadif => "yadif",
+            Deinterlace::Bob => "bob",
+        }
+    }
+}
+
+/// YUV to RGB matrix for `--assume-colorspace`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum ColorMatrix {
+    /// From the frames' metadata, else BT.709 from 720 lines up and BT.601 below
+    #[default]
+    Auto,
+    /// SD video (BT.470BG / SMPTE 170M)
+    Bt601,
+    /// HD video
+    Bt709,
+    /// UHD and HDR video
+    Bt2020,
+    /// Early HD video
+    Smpte240m,
+}
+
+impl ColorMatrix {
+    /// The matrix FFmpeg's colorspace tag stands for, None when unspecified or not YUV.
+    fn from_space(space: ffmpeg::color::Space) -> Option<Self> {
+        use ffmpeg::color::Space;
+        match space {
+            Space::BT709 => Some(ColorMatrix::Bt709),
+            Space::BT470BG | Space::SMPTE170M => Some(ColorMatrix::Bt601),
+            Space::BT2020NCL | Space::BT2020CL => Some(ColorMatrix::Bt2020),
+            Space::SMPTE240M => Some(ColorMatrix::Smpte240m),
+            _ => None,
+        }
+    }
+    
+    /// Red and blue luma weights (Kr, Kb), BT.601's for `Auto`.
+    fn luma_weights(self) -> (f64, f64) {
+        match self {
+            ColorMatrix::Auto | ColorMatrix::Bt601 => (0.299, 0.114),
+            ColorMatrix::Bt709 => (0.2126, 0.0722),
+            ColorMatrix::Bt2020 => (0.2627, 0.0593),
+            ColorMatrix::Smpte240m => (0.212, 0.087),
+        }
+    }
+    
+    /// 8.8 fixed point factors for YUV to RGB: luma scale, V to red, U and V subtracted from
+    /// green, U to blue. BT.601 limited range gives the familiar 298, 409, 100, 208, 516.
+    fn rgb_factors(self, full_range: bool) -> [i32; 5] {
+        let (kr, kb) = self.luma_weights();
+        let kg = 1.0 - kr - kb;
+        let (luma_scale, chroma_scale) = if full_range { (1.0, 1.0) } else { (255.0 / 219.0, 255.0 / 224.0) };
+        [
+            luma_scale,
+            2.0 * (1.0 - kr) * chroma_scale,
+            2.0 * (1.0 - kb) * kb / kg * chroma_scale,
+            2.0 * (1.0 - kr) * kr / kg * chroma_scale,
+            2.0 * (1.0 - kb) * chroma_scale,
+        ]
+        .map(|factor| (factor * 256.0).round() as i32)
+    }
+    
+    /// What untagged video of `height` lines most likely uses, as players commonly guess.
+    fn guess(height: u32) -> Self {
+        if height >= 720 { ColorMatrix::Bt709 } else { ColorMatrix::Bt601 }
+    }
+    
+    /// swscale's SWS_CS_* constant.
+    fn sws_colorspace(self) -> i32 {
+        (match self {
+            ColorMatrix::Auto => ffmpeg_sys_next::SWS_CS_DEFAULT,
+            ColorMatrix::Bt601 => ffmpeg_sys_next::SWS_CS_ITU601,
+            ColorMatrix::Bt709 => ffmpeg_sys_next::SWS_CS_ITU709,
+            ColorMatrix::Bt2020 => ffmpeg_sys_next::SWS_CS_BT2020,
+            ColorMatrix::Smpte240m => ffmpeg_sys_next::SWS_CS_SMPTE240M,
+        }) as i32
+    }
+    
+    pub fn name(self) -> &'static str {
+        match self {
+            ColorMatrix::Auto => "auto",
+            ColorMatrix::Bt601 => "bt601",
+            ColorMatrix::Bt709 => "bt709",
+            ColorMatrix::Bt2020 => "bt2020",
+            ColorMatrix::Smpte240m => "smpte240m",
+        }
+    }
+}
+
+/// Range of YUV values for `--assume-range`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum YuvRange {
+    /// From the frames' metadata, else limited
+    #[default]
+    Auto,
+    /// 16-235 luma, as broadcast and most video
+    Limited,
+    /// 0-255 luma, as JPEG and some phone recordings
+    Full,
+}
+
+impl YuvRange {
+    pub fn name(self) -> &'static str {
+        match self {
+            YuvRange::Auto => "auto",
+            YuvRange::Limited => "limited",
+            YuvRange::Full => "full",
+        }
+    }
+}
+
+/// Tell swscale how `colors` (matrix, full range) encode the source. RGB output is always full range,
+/// YUV and gray output keep the source's range and matrix.
+fn set_yuv_colors(scaler: &mut ffmpeg::software::scaling::Context, (matrix, full_range): (ColorMatrix, bool), rgb_output: bool) {
+    unsafe {
+        let coefficients = ffmpeg_sys_next::sws_getCoefficients(matrix.sws_colorspace());
+        let result = ffmpeg_sys_next::sws_setColorspaceDetails(
+            scaler.as_mut_ptr(),
+            coefficients,
+            full_range as i32,
+            coefficients,
+            if rgb_output { 1 } else { full_range as i32 },
+            0,
+            1 << 16,
+            1 << 16,
+        );
+        if result < 0 {
+            log::debug!("swscale ignored the {} {} range color details", matrix.name(), if full_range { "full" } else { "limited" });
+        }
+    }
+}
+
+/// Tone mapping curve for `--tonemap`, applied to HDR (PQ or HLG) sources only.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum Tonemap {
+    /// Show HDR frames as decoded, which looks washed out
+    None,
+    /// Filmic curve that keeps highlight detail
+    #[default]
+    Hable,
+    /// Simple curve, brighter midtones but flatter highlights
+    Reinhard,
+}
+
+impl Tonemap {
+    /// Filter chain converting HDR frames to 8-bit BT.709 SDR, None when not tone mapping.
+    fn filter(self) -> Option<String> {
+        let curve = match self {
+            Tonemap::None => return None,
+            Tonemap::Hable => "hable",
+            Tonemap::Reinhard => "reinhard",
+        };
+        // Linearize and convert the primaries in float, then map and dither down to 8 bits
+        Some(format!(
+            "zscale=t=linear:npl=100,format=gbrpf32le,zscale=p=bt709,tonemap={}:desat=0,\
+             zscale=t=bt709:m=bt709:r=tv:d=error_diffusion,format=yuv420p",
+            curve
+        ))
+    }
+    
+    pub fn name(self) -> &'static str {
+        match self {
+            Tonemap::None => "none",
+            Tonemap::Hable => "hable",
+            Tonemap::Reinhard => "reinhard",
+        }
+    }
+}
+
+/// The deprecated yuvj formats, which are full range whatever the range tag says.
+fn is_jpeg_format(format: ffmpeg::format::Pixel) -> bool {
+    use ffmpeg::format::Pixel;
+    matches!(format, Pixel::YUVJ420P | Pixel::YUVJ422P | Pixel::YUVJ444P | Pixel::YUVJ440P | Pixel::YUVJ411P)
+}
+
+/// Bits per component of `format`, e.g. 10 for yuv420p10le, 8 when unknown.
+fn pixel_bit_depth(format: ffmpeg::format::Pixel) -> u8 {
+    match format.descriptor() {
+        Some(descriptor) => unsafe { (*descriptor.as_ptr()).comp[0].depth as u8 },
+        None => 8,
+    }
+}
+
+/// A buffer -> filters -> buffersink graph between the decoder and the scaler, running yadif
+/// and/or the `--vf` chain.
+struct FilterGraph {
+    graph: ffmpeg::filter::Graph,
+    /// Time base of the frames coming out, finer than the stream's when outputting fields
+    time_base: ffmpeg::Rational,
+    /// Format and size the graph was built for
+    input: (ffmpeg::format::Pixel, u32, u32),
+    /// Filter chain between input and output, as given to `parse`
+    description: String,
+    flushed: bool,
+}
+
+impl FilterGraph {
+    fn new(
+        input: (ffmpeg::format::Pixel, u32, u32),
+        aspect: ffmpeg::Rational,
+        time_base: ffmpeg::Rational,
+        description: &str,
+    ) -> Result<Self> {
+        let (format, width, height) = input;
+        let buffer_args = format!(
+            "video_size={}x{}:pix_fmt={}:time_base={}/{}:pixel_aspect={}/{}",
+            width,
+            height,
+            ffmpeg_sys_next::AVPixelFormat::from(format) as i32,
+            time_base.numerator(),
+            time_base.denominator(),
+            aspect.numerator().max(0),
+            aspect.denominator().max(1),
+        );
+        
+        let mut graph = ffmpeg::filter::Graph::new();
+        let buffer = ffmpeg::filter::find("buffer").context("FFmpeg has no buffer filter")?;
+        let buffersink = ffmpeg::filter::find("buffersink").context("FFmpeg has no buffersink filter")?;
+        graph.add(&buffer, "in", &buffer_args).context("Failed to create filter graph input")?;
+        graph.add(&buffersink, "out", "").context("Failed to create filter graph output")?;
+        graph
+            .output("in", 0)?
+            .input("out", 0)?
+            .parse(description)
+            .with_context(|| format!("FFmpeg rejected the filters '{}'", description))?;
+        graph.validate().with_context(|| format!("Failed to set up the filters '{}'", description))?;
+        
+        let time_base = graph.get("out").context("Filter graph has no output")?.sink().time_base();
+        Ok(Self { graph, time_base, input, description: description.to_string(), flushed: false })
+    }
+    
+    /// Graph for a decoded frame, taking its format, size and aspect ratio.
+    fn for_frame(frame: &ffmpeg::frame::Video, time_base: ffmpeg::Rational, description: &str) -> Result<Self> {
+        Self::new((frame.format(), frame.width(), frame.height()), frame.aspect_ratio(), time_base, description)
+    }
+    
+    /// Format and size of the frames coming out, which filters like format, scale or crop change.
+    fn output(&mut self) -> Option<(ffmpeg::format::Pixel, u32, u32)> {
+        let output = self.graph.get("out")?;
+        let (format, width, height) = unsafe {
+            let sink = output.as_ptr();
+            (
+                ffmpeg_sys_next::av_buffersink_get_format(sink),
+                ffmpeg_sys_next::av_buffersink_get_w(sink),
+                ffmpeg_sys_next::av_buffersink_get_h(sink),
+            )
+        };
+        if width <= 0 || height <= 0 {
+            return None;
+        }
+        // Negotiated by `validate`, so it's one of the AVPixelFormat values
+        let format = unsafe { std::mem::transmute::<i32, ffmpeg_sys_next::AVPixelFormat>(format) };
+        Some((format.into(), width as u32, height as u32))
+    }
+    
+    /// Feed a decoded frame in; the filter takes over its buffers, leaving `frame` empty.
+    fn push(&mut self, frame: &ffmpeg::frame::Video) -> Result<()> {
+        let mut input = self.graph.get("in").context("Filter graph has no input")?;
+        input.source().add(frame).context("Failed to filter frame")
+    }
+    
+    /// Signal the end of the stream, so filters like yadif let go of the frames they hold back.
+    fn flush(&mut self) -> Result<()> {
+        self.flushed = true;
+        let mut input = self.graph.get("in").context("Filter graph has no input")?;
+        input.source().flush().context("Failed to flush filter graph")
+    }
+    
+    /// Take the next filtered frame, if one is ready.
+    fn pull(&mut self, frame: &mut ffmpeg::frame::Video) -> bool {
+        // The sink moves its frame in without releasing what `frame` held
+        unsafe { ffmpeg_sys_next::av_frame_unref(frame.as_mut_ptr()) };
+        self.graph.get("out").is_some_and(|mut output| output.sink().frame(frame).is_ok())
+    }
+}
+
+/// Codec and container details of an opened video, as exported with the metrics.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct VideoInfo {
+    pub codec: String,
+    /// e.g. "High" for H.264, None when the codec has no profiles
+    pub profile: Option<String>,
+    /// Codec level as stored, e.g. 41 for H.264 level 4.1
+    pub level: Option<i32>,
+    /// Pixel format the decoder outputs, e.g. "yuv420p"
+    pub pixel_format: String,
+    /// Bits per component, e.g. 10 for yuv420p10le
+    #[serde(default)]
+    pub bit_depth: u8,
+    /// FFmpeg's names for the stream's color metadata, None when unspecified
+    #[serde(default)]
+    pub color_space: Option<String>,
+    #[serde(default)]
+    pub color_primaries: Option<String>,
+    #[serde(default)]
+    pub color_transfer: Option<String>,
+    /// FFmpeg's name for the stream's color range, "tv" (limited) or "pc" (full), None when unspecified
+    #[serde(default)]
+    pub color_range: Option<String>,
+    /// Matrix and range YUV is converted to RGB with, from the metadata, a guess or `--assume-*`
+    #[serde(default)]
+    pub yuv_matrix: String,
+    #[serde(default)]
+    pub yuv_range: String,
+    /// "PQ" or "HLG" for HDR sources
+    #[serde(default)]
+    pub hdr: Option<String>,
+    /// `--tonemap` curve the HDR frames are converted with, None when shown as decoded
+    #[serde(default)]
+    pub tonemap: Option<String>,
+    /// FFmpeg's name for the container format, e.g. "mov,mp4,m4a,3gp,3g2,mj2"
+    pub container_format: String,
+    /// Overall bitrate the container reports, in bits per second
+    pub container_bit_rate: Option<u64>,
+    /// Size of the video after rotation, before any scaling
+    pub width: u32,
+    pub height: u32,
+    /// Clockwise rotation from the display matrix that frames are turned by, 0 with `--ignore-rotation`
+    #[serde(default)]
+    pub rotation: u32,
+    /// Average frame rate, 0 when the stream doesn't say
+    pub native_fps: f64,
+    /// Frames in the whole video, estimated from the duration unless `frame_count_exact`
+    pub total_frames: u64,
+    pub frame_count_exact: bool,
+    /// 0 when unknown, e.g. for live streams
+    pub duration_seconds: f64,
+}
+
+/// One stream of a container, as printed by `--list-streams`.
+#[derive(Debug, Clone)]
+pub struct StreamDescription {
+    pub index: usize,
+    pub medium: ffmpeg::media::Type,
+    pub codec: String,
+    /// Resolution and frame rate for video, sample rate and channels for audio
+    pub details: String,
+}
+
+impl std::fmt::Display for StreamDescription {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let medium = match self.medium {
+            ffmpeg::media::Type::Video => "video",
+            ffmpeg::media::Type::Audio => "audio",
+            ffmpeg::media::Type::Subtitle => "subtitle",
+            ffmpeg::media::Type::Data => "data",
+            ffmpeg::media::Type::Attachment => "attachment",
+            ffmpeg::media::Type::Unknown => "unknown",
+        };
+        write!(f, "Stream #{}: {} {}", self.index, medium, self.codec)?;
+        if !self.details.is_empty() {
+            write!(f, ", {}", self.details)?;
+        }
+        Ok(())
+    }
+}
+
+/// Describe every stream in `input` from its codec parameters, without opening decoders.
+pub fn describe_streams(input: &ffmpeg::format::context::Input) -> Vec<StreamDescription> {
+    input
+        .streams()
+        .map(|stream| {
+            let parameters = stream.parameters();
+            let medium = parameters.medium();
+            let raw = unsafe { &*parameters.as_ptr() };
+            
+            let mut details = match medium {
+                ffmpeg::media::Type::Video => {
+                    let fps = stream.avg_frame_rate();
+                    let fps = if fps.denominator() > 0 { f64::from(fps) } else { 0.0 };
+                    format!("{}x{} @ {:.2} fps", raw.width, raw.height, fps)
+                }
+                ffmpeg::media::Type::Audio => {
+                    format!("{} Hz, {} channels", raw.sample_rate, raw.ch_layout.nb_channels)
+                }
+                _ => String::new(),
+            };
+            if let Some(language) = stream.metadata().get("language") {
+                if !details.is_empty() {
+                    details.push(' ');
+                }
+                details.push_str(&format!("({})", language));
+            }
+            
+            StreamDescription {
+                index: stream.index(),
+                medium,
+                codec: parameters.id().name().to_string(),
+                details,
+            }
+        })
+        .collect()
+}
+
+/// Names of the input protocols (file, http, rtsp, ...) the linked ffmpeg build supports.
+pub fn input_protocols() -> Vec<String> {
+    let mut protocols = Vec::new();
+    let mut opaque = std::ptr::null_mut();
+    unsafe {
+        loop {
+            let name = ffmpeg_sys_next::avio_enum_protocols(&mut opaque, 0);
+            if name.is_null() {
+                break;
+            }
+            protocols.push(std::ffi::CStr::from_ptr(name).to_string_lossy().into_owned());
+        }
+    }
+    protocols
+}
+
+/// Open `source` and list its streams without decoding anything.
+pub fn list_streams(source: &Path, network_timeout: Option<Duration>) -> Result<Vec<StreamDescription>> {
+    ffmpeg::init().context("Failed to initialize FFmpeg")?;
+    let input = open_input(source, network_timeout)?;
+    Ok(describe_streams(&input))
+}
+
+/// URL schemes passed straight to ffmpeg instead of being treated as file paths
+const NETWORK_SCHEMES: &[&str] = &["http://", "https://", "rtsp://", "rtmp://"];
+
+/// Packet sizes kept for frames still inside the decoder, more than frame threading ever delays
+const MAX_PENDING_PACKETS: usize = 256;
+
+/// Give up on a dropped live stream after this many reconnects without receiving a packet
+const MAX_RECONNECT_ATTEMPTS: u32 = 5;
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// Whether `source` is a network URL rather than a local file.
+pub fn is_url(source: &Path) -> bool {
+    source
+        .to_str()
+        .map(|s| s.to_ascii_lowercase())
+        .is_some_and(|s| NETWORK_SCHEMES.iter().any(|scheme| s.starts_with(scheme)))
+}
+
+/// Whether `source` is `-`, meaning read the video from standard input.
+pub fn is_stdin(source: &Path) -> bool {
+    source.as_os_str() == "-"
+}
+
+/// Format as `HH:MM:SS`, truncating to whole seconds. Hours aren't wrapped at 24.
+pub fn format_timecode(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+/// Format as `HH:MM:SS.mmm`.
+pub fn format_timecode_ms(duration: Duration) -> String {
+    format!("{}.{:03}", format_timecode(duration), duration.subsec_millis())
+}
+
+/// Format as `HH:MM:SS.t`, to a tenth of a second, for position displays.
+pub fn format_timestamp(duration: Duration) -> String {
+    format!("{}.{}", format_timecode(duration), duration.subsec_millis() / 100)
+}
+
+/// Errors that leave the decoder unusable, as opposed to damage confined to one packet or frame.
+fn is_fatal(error: &ffmpeg::Error) -> bool {
+    matches!(
+        error,
+        ffmpeg::Error::Other { errno: ffmpeg::error::ENOMEM }
+            | ffmpeg::Error::Bug
+            | ffmpeg::Error::Bug2
+            | ffmpeg::Error::Exit
+            | ffmpeg::Error::DecoderNotFound
+            | ffmpeg::Error::External
+    )
+}
+
+/// One-letter name of a picture type, as in FFmpeg's own logs.
+fn picture_type_char(kind: ffmpeg::picture::Type) -> char {
+    use ffmpeg::picture::Type;
+    match kind {
+        Type::I => 'I',
+        Type::P => 'P',
+        Type::B => 'B',
+        Type::S => 'S',
+        Type::SI => 'i',
+        Type::SP => 'p',
+        Type::BI => 'b',
+        Type::None => '?',
+    }
+}
+
+/// Clockwise rotation in degrees (0, 90, 180 or 270) from the stream's display matrix.
+fn stream_rotation(stream: &ffmpeg::format::stream::Stream) -> u32 {
+    let Some(matrix) = stream
+        .side_data()
+        .find(|side_data| side_data.kind() == ffmpeg::codec::packet::side_data::Type::DisplayMatrix)
+    else {
+        return 0;
+    };
+    if matrix.data().len() < 9 * std::mem::size_of::<i32>() {
+        return 0;
+    }
+    
+    // av_display_rotation_get reports the counterclockwise angle, in [-180, 180]
+    let angle = unsafe { ffmpeg_sys_next::av_display_rotation_get(matrix.data().as_ptr() as *const i32) };
+    if angle.is_nan() {
+        return 0;
+    }
+    
+    // Snap to quarter turns; arbitrary angles aren't worth a general-purpose rotation
+    ((-angle / 90.0).round() as i32 * 90).rem_euclid(360) as u32
+}
+
+/// Round a derived frame dimension to an even number, some pixel formats and encoders can't handle odd sizes.
+fn even_size(size: f64) -> u32 {
+    ((size / 2.0).round() as u32 * 2).max(2)
+}
+
+/// Output size for a `width`x`height` frame, stretched horizontally to square pixels when asked.
+fn scaled_size(
+    width: u32,
+    height: u32,
+    sample_aspect_ratio: f64,
+    square_pixels: bool,
+    target: (Option<u32>, Option<u32>),
+) -> (u32, u32) {
+    let width = if square_pixels { even_size(width as f64 * sample_aspect_ratio) } else { width };
+    
+    // A single requested dimension keeps the aspect ratio
+    match target {
+        (Some(target_width), Some(target_height)) => (target_width, target_height),
+        (Some(target_width), None) => (target_width, even_size(target_width as f64 * height as f64 / width as f64)),
+        (None, Some(target_height)) => (even_size(target_height as f64 * width as f64 / height as f64), target_height),
+        (None, None) => (width, height),
+    }
+}
+
+/// Count a stream's packets by reading through the whole input, then rewind. Returns the
+/// frame count, the end time of the last packet and whether the timestamps are VFR.
+fn probe_stream(
+    input: &mut ffmpeg::format::context::Input,
+    stream_index: usize,
+    time_base: ffmpeg::Rational,
+) -> Result<(u64, Duration, bool)> {
+    let mut frames = 0;
+    let mut end = 0;
+    let mut timestamps = Vec::new();
+    for (stream, packet) in input.packets() {
+        if stream.index() != stream_index {
+            continue;
+        }
+        frames += 1;
+        if let Some(pts) = packet.pts() {
+            end = end.max(pts + packet.duration());
+            timestamps.push(pts);
+        }
+    }
+    
+    input.seek(0, ..).context("Failed to rewind after counting frames")?;
+    Ok((frames, Duration::from_secs_f64(end as f64 * f64::from(time_base)), is_variable_rate(&mut timestamps)))
+}
+
+/// Whether frames at these presentation timestamps (in time base units) have a variable frame
+/// rate: the average gap is noticeably longer than the shortest one. Sorts and dedups
+/// `timestamps` in place, since packets come in decode order.
+fn is_variable_rate(timestamps: &mut Vec<i64>) -> bool {
+    timestamps.sort_unstable();
+    timestamps.dedup();
+    let (Some(&first), Some(&last)) = (timestamps.first(), timestamps.last()) else {
+        return false;
+    };
+    if timestamps.len() < 3 {
+        return false;
+    }
+    
+    let shortest = timestamps.windows(2).map(|pair| pair[1] - pair[0]).min().unwrap_or(0) as f64;
+    let average = (last - first) as f64 / (timestamps.len() - 1) as f64;
+    // Timestamps are rounded to the time base, so a gap of a tick either way is still constant
+    average - shortest > (shortest * VFR_TOLERANCE).max(1.0)
+}
+
+/// Rotate a tightly packed plane of `bytes_per_pixel`-sized pixels clockwise by `rotation` degrees into `rotated`.
+fn rotate_plane(plane: &[u8], rotated: &mut [u8], width: usize, height: usize, bytes_per_pixel: usize, rotation: u32) {
+    let out_width = if rotation % 180 == 0 { width } else { height };
+    
+    for y in 0..height {
+        for x in 0..width {
+            let (nx, ny) = match rotation {
+                90 => (height - 1 - y, x),
+                180 => (width - 1 - x, height - 1 - y),
+                _ => (y, width - 1 - x),
+            };
+            let src = (y * width + x) * bytes_per_pixel;
+            let dst = (ny * out_width + nx) * bytes_per_pixel;
+            rotated[dst..dst + bytes_per_pixel].copy_from_slice(&plane[src..src + bytes_per_pixel]);
+        }
+    }
+}
+
+/// Rotate tightly packed frame data clockwise by `rotation` degrees into `rotated`,
+/// returning the new width and height.
+fn rotate_frame(data: &[u8], rotated: &mut Vec<u8>, width: u32, height: u32, format: OutputFormat, rotation: u32) -> (u32, u32) {
+    rotated.resize(data.len(), 0);
+    
+    let mut offset = 0;
+    for (plane_width, plane_height) in format.planes(width, height) {
+        let len = plane_width as usize * plane_height as usize * format.bytes_per_pixel();
+        rotate_plane(
+            &data[offset..offset + len],
+            &mut rotated[offset..offset + len],
+            plane_width as usize,
+            plane_height as usize,
+            format.bytes_per_pixel(),
+            rotation,
+        );
+        offset += len;
+    }
+    
+    if rotation % 180 == 0 {
+        (width, height)
+    } else {
+        (height, width)
+    }
+}
+
+fn open_input(source: &Path, network_timeout: Option<Duration>) -> Result<ffmpeg::format::context::Input> {
+    if is_stdin(source) {
+        return ffmpeg::format::input("pipe:0").context("Failed to open standard input");
+    }
+    
+    if !is_url(source) {
+        return ffmpeg::format::input(source).context("Failed to open video file");
+    }
+    
+    // Protocols ignore options they don't know, so one set covers HTTP and RTSP/RTMP alike
+    let mut options = ffmpeg::Dictionary::new();
+    if let Some(timeout) = network_timeout {
+        let micros = timeout.as_micros().to_string();
+        options.set("timeout", &micros);
+        options.set("rw_timeout", &micros);
+    }
+    options.set("reconnect", "1");
+    options.set("reconnect_streamed", "1");
+    options.set("reconnect_delay_max", "5");
+    
+    ffmpeg::format::input_with_dictionary(source, options).with_context(|| {
+        // Builds without network support (or TLS) fail with a vague error, so name the cause
+        let scheme = source.to_string_lossy().split("://").next().unwrap_or_default().to_ascii_lowercase();
+        let protocols = input_protocols();
+        if protocols.contains(&scheme) {
+            "Failed to open video stream".to_string()
+        } else {
+            format!("Failed to open video stream: this ffmpeg build doesn't support '{}' (available: {})",
+                scheme, protocols.join(", "))
+        }
+    })
+}
+
+/// Decodes a video file, URL or standard input with FFmpeg into `VideoFrame`s, optionally paced to a target FPS.
 pub struct VideoPlayer {
+    source: PathBuf,
+    is_network: bool,
+    seekable: bool,
+    network_timeout: Option<Duration>,
+    reconnect_attempts: u32,
+    
     format_context: ffmpeg::format::context::Input,
     video_stream_index: usize,
     decoder: ffmpeg::decoder::Video,
+    hw_decoder: Option<HwDecoder>,
     scaler: ffmpeg::software::scaling::Context,
     
+    // Reused between frames so steady-state playback doesn't allocate
+    decoded: ffmpeg::frame::Video,
+    downloaded: ffmpeg::frame::Video,
+    converted: ffmpeg::frame::Video,
+    rotation_buffer: Vec<u8>,
+    rotation: u32,
+    deinterlace: Deinterlace,
+    filters: Option<String>, // Tone mapping and `--vf`, run after any deinterlacing
+    filter_graph: Option<FilterGraph>, // Built from the first frame to filter, dropped on seeks
+    filter_time: Duration, // Spent in the filter graph since the last frame was handed out
+    filtered_size: (u32, u32), // Size frames come out of `--vf` at, before cropping and rotation
+    interlaced_seen: bool, // Whether `Deinterlace::Auto` has switched on
+    sample_aspect_ratio: f64,
+    square_pixels: bool,
+    target_size: (Option<u32>, Option<u32>), // Requested output size before rotation
+    crop: Option<Crop>, // In stored (unrotated) frame coordinates
+    requested_crop: Option<Crop>,
+    output_format: OutputFormat,
+    yuv_direct: bool,
+    scaler_algorithm: Scaler,
+    assumed_colors: (ColorMatrix, YuvRange), // `--assume-colorspace` and `--assume-range`
+    stream_colors: (ColorMatrix, bool), // Matrix and full range for frames without metadata
+    scaler_colors: Option<(ColorMatrix, bool)>, // What the scaler was last told, None for a fresh scaler
+    strict: bool,
+    hash_frames: bool,
+    frame_hash: FrameHash,
+    decode_errors: u64, // Rejected packets not yet collected by take_decode_errors
+    corrupt_frames: u64, // Failed or damaged frames not yet collected by take_corrupt_frames
+    packet_sizes: VecDeque<usize>, // Sent to the decoder, not yet matched to a frame
+    decoded_packet_bytes: usize, // Packet size matched to the frame in `decoded`
+    dropped_frames: u64, // Late frames skipped by next_frame_realtime, not yet collected by take_dropped_frames
+    
     target_fps: u32,
     frame_duration: Option<Duration>,
     last_frame_time: Option<Instant>,
+    playback_speed: f32,
+    pacing: PacingMode,
+    pacing_error: Option<Duration>, // Overshoot of the last paced wait, not yet collected by take_pacing_error
+    realtime_clock: Option<(Instant, Duration, u64)>, // Schedule start, frame interval and frames scheduled since
+    
+    time_base: ffmpeg::Rational,
+    eof_sent: bool,
+    seek_target: Option<Duration>,
     
+    codec_name: String,
+    video_info: VideoInfo,
     current_frame: u64,
+    position: Duration,
     total_frames: u64,
+    frame_count_exact: bool, // False when total_frames was derived from duration and frame rate
+    native_fps: f64, // avg_frame_rate, or r_frame_rate, 0 when neither is set
+    is_vfr: bool,
     duration: Duration,
+    // Played segment from --start/--end
+    start: Duration,
+    end: Option<Duration>,
+}
+
+impl std::fmt::Debug for VideoPlayer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("VideoPlayer")
+            .field("source", &self.source)
+            .field("codec", &self.codec_name)
+            .field("size", &(self.get_width(), self.get_height()))
+            .field("current_frame", &self.current_frame)
+            .finish_non_exhaustive()
+    }
 }
 
 impl VideoPlayer {
-    pub fn new(video_path: &Path, target_fps: u32) -> Result<Self> {
+    /// Open a file path, `-` for stdin, or a network URL such as `rtsp://` or `https://`.
+    pub fn new(video_path: &Path, options: &PlayerOptions) -> Result<Self> {
+        let target_fps = options.target_fps;
+        
         // Initialize FFmpeg
         ffmpeg::init().context("Failed to initialize FFmpeg")?;
         
         log::info!("Loading video file: {:?}", video_path);
         
-        // Open input file
-        let input = ffmpeg::format::input(video_path)
-            .context("Failed to open video file")?;
+        // Open input file or network stream
+        let mut input = open_input(video_path, options.network_timeout)?;
         
-        // Find video stream
-        let video_stream = input
-            .streams()
-            .best(ffmpeg::media::Type::Video)
-            .context("No video stream found")?;
+        // Pipes and protocols without an I/O context (e.g. RTSP) can't seek
+        let seekable = unsafe {
+            let pb = (*input.as_ptr()).pb;
+            !pb.is_null() && (*pb).seekable != 0
+        };
+        
+        // Find video stream, either the requested one or ffmpeg's pick
+        let video_stream = match options.stream_index {
+            Some(index) => input
+                .stream(index)
+                .filter(|stream| stream.parameters().medium() == ffmpeg::media::Type::Video)
+                .with_context(|| {
+                    let available: Vec<String> = describe_streams(&input)
+                        .iter()
+                        .filter(|stream| stream.medium == ffmpeg::media::Type::Video)
+                        .map(|stream| format!("  {}", stream))
+                        .collect();
+                    format!("Stream {} is not a video stream. Available video streams:\n{}",
+                        index, available.join("\n"))
+                })?,
+            None => input
+                .streams()
+                .best(ffmpeg::media::Type::Video)
+                .context("No video stream found")?,
+        };
         
         let video_stream_index = video_stream.index();
+        let time_base = video_stream.time_base();
+        let rotation = if options.ignore_rotation { 0 } else { stream_rotation(&video_stream) };
         
         // Get decoder with hardware acceleration if available
-        let context_decoder = ffmpeg::codec::context::Context::from_parameters(video_stream.parameters())
+        let mut context_decoder = ffmpeg::codec::context::Context::from_parameters(video_stream.parameters())
             .context("Failed to create decoder context")?;
         
+        // The device has to be attached before the decoder is opened
+        let hw_decoder = attach_hw_device(&mut context_decoder, options.hwaccel);
+        match &hw_decoder {
+            Some(hw_decoder) => log::info!("Hardware decoding via {}", hw_decoder.name),
+            None if options.hwaccel != HwAccel::None => {
+                log::info!("No usable hardware decoder for --hwaccel {:?}, decoding in software", options.hwaccel)
+            }
+            None => log::info!("Software decoding"),
+        }
+        
         let mut decoder = context_decoder
             .decoder()
             .video()
             .context("Failed to create video decoder")?;
         
-        // Try to enable hardware acceleration
-        // Note: This may not work on all systems, but will gracefully fall back to software decoding
         unsafe {
             // Enable multi-threading for faster decoding
             (*decoder.as_mut_ptr()).thread_count = num_cpus::get() as i32;
@@ -64,19 +1453,139 @@ impl VideoPlayer {
             log::debug!("Decoder configured with {} threads", (*decoder.as_mut_ptr()).thread_count);
         }
         
-        if let Some(codec) = decoder.codec() {
-            log::info!("Codec: {}", codec.name());
+        let codec_name = decoder
+            .codec()
+            .map(|codec| codec.name().to_string())
+            .unwrap_or_else(|| "unknown".to_string());
+        log::info!("Codec: {}", codec_name);
+        
+        if decoder.width() == 0 || decoder.height() == 0 {
+            anyhow::bail!("Video stream reports invalid dimensions {}x{}", decoder.width(), decoder.height());
+        }
+        
+        // The decoder's format stands in for downloaded hardware frames when checking filters
+        let input_format = match decoder.format() {
+            ffmpeg::format::Pixel::None => ffmpeg::format::Pixel::YUV420P,
+            format => format,
+        };
+        let filter_input = (input_format, decoder.width(), decoder.height());
+        
+        // Untagged video is guessed from its size, like other players do, unless --assume-* says otherwise
+        let stream_colors = (
+            match options.assume_colorspace {
+                ColorMatrix::Auto => ColorMatrix::from_space(decoder.color_space()).unwrap_or(ColorMatrix::guess(decoder.height())),
+                matrix => matrix,
+            },
+            match options.assume_range {
+                YuvRange::Auto => decoder.color_range() == ffmpeg::color::Range::JPEG || is_jpeg_format(decoder.format()),
+                range => range == YuvRange::Full,
+            },
+        );
+        
+        // HDR is tone mapped ahead of --vf, so those filters see SDR frames. Without zscale
+        // (FFmpeg built without libzimg) the frames are shown washed out rather than not at all
+        let hdr = match decoder.color_transfer_characteristic() {
+            ffmpeg::color::TransferCharacteristic::SMPTE2084 => Some("PQ"),
+            ffmpeg::color::TransferCharacteristic::ARIB_STD_B67 => Some("HLG"),
+            _ => None,
+        };
+        let tonemap_filter = hdr.and_then(|hdr| {
+            let filter = options.tonemap.filter()?;
+            match FilterGraph::new(filter_input, decoder.aspect_ratio(), time_base, &filter) {
+                Ok(_) => Some(filter),
+                Err(e) => {
+                    log::warn!("Can't tone map {} HDR, colors will look washed out: {:#}", hdr, e);
+                    None
+                }
+            }
+        });
+        let filters: Vec<String> = tonemap_filter.iter().chain(&options.video_filter).cloned().collect();
+        let filters = (!filters.is_empty()).then(|| filters.join(","));
+        
+        // Build the filters once up front, so --vf typos fail here rather than on the first frame,
+        // and to learn the size and format they output
+        let (filtered_format, filtered_width, filtered_height) = match &filters {
+            Some(filter) => {
+                let mut graph = FilterGraph::new(filter_input, decoder.aspect_ratio(), time_base, filter)
+                    .context("Invalid --vf")?;
+                graph.output().context("Invalid --vf: the filters output no video")?
+            }
+            None => (decoder.format(), decoder.width(), decoder.height()),
+        };
+        
+        // The region is given upright, the frames are cropped before they're rotated
+        let crop = options.crop
+            .map(|crop| {
+                let (width, height) = if rotation % 180 == 0 {
+                    (filtered_width, filtered_height)
+                } else {
+                    (filtered_height, filtered_width)
+                };
+                if u64::from(crop.x) + u64::from(crop.width) > u64::from(width)
+                    || u64::from(crop.y) + u64::from(crop.height) > u64::from(height)
+                {
+                    anyhow::bail!("Crop region {}x{} at {},{} doesn't fit the {}x{} video",
+                        crop.width, crop.height, crop.x, crop.y, width, height);
+                }
+                Ok(crop.unrotate(filtered_width, filtered_height, rotation))
+            })
+            .transpose()?;
+        let (source_width, source_height) = crop.map_or((filtered_width, filtered_height), |crop| (crop.width, crop.height));
+        
+        // Container SAR takes precedence over the codec's, like ffmpeg itself does;
+        // 0/0 means unknown and is treated as square
+        let sample_aspect_ratio = unsafe {
+            ffmpeg_sys_next::av_guess_sample_aspect_ratio(
+                input.as_ptr() as *mut _,
+                video_stream.as_ptr() as *mut _,
+                std::ptr::null_mut(),
+            )
+        };
+        let sample_aspect_ratio = if sample_aspect_ratio.num > 0 && sample_aspect_ratio.den > 0 {
+            sample_aspect_ratio.num as f64 / sample_aspect_ratio.den as f64
+        } else {
+            1.0
+        };
+        let square_pixels = options.square_pixels && sample_aspect_ratio != 1.0;
+        // The requested size applies to the rotated frame, the scaler runs before rotation
+        let target_size = match options.scale_factor {
+            Some(factor) => {
+                let (width, height) =
+                    scaled_size(source_width, source_height, sample_aspect_ratio, square_pixels, (None, None));
+                (Some(even_size(width as f64 * factor)), Some(even_size(height as f64 * factor)))
+            }
+            None if rotation % 180 == 0 => (options.output_width, options.output_height),
+            None => (options.output_height, options.output_width),
+        };
+        let (output_width, output_height) =
+            scaled_size(source_width, source_height, sample_aspect_ratio, square_pixels, target_size);
+        
+        // Guard against frames too large to convert comfortably (an 8K RGB24 frame is ~100 MB).
+        // What matters is the converted frame, after cropping and scaling
+        let frame_bytes = options.output_format.frame_bytes(output_width, output_height);
+        if let Some(max_frame_bytes) = options.max_frame_bytes {
+            if frame_bytes > max_frame_bytes {
+                anyhow::bail!(
+                    "{}x{} {} needs {:.1} MB per frame, above the {:.1} MB limit \
+                     (raise --max-frame-mb, scale down or pass --allow-huge-frames)",
+                    output_width,
+                    output_height,
+                    options.output_format.name(),
+                    frame_bytes as f64 / 1024.0 / 1024.0,
+                    max_frame_bytes as f64 / 1024.0 / 1024.0
+                );
+            }
         }
         
-        // Create scaler for RGB conversion (use FAST_BILINEAR for speed)
+        // Create scaler for the output format conversion
         let scaler = ffmpeg::software::scaling::Context::get(
-            decoder.format(),
-            decoder.width(),
-            decoder.height(),
-            ffmpeg::format::Pixel::RGB24,
-            decoder.width(),
-            decoder.height(),
-            ffmpeg::software::scaling::Flags::FAST_BILINEAR,
+            filtered_format,
+            source_width,
+            source_height,
+            options.output_format.pixel(),
+            output_width,
+            output_height,
+            options.scaler.flags(filtered_format),
         ).context("Failed to create scaler")?;
         
         // Calculate frame duration for target FPS
@@ -86,207 +1595,1375 @@ impl VideoPlayer {
             None
         };
         
-        // Get video metadata
-        let total_frames = video_stream.frames() as u64;
-        let duration_secs = video_stream.duration() as f64 * f64::from(video_stream.time_base());
-        let duration = if duration_secs > 0.0 {
-            Duration::from_secs_f64(duration_secs)
+        // Get video metadata. Matroska/WebM usually stores neither a frame count nor a
+        // stream duration, so fall back to the container duration and the frame rate
+        let valid_rate = |fps: ffmpeg::Rational| (fps.numerator() > 0 && fps.denominator() > 0).then(|| f64::from(fps));
+        let average_rate = valid_rate(video_stream.avg_frame_rate());
+        let base_rate = valid_rate(video_stream.rate());
+        let native_fps = average_rate.or(base_rate).unwrap_or(0.0);
+        // r_frame_rate is the lowest rate all timestamps fit on; with variable frame rate
+        // (screen and phone recordings) the average falls noticeably below it
+        let mut is_vfr = match (average_rate, base_rate) {
+            (Some(average), Some(base)) => (average - base).abs() / base > VFR_TOLERANCE,
+            _ => false,
+        };
+        let stream_frames = video_stream.frames().max(0) as u64;
+        let stream_duration = video_stream.duration() as f64 * f64::from(video_stream.time_base());
+        let container_duration = input.duration() as f64 / f64::from(ffmpeg_sys_next::AV_TIME_BASE);
+        let mut duration = if stream_duration > 0.0 {
+            Duration::from_secs_f64(stream_duration)
+        } else if container_duration > 0.0 {
+            Duration::from_secs_f64(container_duration)
+        } else if stream_frames > 0 && native_fps > 0.0 {
+            Duration::from_secs_f64(stream_frames as f64 / native_fps)
+        } else {
+            Duration::ZERO // Unknown, e.g. live streams
+        };
+        let mut total_frames = if stream_frames > 0 {
+            stream_frames
+        } else {
+            (duration.as_secs_f64() * native_fps).round() as u64
+        };
+        let mut frame_count_exact = stream_frames > 0;
+        
+        if options.probe && !frame_count_exact {
+            if seekable {
+                log::info!("Counting frames (--probe)...");
+                match probe_stream(&mut input, video_stream_index, time_base) {
+                    Ok((frames, end, vfr)) => {
+                        total_frames = frames;
+                        frame_count_exact = true;
+                        // The timestamps themselves beat the header's rates
+                        is_vfr = vfr;
+                        if duration.is_zero() {
+                            duration = end;
+                        }
+                    }
+                    Err(e) => log::warn!("Frame count probe failed: {:#}", e),
+                }
+            } else {
+                log::warn!("Can't count frames of a non-seekable input, ignoring --probe");
+            }
+        }
+        
+        // Bob outputs every field as a frame, so the player runs at twice the stream's rate
+        let (native_fps, total_frames) = if options.deinterlace == Deinterlace::Bob {
+            (native_fps * 2.0, total_frames * 2)
+        } else {
+            (native_fps, total_frames)
+        };
+        
+        let start = options.start.unwrap_or(Duration::ZERO);
+        if let Some(end) = options.end {
+            if end <= start {
+                anyhow::bail!("End time {:.3}s must be after start time {:.3}s", end.as_secs_f64(), start.as_secs_f64());
+            }
+        }
+        if !duration.is_zero() && start >= duration {
+            anyhow::bail!("Start time {:.3}s is past the end of the video ({:.3}s)",
+                start.as_secs_f64(), duration.as_secs_f64());
+        }
+        
+        let (width, height) = if rotation % 180 == 0 {
+            (decoder.width(), decoder.height())
         } else {
-            // Fallback: estimate from frame rate if duration is invalid
-            let fps = video_stream.avg_frame_rate();
-            if fps.numerator() > 0 && fps.denominator() > 0 {
-                let native_fps = fps.numerator() as f64 / fps.denominator() as f64;
-                Duration::from_secs_f64(total_frames as f64 / native_fps)
+            (decoder.height(), decoder.width())
+        };
+        let video_info = unsafe {
+            let raw = &*decoder.as_ptr();
+            let profile = ffmpeg_sys_next::avcodec_profile_name(raw.codec_id, raw.profile);
+            VideoInfo {
+                codec: codec_name.clone(),
+                profile: (!profile.is_null()).then(|| CStr::from_ptr(profile).to_string_lossy().into_owned()),
+                // Unknown levels are negative (FF_LEVEL_UNKNOWN)
+                level: (raw.level > 0).then_some(raw.level),
+                pixel_format: decoder.format().descriptor().map_or("unknown", |descriptor| descriptor.name()).to_string(),
+                bit_depth: pixel_bit_depth(decoder.format()),
+                color_space: decoder.color_space().name().map(str::to_string),
+                color_primaries: decoder.color_primaries().name().map(str::to_string),
+                color_transfer: decoder.color_transfer_characteristic().name().map(str::to_string),
+                color_range: decoder.color_range().name().map(str::to_string),
+                yuv_matrix: stream_colors.0.name().to_string(),
+                yuv_range: if stream_colors.1 { "full" } else { "limited" }.to_string(),
+                hdr: hdr.map(str::to_string),
+                tonemap: tonemap_filter.is_some().then(|| options.tonemap.name().to_string()),
+                container_format: input.format().name().to_string(),
+                container_bit_rate: u64::try_from(input.bit_rate()).ok().filter(|&bit_rate| bit_rate > 0),
+                width,
+                height,
+                rotation,
+                native_fps,
+                total_frames,
+                frame_count_exact,
+                duration_seconds: duration.as_secs_f64(),
+            }
+        };
+        
+        log::info!("Video loaded:");
+        log::info!("  Container: {}", video_info.container_format);
+        if let Some(profile) = &video_info.profile {
+            log::info!("  Profile: {}", profile);
+        }
+        log::info!("  Resolution: {}x{}", decoder.width(), decoder.height());
+        if rotation != 0 {
+            log::info!("  Rotation: {}°", rotation);
+        }
+        if let Some(crop) = options.crop {
+            log::info!("  Crop: {}x{} at {},{}", crop.width, crop.height, crop.x, crop.y);
+        }
+        if sample_aspect_ratio != 1.0 {
+            log::info!("  Sample aspect ratio: {:.4}{}", sample_aspect_ratio,
+                if square_pixels { " (resampled to square pixels)" } else { "" });
+        }
+        if (output_width, output_height) != (source_width, source_height) {
+            log::info!("  Output size: {}x{}", output_width, output_height);
+        }
+        log::info!("  Total frames: {}{}", total_frames,
+            if !frame_count_exact && total_frames > 0 { " (estimated)" } else { "" });
+        log::info!("  Duration: {:.2}s", duration.as_secs_f64());
+        log::info!("  Native FPS: {:.2}{}", native_fps, if is_vfr { " (variable frame rate)" } else { "" });
+        if !seekable {
+            log::info!("  Input is not seekable");
+        }
+        if options.start.is_some() || options.end.is_some() {
+            log::info!("  Segment: {:.3}s - {}", start.as_secs_f64(),
+                options.end.map_or("end".to_string(), |end| format!("{:.3}s", end.as_secs_f64())));
+        }
+        log::info!("  Scaler: {}", options.scaler.name());
+        if options.deinterlace != Deinterlace::None {
+            log::info!("  Deinterlacing: {}", options.deinterlace.name());
+        }
+        log::info!("  YUV conversion: {}, {} range{}", video_info.yuv_matrix, video_info.yuv_range,
+            if video_info.color_space.is_none() && options.assume_colorspace == ColorMatrix::Auto { " (matrix guessed from size)" } else { "" });
+        if video_info.bit_depth > 8 || hdr.is_some() {
+            log::info!("  Color: {}-bit {}{}", video_info.bit_depth,
+                video_info.color_transfer.as_deref().unwrap_or("unspecified transfer"),
+                match (hdr, &video_info.tonemap) {
+                    (Some(hdr), Some(tonemap)) => format!(" ({} HDR, tone mapped with {})", hdr, tonemap),
+                    (Some(hdr), None) => format!(" ({} HDR, not tone mapped)", hdr),
+                    (None, _) => String::new(),
+                });
+        }
+        if let Some(filter) = &options.video_filter {
+            log::info!("  Filters: {}", filter);
+        }
+        if options.yuv_direct {
+            if filtered_format == ffmpeg::format::Pixel::YUV420P
+                && rotation == 0
+                && crop.is_none()
+                && (output_width, output_height) == (source_width, source_height)
+            {
+                log::info!("  Passing YUV420P frames through without conversion");
+            } else {
+                log::info!("  Can't pass {:?} frames through directly, converting to {}",
+                    filtered_format, options.output_format.name());
+            }
+        }
+        
+        let mut player = VideoPlayer {
+            source: video_path.to_path_buf(),
+            is_network: is_url(video_path),
+            seekable,
+            network_timeout: options.network_timeout,
+            reconnect_attempts: 0,
+            format_context: input,
+            video_stream_index,
+            decoder,
+            hw_decoder,
+            scaler,
+            decoded: ffmpeg::frame::Video::empty(),
+            downloaded: ffmpeg::frame::Video::empty(),
+            converted: ffmpeg::frame::Video::empty(),
+            rotation_buffer: Vec::new(),
+            rotation,
+            deinterlace: options.deinterlace,
+            filters,
+            filter_graph: None,
+            filter_time: Duration::ZERO,
+            filtered_size: (filtered_width, filtered_height),
+            interlaced_seen: false,
+            sample_aspect_ratio,
+            square_pixels,
+            target_size,
+            crop,
+            requested_crop: options.crop,
+            output_format: options.output_format,
+            yuv_direct: options.yuv_direct,
+            scaler_algorithm: options.scaler,
+            assumed_colors: (options.assume_colorspace, options.assume_range),
+            stream_colors,
+            scaler_colors: None,
+            strict: options.strict,
+            hash_frames: options.hash_frames,
+            frame_hash: options.frame_hash,
+            decode_errors: 0,
+            corrupt_frames: 0,
+            packet_sizes: VecDeque::new(),
+            decoded_packet_bytes: 0,
+            dropped_frames: 0,
+            target_fps,
+            frame_duration,
+            last_frame_time: None,
+            realtime_clock: None,
+            playback_speed: 1.0,
+            pacing: options.pacing,
+            pacing_error: None,
+            time_base,
+            eof_sent: false,
+            seek_target: None,
+            codec_name,
+            video_info,
+            current_frame: 0,
+            position: Duration::ZERO,
+            total_frames,
+            frame_count_exact,
+            native_fps,
+            is_vfr,
+            duration,
+            start,
+            end: options.end,
+        };
+        
+        if !start.is_zero() {
+            if seekable {
+                player.seek_to_time(start)?;
+            } else {
+                // Pipes can't seek, decode forward and drop frames until the start instead
+                player.seek_target = Some(start);
+            }
+        }
+        
+        Ok(player)
+    }
+    
+    pub fn next_frame(&mut self) -> Result<Option<VideoFrame>> {
+        let mut frame = VideoFrame::default();
+        Ok(self.next_frame_into(&mut frame)?.then_some(frame))
+    }
+    
+    /// Iterate over the remaining frames. The iterator borrows the player mutably, so seeks
+    /// can't interleave with it; use `Frames::player` for anything else between frames.
+    pub fn frames(&mut self) -> Frames<'_> {
+        Frames { player: self, spare: None, finished: false }
+    }
+    
+    /// Decode the next frame into `frame`, reusing its buffers. Returns false at the end
+    /// of the video, leaving `frame` untouched.
+    pub fn next_frame_into(&mut self, frame: &mut VideoFrame) -> Result<bool> {
+        let Some(timestamp) = self.decode_next()? else {
+            return Ok(false);
+        };
+        let downloaded = self.download_frame()?;
+        self.convert_frame(timestamp, downloaded, frame)?;
+        Ok(true)
+    }
+    
+    /// Decode the frame due now on a wall-clock schedule of one frame per `target_interval`
+    /// (scaled by the playback speed), sleeping if it's early. When decoding has fallen behind,
+    /// frames whose slot has already passed are decoded but discarded without conversion, and
+    /// counted by `take_dropped_frames`. Returns None at the end of the video.
+    pub fn next_frame_realtime(&mut self, target_interval: Duration) -> Result<Option<VideoFrame>> {
+        let interval = target_interval.div_f32(self.playback_speed);
+        let (started, _, scheduled) = match self.realtime_clock {
+            Some(clock) if clock.1 == interval => clock,
+            // First frame, or the rate changed: start a new schedule from now
+            _ => (Instant::now(), interval, 0),
+        };
+        
+        let mut scheduled = scheduled;
+        loop {
+            let Some(timestamp) = self.decode_next()? else {
+                self.realtime_clock = None;
+                return Ok(None);
+            };
+            let due = started + interval.mul_f64(scheduled as f64);
+            scheduled += 1;
+            
+            // Keep the frame unless the next one is already due too
+            let now = Instant::now();
+            if now < due + interval {
+                if now < due {
+                    self.pacing.wait_until(due);
+                    self.pacing_error = Some(due.elapsed());
+                }
+                self.realtime_clock = Some((started, interval, scheduled));
+                
+                let mut frame = VideoFrame::default();
+                let downloaded = self.download_frame()?;
+                self.convert_frame(timestamp, downloaded, &mut frame)?;
+                return Ok(Some(frame));
+            }
+            
+            self.current_frame += 1;
+            self.position = timestamp;
+            self.dropped_frames += 1;
+            log::debug!("Dropped late frame {} at {:.3}s", self.current_frame, timestamp.as_secs_f64());
+        }
+    }
+    
+    /// Restart the `next_frame_realtime` schedule from the next call, e.g. after a pause,
+    /// so the time spent paused isn't treated as falling behind.
+    pub fn reset_realtime_clock(&mut self) {
+        self.realtime_clock = None;
+    }
+    
+    /// Decode the next frame into `self.decoded`, returning its timestamp, or None at the end.
+    fn decode_next(&mut self) -> Result<Option<Duration>> {
+        // Drain decoded frames first, feeding packets until one comes out
+        loop {
+            // Filtered frames come out of the filter graph, which the decoder feeds below
+            let pull_started = Instant::now();
+            let filtered = self.filter_graph.as_mut().is_some_and(|graph| graph.pull(&mut self.decoded));
+            if self.filter_graph.is_some() {
+                self.filter_time += pull_started.elapsed();
+            }
+            let received = filtered || match self.decoder.receive_frame(&mut self.decoded) {
+                Ok(()) => true,
+                // Needs more packets
+                Err(ffmpeg::Error::Other { errno: ffmpeg::error::EAGAIN }) => false,
+                // Drained after EOF, which the filters have to be told about to give up their last frames
+                Err(ffmpeg::Error::Eof) => match self.filter_graph.as_mut().filter(|graph| !graph.flushed) {
+                    Some(graph) => {
+                        graph.flush()?;
+                        continue;
+                    }
+                    None => false,
+                },
+                Err(e) if self.strict || is_fatal(&e) => return Err(e).context("Failed to decode frame"),
+                Err(e) => {
+                    log::warn!("Skipping frame the decoder failed on: {}", e);
+                    self.corrupt_frames += 1;
+                    continue;
+                }
+            };
+            if received {
+                if !filtered {
+                    // Damaged frames still come out with the missing parts concealed, count but show them
+                    if self.decoded.is_corrupt() {
+                        if self.strict {
+                            anyhow::bail!("Decoder output a corrupt frame");
+                        }
+                        self.corrupt_frames += 1;
+                    }
+                    self.decoded_packet_bytes = self.packet_sizes.pop_front().unwrap_or(0);
+                    if self.filter_decoded()? {
+                        continue;
+                    }
+                }
+                let time_base = match &self.filter_graph {
+                    Some(graph) if filtered => graph.time_base,
+                    _ => self.time_base,
+                };
+                let timestamp = self.frame_timestamp(&self.decoded, time_base);
+                
+                // After a seek we land on the preceding keyframe; decode forward to the target
+                if let Some(target) = self.seek_target {
+                    if timestamp < target {
+                        continue;
+                    }
+                    self.seek_target = None;
+                    self.current_frame = (timestamp.as_secs_f64() * self.get_native_fps()).round() as u64;
+                }
+                
+                // Past the requested segment, stop reading
+                if self.end.is_some_and(|end| timestamp >= end) {
+                    self.eof_sent = true;
+                    return Ok(None);
+                }
+                
+                return Ok(Some(timestamp));
+            }
+            
+            if self.eof_sent {
+                return Ok(None);
+            }
+            
+            match self.read_video_packet() {
+                Some(packet) => {
+                    self.reconnect_attempts = 0;
+                    let packet_bytes = packet.size();
+                    if let Err(e) = self.decoder.send_packet(&packet) {
+                        if self.strict || is_fatal(&e) {
+                            return Err(e).context("Failed to decode packet");
+                        }
+                        // One corrupted packet shouldn't end playback, the decoder recovers at the next keyframe
+                        log::warn!("Skipping packet the decoder rejected: {}", e);
+                        self.decode_errors += 1;
+                    } else {
+                        // Packets the decoder swallows without output would otherwise pile up
+                        if self.packet_sizes.len() >= MAX_PENDING_PACKETS {
+                            self.packet_sizes.pop_front();
+                        }
+                        self.packet_sizes.push_back(packet_bytes);
+                    }
+                }
+                None if self.should_reconnect() => self.reconnect(),
+                None => {
+                    // End of stream - flush decoder
+                    self.decoder.send_eof()?;
+                    self.eof_sent = true;
+                }
+            }
+        }
+    }
+    
+    fn read_video_packet(&mut self) -> Option<ffmpeg::Packet> {
+        let mut packet = ffmpeg::Packet::empty();
+        loop {
+            match packet.read(&mut self.format_context) {
+                Ok(()) if packet.stream() == self.video_stream_index => return Some(packet),
+                Ok(()) => continue,
+                Err(ffmpeg::Error::Eof) => return None,
+                Err(e) if self.is_network => {
+                    // Treat as a dropped connection rather than retrying a dead socket forever
+                    log::warn!("Network read error: {}", e);
+                    return None;
+                }
+                Err(_) => continue,
+            }
+        }
+    }
+    
+    /// Live streams have no real end, so running out of packets means the connection dropped.
+    fn should_reconnect(&self) -> bool {
+        self.is_live() && self.reconnect_attempts < MAX_RECONNECT_ATTEMPTS
+    }
+    
+    fn reconnect(&mut self) {
+        self.reconnect_attempts += 1;
+        log::warn!("Stream interrupted, reconnecting (attempt {}/{})...",
+            self.reconnect_attempts, MAX_RECONNECT_ATTEMPTS);
+        std::thread::sleep(RECONNECT_DELAY);
+        
+        let input = match open_input(&self.source, self.network_timeout) {
+            Ok(input) => input,
+            Err(e) => {
+                log::warn!("Reconnect failed: {:#}", e);
+                return;
+            }
+        };
+        
+        let Some(stream) = input.streams().best(ffmpeg::media::Type::Video) else {
+            log::warn!("Reconnected stream has no video");
+            return;
+        };
+        self.video_stream_index = stream.index();
+        self.time_base = stream.time_base();
+        
+        self.format_context = input;
+        self.decoder.flush();
+        self.filter_graph = None;
+        log::info!("Reconnected to {:?}", self.source);
+    }
+    
+    /// Copy a frame decoded on the GPU back to system memory so swscale can read it.
+    /// Returns whether `downloaded` (rather than `decoded`) holds the frame to convert.
+    fn download_frame(&mut self) -> Result<bool> {
+        let Some(hw_decoder) = &self.hw_decoder else {
+            return Ok(false);
+        };
+        if unsafe { (*self.decoded.as_ptr()).format } != hw_decoder.pixel_format {
+            // Software fallback chosen by get_hw_format
+            return Ok(false);
+        }
+        
+        unsafe {
+            ffmpeg_sys_next::av_frame_unref(self.downloaded.as_mut_ptr());
+            let result = ffmpeg_sys_next::av_hwframe_transfer_data(self.downloaded.as_mut_ptr(), self.decoded.as_ptr(), 0);
+            if result < 0 {
+                return Err(ffmpeg::Error::from(result)).context("Failed to download hardware frame");
+            }
+            ffmpeg_sys_next::av_frame_copy_props(self.downloaded.as_mut_ptr(), self.decoded.as_ptr());
+        }
+        Ok(true)
+    }
+    
+    /// Filter chain for the frame in `decoded`: yadif when deinterlacing, then tone mapping and `--vf`.
+    /// None when the frame should be shown as decoded.
+    fn filter_description(&mut self) -> Option<String> {
+        let mut deinterlace = self.deinterlace.filter_options();
+        if self.deinterlace == Deinterlace::Auto && !self.interlaced_seen {
+            if self.decoded.is_interlaced() {
+                log::info!("Interlaced frames found, deinterlacing with yadif");
+                self.interlaced_seen = true;
             } else {
-                Duration::from_secs(1) // Fallback to 1 second if we can't determine
+                deinterlace = None;
             }
+        }
+        let filters: Vec<String> = deinterlace
+            .map(|options| format!("yadif={}", options))
+            .into_iter()
+            .chain(self.filters.clone())
+            .collect();
+        (!filters.is_empty()).then(|| filters.join(","))
+    }
+    
+    /// Pass the frame in `decoded` to the filter graph, setting it up on first use. Returns false
+    /// when the frame isn't filtered and should be shown as decoded.
+    fn filter_decoded(&mut self) -> Result<bool> {
+        let Some(description) = self.filter_description() else {
+            return Ok(false);
         };
         
-        log::info!("Video loaded:");
-        log::info!("  Resolution: {}x{}", decoder.width(), decoder.height());
-        log::info!("  Total frames: {}", total_frames);
-        log::info!("  Duration: {:.2}s", duration.as_secs_f64());
-        log::info!("  Native FPS: {:.2}", total_frames as f64 / duration.as_secs_f64());
+        let started = Instant::now();
+        // Filters run on the CPU, so hardware frames are downloaded first
+        let frame = if self.download_frame()? { &self.downloaded } else { &self.decoded };
+        let input = (frame.format(), frame.width(), frame.height());
+        if !matches!(&self.filter_graph, Some(graph) if graph.input == input && graph.description == description) {
+            log::debug!("Setting up filters '{}' for {}x{} {:?}", description, input.1, input.2, input.0);
+            self.filter_graph = Some(FilterGraph::for_frame(frame, self.time_base, &description)?);
+        }
+        if let Some(graph) = &mut self.filter_graph {
+            graph.push(frame)?;
+        }
+        self.filter_time += started.elapsed();
+        Ok(true)
+    }
+    
+    /// Matrix and full range flag `frame` is converted from YUV with, None when it isn't YUV.
+    /// `--assume-*` wins over the frame's tags, which win over the stream's.
+    fn frame_colors(&self, frame: &ffmpeg::frame::Video) -> Option<(ColorMatrix, bool)> {
+        let descriptor = frame.format().descriptor()?;
+        let is_rgb = unsafe { (*descriptor.as_ptr()).flags } & ffmpeg_sys_next::AV_PIX_FMT_FLAG_RGB as u64 != 0;
+        if is_rgb || descriptor.nb_components() < 3 {
+            return None;
+        }
         
-        Ok(VideoPlayer {
-            format_context: input,
-            video_stream_index,
-            decoder,
-            scaler,
-            target_fps,
-            frame_duration,
-            last_frame_time: None,
-            current_frame: 0,
-            total_frames,
-            duration,
-        })
+        let (assumed_matrix, assumed_range) = self.assumed_colors;
+        let matrix = match assumed_matrix {
+            ColorMatrix::Auto => ColorMatrix::from_space(frame.color_space()).unwrap_or(self.stream_colors.0),
+            matrix => matrix,
+        };
+        let full_range = match assumed_range {
+            YuvRange::Auto => is_jpeg_format(frame.format()) || match frame.color_range() {
+                ffmpeg::color::Range::JPEG => true,
+                ffmpeg::color::Range::MPEG => false,
+                ffmpeg::color::Range::Unspecified => self.stream_colors.1,
+            },
+            range => range == YuvRange::Full,
+        };
+        Some((matrix, full_range))
     }
     
-    pub fn next_frame(&mut self) -> Result<Option<VideoFrame>> {
-        let mut frame = ffmpeg::frame::Video::empty();
-        let mut rgb_frame = ffmpeg::frame::Video::empty();
+    fn frame_timestamp(&self, frame: &ffmpeg::frame::Video, time_base: ffmpeg::Rational) -> Duration {
+        match frame.timestamp() {
+            Some(pts) => {
+                let time_secs = pts as f64 * f64::from(time_base);
+                // Handle negative timestamps (can occur in some video formats)
+                if time_secs >= 0.0 {
+                    Duration::from_secs_f64(time_secs)
+                } else {
+                    Duration::from_secs_f64(self.current_frame as f64 / self.get_native_fps())
+                }
+            }
+            None => Duration::from_secs_f64(self.current_frame as f64 / self.get_native_fps()),
+        }
+    }
+    
+    fn convert_frame(&mut self, timestamp: Duration, downloaded: bool, out: &mut VideoFrame) -> Result<()> {
+        let started = Instant::now();
+        if let Some(crop) = self.crop {
+            crop.apply(if downloaded { &mut self.downloaded } else { &mut self.decoded })?;
+        }
+        let source = if downloaded { &self.downloaded } else { &self.decoded };
+        let colors = self.frame_colors(source);
+        
+        // Rotation and resizing both need swscale's help. So does cropping: copying whole strides
+        // from the offset planes would read past the end of the last row
+        let passthrough = self.yuv_direct
+            && source.format() == ffmpeg::format::Pixel::YUV420P
+            && self.rotation == 0
+            && self.crop.is_none()
+            && self.frame_size(source.width(), source.height()) == (source.width(), source.height());
         
-        // Read packets until we get a video frame
-        for (stream, packet) in self.format_context.packets() {
-            if stream.index() != self.video_stream_index {
-                continue;
+        if passthrough {
+            // Hand the decoder's planes over as they are, padding included. The decoder reuses
+            // its buffers, so this single copy per plane is the one we can't avoid
+            let format = OutputFormat::Yuv420p;
+            let planes = format.planes(source.width(), source.height());
+            let total = planes.clone().enumerate().map(|(plane, (_, rows))| source.stride(plane) * rows as usize).sum();
+            out.data.resize(total, 0);
+            out.strides.clear();
+            
+            let mut offset = 0;
+            for (plane, (_, rows)) in planes.enumerate() {
+                let stride = source.stride(plane);
+                let len = stride * rows as usize;
+                out.data[offset..offset + len].copy_from_slice(&source.data(plane)[..len]);
+                out.strides.push(stride);
+                offset += len;
             }
             
-            self.decoder.send_packet(&packet)?;
+            out.format = format;
+            out.width = source.width();
+            out.height = source.height();
+        } else {
+            // The decoder applies the stream's cropping side data, so the frame can be smaller
+            // (or change size mid-stream) compared to what the scaler was set up for
+            let input = *self.scaler.input();
+            let output = *self.scaler.output();
+            if input.format != source.format()
+                || input.width != source.width()
+                || input.height != source.height()
+                || output.format != self.output_format.pixel()
+            {
+                log::debug!("Frame geometry changed to {}x{} {:?}, rebuilding scaler",
+                    source.width(), source.height(), source.format());
+                let (output_width, output_height) = self.frame_size(source.width(), source.height());
+                self.scaler.cached(
+                    source.format(),
+                    source.width(),
+                    source.height(),
+                    self.output_format.pixel(),
+                    output_width,
+                    output_height,
+                    self.scaler_algorithm.flags(source.format()),
+                );
+                // The old output frame has the wrong size or format now
+                self.converted = ffmpeg::frame::Video::empty();
+                self.scaler_colors = None;
+            }
             
-            while self.decoder.receive_frame(&mut frame).is_ok() {
-                // Scale to RGB24
-                self.scaler.run(&frame, &mut rgb_frame)?;
-                
-                self.current_frame += 1;
-                
-                // Convert frame data with proper stride handling
-                let width = rgb_frame.width();
-                let height = rgb_frame.height();
-                let linesize = rgb_frame.stride(0);
-                let data_ptr = rgb_frame.data(0);
+            // Left alone, swscale assumes limited range BT.601 for everything
+            if let Some(colors) = colors.filter(|&colors| Some(colors) != self.scaler_colors) {
+                let rgb_output = matches!(self.output_format, OutputFormat::Rgb24 | OutputFormat::Rgba);
+                set_yuv_colors(&mut self.scaler, colors, rgb_output);
+                self.scaler_colors = Some(colors);
+            }
+            
+            // Convert to the output format
+            self.scaler.run(source, &mut self.converted)?;
+            
+            let format = self.output_format;
+            let width = self.converted.width();
+            let height = self.converted.height();
+            let planes = format.planes(width, height);
+            let total = format.frame_bytes(width, height);
+            
+            // Write straight into the caller's buffer, which only reallocates when the
+            // resolution grows; rotated frames go through a scratch buffer first
+            let target = if self.rotation == 0 { &mut out.data } else { &mut self.rotation_buffer };
+            target.resize(total, 0);
+            
+            // Convert frame data with proper stride handling. FFmpeg pads each linesize to its SIMD
+            // alignment, so any row length that isn't a multiple of it (odd or not) leaves padding
+            // that has to be skipped row by row, or the image comes out sheared
+            let mut offset = 0;
+            for (plane, (plane_width, plane_height)) in planes.enumerate() {
+                let row_bytes = plane_width as usize * format.bytes_per_pixel();
+                let rows = plane_height as usize;
+                let linesize = self.converted.stride(plane);
+                let plane_data = self.converted.data(plane);
                 
-                log::debug!("Frame {}: width={}, height={}, linesize={}, expected={}", 
-                    self.current_frame, width, height, linesize, width as usize * 3);
+                log::debug!("Frame {} plane {}: width={}, height={}, linesize={}, expected={}",
+                    self.current_frame + 1, plane, plane_width, plane_height, linesize, row_bytes);
                 
-                // If linesize equals width * 3, we can use the data directly
+                // If linesize equals the row length, we can copy the plane directly
                 // Otherwise, we need to copy row by row to remove padding
-                let data = if linesize == width as usize * 3 {
-                    log::debug!("Using direct copy (no padding)");
-                    data_ptr.to_vec()
+                let dst = &mut target[offset..offset + row_bytes * rows];
+                if linesize == row_bytes {
+                    dst.copy_from_slice(&plane_data[..row_bytes * rows]);
                 } else {
-                    log::debug!("Copying row by row (has padding)");
-                    let mut data = Vec::with_capacity(width as usize * height as usize * 3);
-                    for y in 0..height as usize {
+                    for (y, row) in dst.chunks_exact_mut(row_bytes).enumerate() {
                         let row_start = y * linesize;
-                        let row_end = row_start + (width as usize * 3);
-                        data.extend_from_slice(&data_ptr[row_start..row_end]);
-                    }
-                    data
-                };
-                
-                // Debug: Check if we have actual pixel data (not all zeros) - only with verbose logging
-                let non_zero_pixels = data.iter().take(100).filter(|&&b| b != 0).count();
-                log::debug!("Frame {} data sample: first 100 bytes have {} non-zero values", 
-                    self.current_frame, non_zero_pixels);
-                
-                let timestamp = if let Some(pts) = frame.timestamp() {
-                    let time_secs = pts as f64 * f64::from(stream.time_base());
-                    // Handle negative timestamps (can occur in some video formats)
-                    if time_secs >= 0.0 {
-                        Duration::from_secs_f64(time_secs)
-                    } else {
-                        Duration::from_secs_f64(self.current_frame as f64 / self.get_native_fps())
+                        row.copy_from_slice(&plane_data[row_start..row_start + row_bytes]);
                     }
-                } else {
-                    Duration::from_secs_f64(self.current_frame as f64 / self.get_native_fps())
-                };
-                
-                return Ok(Some(VideoFrame {
-                    data,
-                    width,
-                    height,
-                    timestamp,
-                    frame_number: self.current_frame,
-                }));
+                }
+                offset += row_bytes * rows;
             }
-        }
-        
-        // End of stream - flush decoder
-        self.decoder.send_eof()?;
-        while self.decoder.receive_frame(&mut frame).is_ok() {
-            self.scaler.run(&frame, &mut rgb_frame)?;
-            
-            self.current_frame += 1;
             
-            // Convert frame data with proper stride handling
-            let width = rgb_frame.width();
-            let height = rgb_frame.height();
-            let linesize = rgb_frame.stride(0);
-            let data_ptr = rgb_frame.data(0);
-            
-            // If linesize equals width * 3, we can use the data directly
-            // Otherwise, we need to copy row by row to remove padding
-            let data = if linesize == width as usize * 3 {
-                data_ptr.to_vec()
+            // Phone recordings store frames sideways and rely on the display matrix to turn them upright
+            let (width, height) = if self.rotation == 0 {
+                (width, height)
             } else {
-                let mut data = Vec::with_capacity(width as usize * height as usize * 3);
-                for y in 0..height as usize {
-                    let row_start = y * linesize;
-                    let row_end = row_start + (width as usize * 3);
-                    data.extend_from_slice(&data_ptr[row_start..row_end]);
-                }
-                data
+                rotate_frame(&self.rotation_buffer, &mut out.data, width, height, format, self.rotation)
             };
             
-            let timestamp = Duration::from_secs_f64(
-                self.current_frame as f64 / self.get_native_fps()
+            out.format = format;
+            out.width = width;
+            out.height = height;
+            out.strides.clear();
+            out.strides.extend(
+                format
+                    .planes(width, height)
+                    .map(|(plane_width, _)| plane_width as usize * format.bytes_per_pixel()),
             );
-            
-            return Ok(Some(VideoFrame {
-                data,
-                width,
-                height,
-                timestamp,
-                frame_number: self.current_frame,
-            }));
         }
         
-        Ok(None)
+        self.current_frame += 1;
+        self.position = timestamp;
+        
+        out.timestamp = timestamp;
+        out.frame_number = self.current_frame;
+        out.conversion_time = started.elapsed();
+        out.filter_time = std::mem::take(&mut self.filter_time);
+        out.passthrough = passthrough;
+        out.packet_bytes = self.decoded_packet_bytes;
+        out.frame_type = picture_type_char(self.decoded.kind());
+        out.is_keyframe = self.decoded.is_key();
+        out.ideal_interval = self.ideal_frame_interval();
+        // swscale keeps the source's matrix and range for YUV output
+        out.yuv_colors = colors.filter(|_| out.format == OutputFormat::Yuv420p);
+        out.content_hash = self.hash_frames.then(|| out.compute_content_hash());
+        let hashing_started = Instant::now();
+        out.digest = out.compute_digest(self.frame_hash);
+        out.hash_time = hashing_started.elapsed();
+        
+        // Debug: Check if we have actual pixel data (not all zeros) - only with verbose logging
+        let non_zero_pixels = out.data.iter().take(100).filter(|&&b| b != 0).count();
+        log::debug!("Frame {} data sample: first 100 bytes have {} non-zero values", 
+            self.current_frame, non_zero_pixels);
+        
+        Ok(())
+    }
+    
+    /// Pace playback at `fps` frames per second (0 = as fast as possible).
+    pub fn set_target_fps(&mut self, fps: u32) {
+        self.target_fps = fps;
+        self.frame_duration = (fps > 0).then(|| Duration::from_nanos(1_000_000_000 / fps as u64));
+        // Pacing sleeps relative to the previous frame rather than a fixed schedule,
+        // so a lower rate can't leave a backlog of frames to catch up on
+    }
+    
+    pub fn get_target_fps(&self) -> u32 {
+        self.target_fps
+    }
+    
+    /// Interval frames would ideally be presented at: the target FPS if set, otherwise the
+    /// native frame rate, scaled by the playback speed.
+    pub fn ideal_frame_interval(&self) -> Duration {
+        let fps = if self.target_fps > 0 { self.target_fps as f64 } else { self.get_native_fps() };
+        Duration::from_secs_f64(1.0 / fps).div_f32(self.playback_speed)
     }
     
     pub fn maintain_target_fps(&mut self) {
         if let Some(frame_duration) = self.frame_duration {
+            let frame_duration = frame_duration.div_f32(self.playback_speed);
             if let Some(last_time) = self.last_frame_time {
-                let elapsed = last_time.elapsed();
-                if elapsed < frame_duration {
-                    std::thread::sleep(frame_duration - elapsed);
+                let deadline = last_time + frame_duration;
+                if Instant::now() < deadline {
+                    self.pacing.wait_until(deadline);
+                    self.pacing_error = Some(deadline.elapsed());
                 }
             }
             self.last_frame_time = Some(Instant::now());
         }
     }
     
+    /// Scale frame pacing, e.g. 2.0 plays twice as fast. Clamped to 0.25x - 4x.
+    pub fn set_playback_speed(&mut self, speed: f32) {
+        let clamped = speed.clamp(MIN_PLAYBACK_SPEED, MAX_PLAYBACK_SPEED);
+        if clamped != speed {
+            log::warn!("Playback speed {} out of range, using {}", speed, clamped);
+        }
+        self.playback_speed = clamped;
+    }
+    
+    pub fn get_playback_speed(&self) -> f32 {
+        self.playback_speed
+    }
+    
+    /// Number of the last returned frame, counted from the start of the played segment.
     pub fn get_current_frame(&self) -> u64 {
-        self.current_frame
+        self.current_frame.saturating_sub(self.start_frame())
     }
     
+    /// Index of the first frame of the played segment.
+    fn start_frame(&self) -> u64 {
+        (self.start.as_secs_f64() * self.get_native_fps()).round() as u64
+    }
+    
+    /// Timestamp of the most recently decoded frame.
+    pub fn get_position(&self) -> Duration {
+        self.position
+    }
+    
+    /// "position / end" for display, e.g. `00:01:23.4 / 00:45:00.0`. The position is the last decoded
+    /// frame's timestamp, and the end is "—" when the duration is unknown (pipes, live streams).
+    pub fn format_position(&self) -> String {
+        let end = if self.has_duration() { format_timestamp(self.get_segment().1) } else { "—".to_string() };
+        format!("{} / {}", format_timestamp(self.position), end)
+    }
+    
+    /// Frames in the played segment (the whole video unless --start/--end were given).
     pub fn get_total_frames(&self) -> u64 {
-        self.total_frames
+        match self.end {
+            Some(end) => {
+                let end_frame = (end.as_secs_f64() * self.get_native_fps()).round() as u64;
+                let end_frame = if self.total_frames > 0 { end_frame.min(self.total_frames) } else { end_frame };
+                end_frame.saturating_sub(self.start_frame())
+            }
+            None => self.total_frames.saturating_sub(self.start_frame()),
+        }
+    }
+    
+    /// Whether `get_total_frames` comes from the container (or --probe) rather than an estimate.
+    pub fn is_frame_count_exact(&self) -> bool {
+        self.frame_count_exact
     }
     
     pub fn get_duration(&self) -> Duration {
         self.duration
     }
     
+    /// Playback progress in [0, 1], or 0 when neither duration nor frame count is known.
+    /// Timestamps are preferred since the frame count may only be an estimate, and
+    /// frame counting drifts from real time with variable frame rate video.
     pub fn get_progress(&self) -> f64 {
-        if self.total_frames == 0 {
-            0.0
+        let (start, end) = self.get_segment();
+        if end > start {
+            (self.position.saturating_sub(start).as_secs_f64() / (end - start).as_secs_f64()).min(1.0)
+        } else if self.get_total_frames() > 0 {
+            (self.get_current_frame() as f64 / self.get_total_frames() as f64).min(1.0)
         } else {
-            self.current_frame as f64 / self.total_frames as f64
+            0.0
         }
     }
     
+    /// Start and end of the played segment. The end is the duration unless --end was given,
+    /// so it is zero for live streams.
+    pub fn get_segment(&self) -> (Duration, Duration) {
+        let end = match self.end {
+            Some(end) if self.has_duration() => end.min(self.duration),
+            Some(end) => end,
+            None => self.duration,
+        };
+        (self.start, end)
+    }
+    
+    /// False for pipes and live streams, where seek_to_time/seek_to_frame/restart fail.
+    pub fn is_seekable(&self) -> bool {
+        self.seekable
+    }
+    
+    /// False for inputs that don't report a duration, such as live streams.
+    pub fn has_duration(&self) -> bool {
+        !self.duration.is_zero()
+    }
+    
+    /// Network input without a known end, e.g. an RTSP camera.
+    pub fn is_live(&self) -> bool {
+        self.is_network && !self.has_duration()
+    }
+    
+    /// Width of the source video after `--vf` and rotation, before any scaling (see `get_output_size`).
     pub fn get_width(&self) -> u32 {
-        self.decoder.width()
+        let (width, height) = self.filtered_size;
+        if self.rotation % 180 == 0 { width } else { height }
     }
     
+    /// Height of the source video after `--vf` and rotation, before any scaling (see `get_output_size`).
     pub fn get_height(&self) -> u32 {
-        self.decoder.height()
+        let (width, height) = self.filtered_size;
+        if self.rotation % 180 == 0 { height } else { width }
+    }
+    
+    /// Width and height of the returned frames, i.e. after scaling and rotation.
+    pub fn get_output_size(&self) -> (u32, u32) {
+        let (width, height) = self.output_size();
+        if self.rotation % 180 == 0 { (width, height) } else { (height, width) }
+    }
+    
+    /// Size of scaled frames before rotation.
+    fn output_size(&self) -> (u32, u32) {
+        match self.crop {
+            Some(crop) => self.frame_size(crop.width, crop.height),
+            None => self.frame_size(self.filtered_size.0, self.filtered_size.1),
+        }
+    }
+    
+    /// Size a decoded frame of `width`x`height` is scaled to, before rotation.
+    fn frame_size(&self, width: u32, height: u32) -> (u32, u32) {
+        scaled_size(width, height, self.sample_aspect_ratio, self.square_pixels, self.target_size)
+    }
+    
+    /// Width / height the frames should be shown at, accounting for non-square
+    /// sample aspect ratios (e.g. anamorphic DVD video) and rotation.
+    pub fn get_display_aspect_ratio(&self) -> f64 {
+        let (width, height) = self.output_size();
+        let sample_aspect_ratio = if self.square_pixels { 1.0 } else { self.sample_aspect_ratio };
+        let ratio = width as f64 * sample_aspect_ratio / height as f64;
+        if self.rotation % 180 == 0 { ratio } else { 1.0 / ratio }
+    }
+    
+    /// Change the pixel layout of subsequently returned frames.
+    pub fn set_output_format(&mut self, format: OutputFormat) {
+        self.output_format = format;
+    }
+    
+    pub fn get_output_format(&self) -> OutputFormat {
+        self.output_format
+    }
+    
+    /// Clockwise rotation applied to decoded frames (0, 90, 180 or 270 degrees).
+    pub fn get_rotation(&self) -> u32 {
+        self.rotation
+    }
+    
+    /// Region from `--crop`, in the upright frame's coordinates as it was given.
+    pub fn get_crop(&self) -> Option<Crop> {
+        self.requested_crop
+    }
+    
+    pub fn get_codec_name(&self) -> &str {
+        &self.codec_name
+    }
+    
+    /// Codec, container, size, frame rate and length of the video, as found when opening it.
+    pub fn info(&self) -> &VideoInfo {
+        &self.video_info
+    }
+    
+    /// File, URL or `-` the video was opened from.
+    pub fn get_source(&self) -> &Path {
+        &self.source
+    }
+    
+    /// Number of packets skipped because the decoder rejected them, since the last call.
+    pub fn take_decode_errors(&mut self) -> u64 {
+        std::mem::take(&mut self.decode_errors)
+    }
+    
+    /// Number of frames the decoder failed on or flagged as damaged, since the last call.
+    pub fn take_corrupt_frames(&mut self) -> u64 {
+        std::mem::take(&mut self.corrupt_frames)
+    }
+    
+    /// How far past its deadline the last paced wait returned, if the player has waited since the last call.
+    pub fn take_pacing_error(&mut self) -> Option<Duration> {
+        self.pacing_error.take()
+    }
+    
+    /// Number of late frames `next_frame_realtime` skipped, since the last call.
+    pub fn take_dropped_frames(&mut self) -> u64 {
+        std::mem::take(&mut self.dropped_frames)
+    }
+    
+    /// Name of the hardware device decoding the video (e.g. "vaapi"), None for software decoding.
+    pub fn get_hwaccel_name(&self) -> Option<&str> {
+        self.hw_decoder.as_ref().map(|hw_decoder| hw_decoder.name.as_str())
+    }
+    
+    /// Whether the frame rate varies, making `get_native_fps` only an average.
+    pub fn is_vfr(&self) -> bool {
+        self.is_vfr
     }
     
     pub fn get_native_fps(&self) -> f64 {
-        if self.duration.as_secs_f64() > 0.0 {
+        if self.native_fps > 0.0 {
+            self.native_fps
+        } else if self.total_frames > 0 && self.has_duration() {
             self.total_frames as f64 / self.duration.as_secs_f64()
         } else {
             30.0 // Default fallback
         }
     }
     
-    pub fn seek_to_frame(&mut self, _frame_number: u64) -> Result<()> {
-        // Basic seek implementation - more advanced seeking would require
-        // using ffmpeg's seek_frame functionality
-        log::warn!("Seeking not fully implemented yet");
+    /// Rewind to the start of the video, or of the --start/--end segment.
+    pub fn restart(&mut self) -> Result<()> {
+        self.seek_to_time(self.start)
+    }
+    
+    /// Seek to a 0-based frame index, counted from the start of the played segment.
+    pub fn seek_to_frame(&mut self, frame_number: u64) -> Result<()> {
+        let time_secs = frame_number as f64 / self.get_native_fps();
+        self.seek_to_time(self.start + Duration::from_secs_f64(time_secs))
+    }
+    
+    /// Seek so that the next decoded frame is the first one at or after `position`.
+    pub fn seek_to_time(&mut self, position: Duration) -> Result<()> {
+        self.seek(position, true)
+    }
+    
+    /// Seek to the keyframe at or before `position`, which becomes the next frame. Cheaper than
+    /// `seek_to_time` when any nearby frame will do, e.g. for previews; frame numbers are estimates until the next seek.
+    pub fn seek_to_keyframe(&mut self, position: Duration) -> Result<()> {
+        self.seek(position, false)
+    }
+    
+    /// Seek to the keyframe before `position`, then with `exact` decode forward to it.
+    fn seek(&mut self, position: Duration, exact: bool) -> Result<()> {
+        if !self.seekable {
+            anyhow::bail!("Input is not seekable");
+        }
+        
+        let position = if self.has_duration() { position.min(self.duration) } else { position };
+        let position = position.max(self.start);
+        
+        // Seek to the keyframe before the target (in AV_TIME_BASE units, i.e. microseconds),
+        // next_frame decodes forward from there
+        let timestamp = position.as_micros() as i64;
+        if self.format_context.seek(timestamp, ..timestamp).is_err() {
+            // No keyframe before the target (e.g. streams starting after zero), take the nearest one
+            self.format_context
+                .seek(timestamp, ..)
+                .context("Failed to seek")?;
+        }
+        self.decoder.flush();
+        self.packet_sizes.clear();
+        // Filters like yadif hold on to frames from before the seek, start them over
+        self.filter_graph = None;
+        self.filter_time = Duration::ZERO;
+        
+        self.eof_sent = false;
+        self.seek_target = exact.then_some(position);
+        self.position = position;
+        self.current_frame = (position.as_secs_f64() * self.get_native_fps()).round() as u64;
+        self.last_frame_time = None;
+        self.realtime_clock = None;
+        
+        log::debug!("Seeked to {:.3}s", position.as_secs_f64());
         Ok(())
     }
+}
+
+/// Iterator over a `VideoPlayer`'s frames, from `VideoPlayer::frames`. Ends for good at the
+/// end of the video or after yielding the first error.
+pub struct Frames<'a> {
+    player: &'a mut VideoPlayer,
+    spare: Option<VideoFrame>, // Buffers handed back by recycle for the next decode
+    finished: bool,
+}
+
+impl Frames<'_> {
+    /// The player being iterated, e.g. to collect decode errors between frames.
+    pub fn player(&mut self) -> &mut VideoPlayer {
+        self.player
+    }
+    
+    /// Hand back a frame that's no longer needed, so the next one reuses its buffers.
+    pub fn recycle(&mut self, frame: VideoFrame) {
+        self.spare = Some(frame);
+    }
+}
+
+impl Iterator for Frames<'_> {
+    type Item = Result<VideoFrame>;
+    
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        
+        let mut frame = self.spare.take().unwrap_or_default();
+        match self.player.next_frame_into(&mut frame) {
+            Ok(true) => Some(Ok(frame)),
+            Ok(false) => {
+                self.finished = true;
+                self.spare = Some(frame);
+                None
+            }
+            Err(e) => {
+                self.finished = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+impl std::iter::FusedIterator for Frames<'_> {}
+
+/// `for frame in &mut player` is `player.frames()`.
+impl<'a> IntoIterator for &'a mut VideoPlayer {
+    type Item = Result<VideoFrame>;
+    type IntoIter = Frames<'a>;
+    
+    fn into_iter(self) -> Frames<'a> {
+        self.frames()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_clips::{self, Clip};
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::cell::Cell;
+    
+    /// Counts allocations per thread, so tests running in parallel don't see each other's.
+    /// FFmpeg's own buffers come from av_malloc and aren't counted.
+    struct CountingAllocator;
+    
+    thread_local! {
+        static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+    }
+    
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+            System.alloc(layout)
+        }
+        
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout)
+        }
+        
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+            System.realloc(ptr, layout, new_size)
+        }
+    }
+    
+    #[global_allocator]
+    static ALLOCATOR: CountingAllocator = CountingAllocator;
+    
+    /// Luma of the `test_clips::gradient` pattern at (`x`, `y`) in frame `index`.
+    fn gradient_luma(x: usize, y: usize, index: u32) -> u8 {
+        (x as u32 + y as u32 * 2 + index) as u8
+    }
+    
+    /// A lossless clip with odd dimensions, so rows have padding and chroma rounds up.
+    fn odd_clip(dir: &Path) -> PathBuf {
+        test_clips::write_clip(dir, "odd", &Clip { width: 33, height: 17, codec: "ffv1", extension: "mkv", ..Clip::default() })
+    }
+    
+    fn yuv_options() -> PlayerOptions {
+        PlayerOptions { output_format: OutputFormat::Yuv420p, ..PlayerOptions::default() }
+    }
+    
+    #[test]
+    fn frame_bytes_rounds_chroma_up() {
+        assert_eq!(OutputFormat::Rgb24.frame_bytes(33, 17), 33 * 17 * 3);
+        assert_eq!(OutputFormat::Rgba.frame_bytes(33, 17), 33 * 17 * 4);
+        assert_eq!(OutputFormat::Gray8.frame_bytes(33, 17), 33 * 17);
+        assert_eq!(OutputFormat::Yuv420p.frame_bytes(33, 17), 33 * 17 + 2 * 17 * 9);
+    }
+    
+    #[test]
+    fn frame_size_limit_uses_output_size_and_format() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = test_clips::write_clip(dir.path(), "limit", &Clip::default());
+        // Exactly one 64x48 RGB24 frame
+        let open = |options: PlayerOptions| {
+            VideoPlayer::new(&path, &PlayerOptions { max_frame_bytes: Some(64 * 48 * 3), ..options })
+        };
+        let rgba = || PlayerOptions { output_format: OutputFormat::Rgba, ..PlayerOptions::default() };
+        
+        assert!(open(PlayerOptions::default()).is_ok());
+        assert!(open(rgba()).is_err());
+        assert!(open(PlayerOptions { scale_factor: Some(0.5), ..rgba() }).is_ok());
+        assert!(open(PlayerOptions { crop: Some(Crop { x: 0, y: 0, width: 32, height: 48 }), ..rgba() }).is_ok());
+        assert!(open(PlayerOptions { output_width: Some(128), ..PlayerOptions::default() }).is_err());
+    }
+    
+    #[test]
+    fn odd_sizes_are_copied_without_shearing() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = odd_clip(dir.path());
+        
+        let mut player = VideoPlayer::new(&path, &yuv_options()).unwrap();
+        for index in 0..3 {
+            let frame = player.next_frame().unwrap().unwrap();
+            assert_eq!((frame.width, frame.height), (33, 17));
+            assert_eq!(frame.data.len(), OutputFormat::Yuv420p.frame_bytes(33, 17));
+            let [(luma, stride), ..] = frame.yuv_planes().unwrap();
+            for y in 0..17 {
+                for x in 0..33 {
+                    assert_eq!(luma[y * stride + x], gradient_luma(x, y, index), "pixel {},{} of frame {}", x, y, index);
+                }
+            }
+        }
+        
+        let frame = VideoPlayer::new(&path, &PlayerOptions::default()).unwrap().next_frame().unwrap().unwrap();
+        assert_eq!(frame.data.len(), 33 * 17 * 3);
+        assert_eq!(frame.strides, [33 * 3]);
+    }
+    
+    #[test]
+    fn odd_crops_are_copied_without_shearing() {
+        let dir = tempfile::tempdir().unwrap();
+        let crop = Crop { x: 4, y: 2, width: 21, height: 13 };
+        let mut player = VideoPlayer::new(&odd_clip(dir.path()), &PlayerOptions { crop: Some(crop), ..yuv_options() }).unwrap();
+        
+        let frame = player.next_frame().unwrap().unwrap();
+        assert_eq!((frame.width, frame.height), (21, 13));
+        assert_eq!(frame.data.len(), OutputFormat::Yuv420p.frame_bytes(21, 13));
+        let [(luma, stride), ..] = frame.yuv_planes().unwrap();
+        for y in 0..13 {
+            for x in 0..21 {
+                assert_eq!(luma[y * stride + x], gradient_luma(x + 4, y + 2, 0), "pixel {},{}", x, y);
+            }
+        }
+    }
+    
+    #[test]
+    fn next_frame_into_does_not_allocate_after_the_first_frame() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = test_clips::write_clip(dir.path(), "reuse", &Clip { frames: 20, ..Clip::default() });
+        let mut player = VideoPlayer::new(&path, &PlayerOptions::default()).unwrap();
+        let mut frame = VideoFrame::default();
+        
+        // The first frame sizes the buffers
+        assert!(player.next_frame_into(&mut frame).unwrap());
+        let before = ALLOCATIONS.with(Cell::get);
+        let mut decoded = 1;
+        while player.next_frame_into(&mut frame).unwrap() {
+            decoded += 1;
+        }
+        assert_eq!(ALLOCATIONS.with(Cell::get) - before, 0, "allocated while decoding frames 2 to {}", decoded);
+        assert_eq!(decoded, 20);
+    }
+    
+    #[test]
+    fn constant_rate_timestamps_are_not_vfr() {
+        // 30 FPS in a 1/1000 time base, rounded to 33 or 34 ms
+        let mut rounded: Vec<i64> = (0..300).map(|frame| (frame as f64 * 1000.0 / 30.0).round() as i64).collect();
+        assert!(!is_variable_rate(&mut rounded));
+        
+        // 29.97 FPS in 90 kHz, in B-frame decode order
+        let mut reordered: Vec<i64> = (0..100i64).map(|frame| frame * 3003).collect();
+        for gop in reordered.chunks_mut(3) {
+            gop.rotate_left(1);
+        }
+        assert!(!is_variable_rate(&mut reordered));
+        
+        assert!(!is_variable_rate(&mut vec![]));
+        assert!(!is_variable_rate(&mut vec![0, 1000]));
+    }
+    
+    #[test]
+    fn uneven_timestamps_are_vfr() {
+        // A screen recording: 60 FPS while things move, a frame a second when idle
+        let mut screen = vec![0, 16, 33, 50, 66, 1066, 2066, 2083, 2100];
+        assert!(is_variable_rate(&mut screen));
+        
+        // A phone dropping from 30 to 24 FPS in low light
+        let mut phone: Vec<i64> = (0..60).map(|frame| frame * 3000).chain((0..60).map(|frame| 180_000 + frame * 3750)).collect();
+        assert!(is_variable_rate(&mut phone));
+    }
+    
+    /// One second at 30 FPS in Matroska, which stores no frame count.
+    fn mkv_clip(dir: &Path) -> PathBuf {
+        test_clips::write_clip(dir, "count", &Clip { frames: 30, extension: "mkv", ..Clip::default() })
+    }
+    
+    #[test]
+    fn frame_count_is_estimated_when_the_container_has_none() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut player = VideoPlayer::new(&mkv_clip(dir.path()), &PlayerOptions::default()).unwrap();
+        
+        // From the duration and frame rate, which may lose the last frame's duration
+        assert!(!player.is_frame_count_exact());
+        assert!((29..=30).contains(&player.get_total_frames()), "estimated {} frames", player.get_total_frames());
+        assert_eq!(player.get_progress(), 0.0);
+        
+        for _ in 0..15 {
+            player.next_frame().unwrap().unwrap();
+        }
+        let halfway = player.get_progress();
+        assert!((0.4..=0.6).contains(&halfway), "progress {} after half the frames", halfway);
+        while player.next_frame().unwrap().is_some() {}
+        assert!(player.get_progress() > 0.9);
+    }
+    
+    #[test]
+    fn probe_counts_frames_exactly() {
+        let dir = tempfile::tempdir().unwrap();
+        let options = PlayerOptions { probe: true, ..PlayerOptions::default() };
+        let mut player = VideoPlayer::new(&mkv_clip(dir.path()), &options).unwrap();
+        
+        assert!(player.is_frame_count_exact());
+        assert_eq!(player.get_total_frames(), 30);
+        assert!(!player.is_vfr());
+        // Counting rewinds, so playback still starts at the first frame
+        assert_eq!(player.next_frame().unwrap().unwrap().timestamp, Duration::ZERO);
+    }
+    
+    #[test]
+    fn display_matrix_rotation_turns_frames() {
+        let dir = tempfile::tempdir().unwrap();
+        // av_display_rotation_set takes counterclockwise angles, the player reports clockwise ones
+        for (angle, rotation) in [(-90.0, 90), (90.0, 270)] {
+            let clip = Clip { rotation: Some(angle), ..Clip::default() };
+            let path = test_clips::write_clip(dir.path(), &format!("rotated{}", rotation), &clip);
+            let mut player = VideoPlayer::new(&path, &PlayerOptions::default()).unwrap();
+            
+            assert_eq!(player.info().rotation, rotation);
+            assert_eq!((player.info().width, player.info().height), (48, 64));
+            let frame = player.next_frame().unwrap().unwrap();
+            assert_eq!((frame.width, frame.height), (48, 64));
+            
+            let ignored = VideoPlayer::new(&path, &PlayerOptions { ignore_rotation: true, ..PlayerOptions::default() }).unwrap();
+            assert_eq!(ignored.info().rotation, 0);
+        }
+    }
+    
+    /// Limited range YUV of 100% white, red, green and blue, and the RGB they stand for.
+    const BARS_601: [[u8; 3]; 4] = [[235, 128, 128], [81, 90, 240], [145, 54, 34], [41, 240, 110]];
+    const BARS_709: [[u8; 3]; 4] = [[235, 128, 128], [63, 102, 240], [173, 42, 26], [32, 240, 118]];
+    const BARS_RGB: [[u8; 3]; 4] = [[255, 255, 255], [255, 0, 0], [0, 255, 0], [0, 0, 255]];
+    
+    /// Four vertical bars across a 64x16 frame.
+    fn fill_bars(frame: &mut ffmpeg::frame::Video, bars: &[[u8; 3]; 4]) {
+        for plane in 0..3 {
+            let (width, height) = if plane == 0 { (64, 16) } else { (32, 8) };
+            let stride = frame.stride(plane);
+            let data = frame.data_mut(plane);
+            for y in 0..height {
+                for x in 0..width {
+                    data[y * stride + x] = bars[x * 4 / width][plane];
+                }
+            }
+        }
+    }
+    
+    fn bars_601(frame: &mut ffmpeg::frame::Video, _index: u32) {
+        fill_bars(frame, &BARS_601);
+    }
+    
+    fn bars_709(frame: &mut ffmpeg::frame::Video, _index: u32) {
+        fill_bars(frame, &BARS_709);
+    }
+    
+    #[test]
+    fn color_bars_convert_with_the_stream_matrix() {
+        use ffmpeg::color::{Range, Space};
+        
+        let dir = tempfile::tempdir().unwrap();
+        let cases: [(&str, fn(&mut ffmpeg::frame::Video, u32), Space, ColorMatrix); 2] = [
+            ("bars601", bars_601, Space::BT470BG, ColorMatrix::Bt601),
+            ("bars709", bars_709, Space::BT709, ColorMatrix::Bt709),
+        ];
+        for (name, fill, space, matrix) in cases {
+            let clip = Clip { width: 64, height: 16, frames: 1, codec: "ffv1", extension: "mkv", colorspace: space, range: Range::MPEG, fill, ..Clip::default() };
+            let path = test_clips::write_clip(dir.path(), name, &clip);
+            
+            // Converted by swscale, and by `rgb_data` from YUV output as screenshots are
+            let rgb = VideoPlayer::new(&path, &PlayerOptions::default()).unwrap().next_frame().unwrap().unwrap();
+            let yuv = VideoPlayer::new(&path, &yuv_options()).unwrap().next_frame().unwrap().unwrap();
+            assert_eq!(yuv.yuv_colors, Some((matrix, false)));
+            for frame in [&rgb, &yuv] {
+                let pixels = frame.rgb_data();
+                for (bar, expected) in BARS_RGB.iter().enumerate() {
+                    // The middle of the bar, away from chroma bleeding across its edges
+                    let offset = (8 * 64 + bar * 16 + 8) * 3;
+                    let actual = &pixels[offset..offset + 3];
+                    assert!(
+                        actual.iter().zip(expected).all(|(&actual, &expected)| actual.abs_diff(expected) <= 3),
+                        "{} bar {} came out {:?} from {:?}", name, bar, actual, frame.format
+                    );
+                }
+            }
+        }
+    }
+    
+    #[test]
+    fn yuv_to_rgb_uses_the_frame_matrix() {
+        // A 2x2 frame of BT.709 red
+        let [y, u, v] = BARS_709[1];
+        let mut frame = VideoFrame {
+            data: vec![y, y, y, y, u, v],
+            format: OutputFormat::Yuv420p,
+            width: 2,
+            height: 2,
+            strides: vec![2, 1, 1],
+            yuv_colors: Some((ColorMatrix::Bt709, false)),
+            ..VideoFrame::default()
+        };
+        assert_eq!(frame.rgb_data()[..3], [255, 1, 0]);
+        
+        // Read as BT.601, red comes out noticeably dim
+        frame.yuv_colors = Some((ColorMatrix::Bt601, false));
+        assert!(frame.rgb_data()[0] < 240);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,61 @@
+use crate::{playlist::Playlist, video_player::{format_timecode, VideoPlayer}};
+use std::time::{Duration, Instant};
+
+/// Minimum time between title changes, so the window manager isn't sent one per frame
+const TITLE_REFRESH_INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlaybackState {
+    Playing,
+    Paused,
+    Finished,
+}
+
+/// Live window title shared by both GUIs, e.g. `clip.mp4 — 00:02:13 / 00:10:00 — 61.2 fps — sw [paused]`.
+#[derive(Default)]
+pub struct WindowTitle {
+    title: String,
+    updated: Option<Instant>,
+}
+
+impl WindowTitle {
+    /// The new title if it changed and the last one was set long enough ago, else None.
+    /// `position` is the shown frame's, which can be behind the player's when frames are decoded ahead.
+    pub fn update(&mut self, player: &VideoPlayer, playlist: &Playlist, position: Duration, fps: f64, state: PlaybackState) -> Option<&str> {
+        if self.updated.is_some_and(|updated| updated.elapsed() < TITLE_REFRESH_INTERVAL) {
+            return None;
+        }
+        
+        // Playlists show their position in the list, single videos just the file name
+        let name = playlist.title().unwrap_or_else(|| {
+            let source = player.get_source();
+            source.file_name().unwrap_or(source.as_os_str()).to_string_lossy().into_owned()
+        });
+        let total = if player.has_duration() { format_timecode(player.get_segment().1) } else { "—".to_string() };
+        let backend = match player.get_hwaccel_name() {
+            Some(device) => format!("hw ({})", device),
+            None => "sw".to_string(),
+        };
+        let state = match state {
+            PlaybackState::Playing => "",
+            PlaybackState::Paused => " [paused]",
+            PlaybackState::Finished => " [finished]",
+        };
+        let title = format!(
+            "{} — {} / {} — {:.1} fps — {}{}",
+            name,
+            format_timecode(position),
+            total,
+            fps,
+            backend,
+            state
+        );
+        
+        if title == self.title {
+            return None;
+        }
+        self.title = title;
+        self.updated = Some(Instant::now());
+        Some(&self.title)
+    }
+}
@@ -0,0 +1,45 @@
+//! Without a display the binary should still play and report, falling back to CLI mode.
+#![cfg(all(unix, not(target_os = "macos")))]
+
+#[path = "../src/test_clips.rs"]
+mod test_clips;
+
+use std::path::Path;
+use std::process::{Command, Output};
+
+fn run_without_display(video: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_metric-video-player"))
+        .arg("-i")
+        .arg(video)
+        .args(args)
+        .env_remove("DISPLAY")
+        .env_remove("WAYLAND_DISPLAY")
+        .env_remove("RUST_LOG")
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn falls_back_to_cli_mode_and_exports_metrics() {
+    let dir = tempfile::tempdir().unwrap();
+    let video = test_clips::write_clip(dir.path(), "clip", &test_clips::Clip::default());
+    let metrics = dir.path().join("metrics.json");
+    
+    let output = run_without_display(&video, &["--export-metrics", metrics.to_str().unwrap()]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{}", stderr);
+    assert!(stderr.contains("No display available, falling back to CLI mode"), "{}", stderr);
+    
+    let session: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&metrics).unwrap()).unwrap();
+    assert_eq!(session["total_frames"], 10);
+}
+
+#[test]
+fn explicit_gui_fails_without_a_display() {
+    let dir = tempfile::tempdir().unwrap();
+    let video = test_clips::write_clip(dir.path(), "clip", &test_clips::Clip::default());
+    
+    let output = run_without_display(&video, &["--gui", "true"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("no display is available"));
+}